@@ -1,20 +1,35 @@
 mod sdl_events;
 
+use log::{error, info, warn};
 use sdl2::{image::InitFlag, AudioSubsystem, Sdl, VideoSubsystem, video::FullscreenType};
-use std::{sync::atomic::Ordering, time::Duration};
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::Ordering,
+    time::Duration,
+};
 
 use crate::{
     entity::EventMessage,
     media::{
-        decoder::MediaDecoder,
+        bookmark::{self, next_bookmark, previous_bookmark, Bookmark},
+        chapter::{chapter_at, next_chapter_start, previous_chapter_start, Chapter},
+        clip::{ClipExporter, ClipFormat, ClipRequest},
+        decoder::{MediaDecoder, SeekMode},
         player::{MediaPlayer, Player},
+        sync::MediaDelay,
+        thumbnail::Thumbnailer,
+    },
+    settings,
+    sound::{
+        dsp::{DspSettings, EqPreset, BAND_COUNT, BAND_FREQUENCIES, MAX_BAND_GAIN},
+        loudness::{self, LoudnessCache, LoudnessScanner, NormalizationMode, ReplayGain},
+        output, Sounder,
     },
-    sound::Sounder,
-    ui::{components::{dialog::show_error, TControl}, start_window::StartWindow, video_window::VideoWindow},
+    ui::{components::{dialog::{show_error, show_info}, TControl}, start_window::StartWindow, theme, video_window::{osd::OsdMessage, VideoWindow}, THEME},
     util::error::{handle_result, safe_send, SuperError},
     {
-        AUDIO_PTS_MILLIS, AUDIO_SUMMARY, EVENT_CHANNEL, FR_STEP, MAX_VOLUME, VIDEO_SUMMARY, VOLUME,
-        VOLUME_STEP,
+        AB_LOOP, AUDIO_DELAY_MILLIS, AUDIO_PTS_MILLIS, AUDIO_SUMMARY, EVENT_CHANNEL, FR_STEP,
+        MAX_VOLUME, MEDIA_SUMMARY, MUTED, PLAYBACK_STATS, SETTINGS, VOLUME, VOLUME_STEP,
     },
 };
 
@@ -36,8 +51,19 @@ impl NtApp {
         let video_subsystem = sdl_context.video()?;
         let audio_subsystem = sdl_context.audio()?;
 
+        // The theme is taken by controls while they are created
+        let preset = SETTINGS.read().unwrap().theme;
+        *THEME.write().unwrap() = theme::load_or_default(preset);
+
         let start_wind = StartWindow::new(&video_subsystem)?;
 
+        // Restore the volume of last session
+        {
+            let r = SETTINGS.read().unwrap();
+            VOLUME.store(r.volume.clamp(0, MAX_VOLUME), Ordering::Release);
+            MUTED.store(r.muted, Ordering::Release);
+        }
+
         Ok(Self {
             sdl_context,
             audio_subsystem,
@@ -54,6 +80,17 @@ impl NtApp {
         let mut decoder: Option<MediaDecoder> = None;
         let mut player: Option<MediaPlayer> = None;
         let mut sounder: Option<Sounder> = None;
+        // The path of the media which is currently playing
+        let mut media_path: Option<PathBuf> = None;
+        // The files opened together, the next and previous buttons move between them
+        let mut play_list: Vec<PathBuf> = vec![];
+        // Results of loudness analysis, and the scanner which produces them
+        let mut loudness_cache = loudness::load_or_default();
+        let loudness_scanner = LoudnessScanner::new();
+        let mut clip_exporter = ClipExporter::new();
+        let thumbnailer = Thumbnailer::new();
+        // Bookmarks of all the media, they are saved once changed
+        let mut bookmarks = bookmark::load_or_default();
 
         loop {
             if sdl_eventer.handle_events(&mut self.start_window, &mut self.video_window)?
@@ -84,9 +121,34 @@ impl NtApp {
                             player.stop();
                         }
 
+                        // Contents of previous media should be removed before decoding,
+                        // since the cover is sent while the decoder is being initialized
+                        let wind = self.video_window.as_mut().unwrap();
+                        wind.reset_media();
+                        PLAYBACK_STATS.reset();
+
                         let mut md = MediaDecoder::new(path.to_str().unwrap())?;
                         md.seek_to(0);
 
+                        // Show the title of media, use file name if there's no title tag
+                        let file_name = path
+                            .file_name()
+                            .map(|n| n.to_string_lossy().to_string())
+                            .unwrap_or_default();
+                        let title = MEDIA_SUMMARY
+                            .read()
+                            .unwrap()
+                            .as_ref()
+                            .map_or(file_name.clone(), |s| s.display_title(&file_name));
+                        wind.set_media_title(&title);
+                        Self::show_osd(OsdMessage::info(title));
+
+                        // A-B markers belong to the previous media
+                        AB_LOOP.write().unwrap().clear();
+                        Self::restore_delay(&path);
+                        Self::show_bookmarks(wind, bookmarks.get(&path));
+                        media_path = Some(path);
+
                         let mut plr = MediaPlayer::new();
                         plr.start()?;
 
@@ -97,7 +159,19 @@ impl NtApp {
                         // that's why the sounder is initialized here after media decoder is initialized
                         let r = AUDIO_SUMMARY.read().unwrap();
                         if let Some(summary) = r.as_ref() {
-                            let sdr = Sounder::new(&self.audio_subsystem, summary);
+                            let mut sdr = {
+                                let r = SETTINGS.read().unwrap();
+                                let device = r.audio_device.clone();
+                                Sounder::new(&self.audio_subsystem, summary, device, &r.dsp)
+                            };
+                            if let Some(path) = media_path.as_ref() {
+                                Self::apply_normalization(
+                                    &mut sdr,
+                                    path,
+                                    &loudness_cache,
+                                    &loudness_scanner,
+                                );
+                            }
                             sounder = Some(sdr);
                         }
                     }
@@ -105,11 +179,17 @@ impl NtApp {
                         if let Some(player) = player.as_mut() {
                             player.pause();
                         }
+                        if let Some(wind) = self.video_window.as_mut() {
+                            wind.set_paused(true);
+                        }
                     }
                     EventMessage::Resume => {
                         if let Some(player) = player.as_mut() {
                             player.resume();
                         }
+                        if let Some(wind) = self.video_window.as_mut() {
+                            wind.set_paused(false);
+                        }
                     }
                     EventMessage::Stop => {
                         if let Some(decoder) = decoder.as_mut() {
@@ -121,18 +201,19 @@ impl NtApp {
                     }
                     EventMessage::Forward => {
                         if let (Some(player), Some(decoder)) = (player.as_mut(), decoder.as_mut()) {
-                            let r = VIDEO_SUMMARY.read().unwrap();
-                            let summary = r.as_ref().unwrap();
+                            let r = MEDIA_SUMMARY.read().unwrap();
+                            let duration_millis = r.as_ref().map_or(0, |s| s.duration_millis);
 
                             let pts = AUDIO_PTS_MILLIS.load(Ordering::Acquire);
                             let start = pts + FR_STEP;
 
-                            if start as u64 > summary.duration_millis {
+                            if duration_millis > 0 && start as u64 > duration_millis {
                                 decoder.stop();
                                 player.stop();
                             } else {
                                 player.seeking();
                                 decoder.seek_to(start);
+                                Self::show_osd(OsdMessage::seek(start, duration_millis));
                             }
                         }
                     }
@@ -144,6 +225,7 @@ impl NtApp {
                             let start = pts - FR_STEP - adjust_diff;
                             player.seeking();
                             decoder.seek_to(start);
+                            Self::show_seek_osd(start);
                         }
                     }
                     EventMessage::SeekTo(millis) => {
@@ -152,11 +234,170 @@ impl NtApp {
                             decoder.seek_to(millis);
                         }
                     }
+                    EventMessage::ScrubTo(millis) => {
+                        if let (Some(player), Some(decoder)) = (player.as_mut(), decoder.as_mut()) {
+                            player.seeking();
+                            decoder.seek(millis, SeekMode::Keyframe);
+                        }
+                    }
+                    EventMessage::NextMedia => {
+                        let index = play_list
+                            .iter()
+                            .position(|p| Some(p) == media_path.as_ref());
+                        match index.and_then(|i| play_list.get(i + 1)) {
+                            Some(path) => safe_send(sender.send(EventMessage::Play(path.clone()))),
+                            // A single file is navigated by its chapters
+                            None => safe_send(sender.send(EventMessage::NextChapter)),
+                        }
+                    }
+                    EventMessage::PreviousMedia => {
+                        let index = play_list
+                            .iter()
+                            .position(|p| Some(p) == media_path.as_ref());
+                        match index.and_then(|i| i.checked_sub(1)).map(|i| &play_list[i]) {
+                            Some(path) => safe_send(sender.send(EventMessage::Play(path.clone()))),
+                            None => safe_send(sender.send(EventMessage::PreviousChapter)),
+                        }
+                    }
+                    EventMessage::NextChapter => {
+                        if let (Some(player), Some(decoder)) = (player.as_mut(), decoder.as_mut()) {
+                            let pts = AUDIO_PTS_MILLIS.load(Ordering::Acquire);
+                            if let Some((start, title)) =
+                                Self::chapter_start(pts, next_chapter_start)
+                            {
+                                player.seeking();
+                                decoder.seek_to(start);
+                                Self::show_osd(OsdMessage::info(title));
+                            }
+                        }
+                    }
+                    EventMessage::PreviousChapter => {
+                        if let (Some(player), Some(decoder)) = (player.as_mut(), decoder.as_mut()) {
+                            let pts = AUDIO_PTS_MILLIS.load(Ordering::Acquire);
+                            if let Some((start, title)) =
+                                Self::chapter_start(pts, previous_chapter_start)
+                            {
+                                player.seeking();
+                                decoder.seek_to(start);
+                                Self::show_osd(OsdMessage::info(title));
+                            }
+                        }
+                    }
+                    EventMessage::AddBookmark(note) => {
+                        if let Some(path) = media_path.as_ref() {
+                            let pts = AUDIO_PTS_MILLIS.load(Ordering::Acquire);
+                            bookmarks.add(path, Bookmark::new(pts, note));
+                            handle_result(bookmark::save(&bookmarks));
+                            let time = bookmark::format_position(pts);
+                            Self::show_osd(OsdMessage::info(format!("Bookmark added: {}", time)));
+                            if let Some(wind) = self.video_window.as_mut() {
+                                Self::show_bookmarks(wind, bookmarks.get(path));
+                            }
+                        }
+                    }
+                    EventMessage::RemoveBookmark => {
+                        if let Some(path) = media_path.as_ref() {
+                            let pts = AUDIO_PTS_MILLIS.load(Ordering::Acquire);
+                            if let Some(removed) = bookmarks.remove_near(path, pts) {
+                                handle_result(bookmark::save(&bookmarks));
+                                let time = bookmark::format_position(removed.position);
+                                let text = format!("Bookmark removed: {}", time);
+                                Self::show_osd(OsdMessage::info(text));
+                                if let Some(wind) = self.video_window.as_mut() {
+                                    Self::show_bookmarks(wind, bookmarks.get(path));
+                                }
+                            }
+                        }
+                    }
+                    EventMessage::NextBookmark | EventMessage::PreviousBookmark => {
+                        if let (Some(player), Some(decoder), Some(path)) =
+                            (player.as_mut(), decoder.as_mut(), media_path.as_ref())
+                        {
+                            let pts = AUDIO_PTS_MILLIS.load(Ordering::Acquire);
+                            let found = if matches!(m, EventMessage::NextBookmark) {
+                                next_bookmark(bookmarks.get(path), pts)
+                            } else {
+                                previous_bookmark(bookmarks.get(path), pts)
+                            };
+                            if let Some(found) = found {
+                                player.seeking();
+                                decoder.seek_to(found.position);
+
+                                let time = bookmark::format_position(found.position);
+                                let text = match found.note.as_ref() {
+                                    Some(note) => format!("{}  {}", time, note),
+                                    None => time,
+                                };
+                                Self::show_osd(OsdMessage::info(text));
+                            }
+                        }
+                    }
+                    EventMessage::ListBookmarks => {
+                        if let Some(path) = media_path.as_ref() {
+                            let list: Vec<String> = bookmarks
+                                .get(path)
+                                .iter()
+                                .enumerate()
+                                .map(|(i, b)| {
+                                    let time = bookmark::format_position(b.position);
+                                    let note = b.note.as_deref().unwrap_or_default();
+                                    format!("{}. {}  {}", i + 1, time, note)
+                                })
+                                .collect();
+                            let message = if list.is_empty() {
+                                "No bookmarks yet, press K to add one".to_string()
+                            } else {
+                                list.join("\n")
+                            };
+                            show_info("Bookmarks", &message);
+                        }
+                    }
+                    EventMessage::ExportBookmarks(format) => {
+                        if let Some(path) = media_path.as_ref() {
+                            let dir = SETTINGS.read().unwrap().bookmark_dir.clone();
+                            let result = bookmark::export(path, bookmarks.get(path), &dir, format);
+                            if let Some(saved) = handle_result(result) {
+                                info!("bookmarks saved: {}", saved.display());
+                                let text = format!("Bookmarks saved: {}", saved.display());
+                                Self::show_osd(OsdMessage::info(text));
+                            }
+                        }
+                    }
                     EventMessage::FileOpened(path) => {
+                        play_list = vec![path.clone()];
                         safe_send(sender.send(EventMessage::Play(path)));
                     }
-                    EventMessage::DirOpened(_) => {
-                        todo!();
+                    EventMessage::DirOpened(paths) => {
+                        // The first file is played, and the whole list is analyzed in background
+                        if let Some(first) = paths.first() {
+                            safe_send(sender.send(EventMessage::Play(first.clone())));
+                        }
+                        play_list = paths.clone();
+                        safe_send(sender.send(EventMessage::ScanLoudness(paths)));
+                    }
+                    EventMessage::ShowOsd(msg) => {
+                        if let Some(wind) = self.video_window.as_mut() {
+                            wind.show_osd(msg);
+                        }
+                    }
+                    EventMessage::ToggleStats => {
+                        if let Some(wind) = self.video_window.as_mut() {
+                            wind.toggle_stats();
+                        }
+                    }
+                    EventMessage::NextTheme => {
+                        let preset = {
+                            let mut w = SETTINGS.write().unwrap();
+                            w.theme = theme::next_preset(w.theme);
+                            w.theme
+                        };
+                        let new_theme = theme::load_or_default(preset);
+                        // The controls read the theme while they are rebuilt
+                        *THEME.write().unwrap() = new_theme.clone();
+                        if let Some(wind) = self.video_window.as_mut() {
+                            handle_result(wind.apply_theme(&new_theme));
+                        }
+                        Self::show_osd(OsdMessage::info(format!("Theme: {}", new_theme.name)));
                     }
                     EventMessage::ShowError(msg) => {
                         show_error(msg.as_str());
@@ -167,12 +408,24 @@ impl NtApp {
                         }
                     }
                     EventMessage::RenderAudio(frame) => {
+                        if let Some(wind) = self.video_window.as_mut() {
+                            wind.update_audio_samples(
+                                &frame.data,
+                                frame.channels,
+                                frame.sample_rate,
+                            );
+                        }
                         if let Some(sounder) = sounder.as_mut() {
                             let result = sounder.play_sound(frame);
                             handle_result(result);
                         }
                     }
                     EventMessage::RenderSubtitle(_) => todo!(), // Render video and sound
+                    EventMessage::RenderCover(cover) => {
+                        if let Some(wind) = self.video_window.as_mut() {
+                            wind.update_cover(cover);
+                        }
+                    }
                     EventMessage::Resize((width, height)) => {
                         if let Some(wind) = self.video_window.as_mut() {
                             match player.as_mut() {
@@ -208,23 +461,292 @@ impl NtApp {
                         }
                     }
                     EventMessage::UpVolume => {
-                        let mut volume = VOLUME.load(Ordering::Acquire);
-                        if volume <= MAX_VOLUME {
-                            volume += VOLUME_STEP;
-                            VOLUME.store(volume, Ordering::Release);
-                        }
+                        let volume = VOLUME.load(Ordering::Acquire);
+                        Self::set_volume(volume + VOLUME_STEP);
                     }
                     EventMessage::DownVolume => {
-                        let mut volume = VOLUME.load(Ordering::Acquire);
-                        if volume >= VOLUME_STEP {
-                            volume -= VOLUME_STEP;
-                            VOLUME.store(volume, Ordering::Release);
+                        let volume = VOLUME.load(Ordering::Acquire);
+                        Self::set_volume(volume - VOLUME_STEP);
+                    }
+                    EventMessage::SetVolume(volume) => {
+                        Self::set_volume(volume);
+                    }
+                    EventMessage::ToggleMute => {
+                        let muted = !MUTED.load(Ordering::Acquire);
+                        MUTED.store(muted, Ordering::Release);
+                        {
+                            let mut w = SETTINGS.write().unwrap();
+                            w.muted = muted;
+                            handle_result(settings::save(&w));
                         }
+                        let volume = VOLUME.load(Ordering::Acquire);
+                        Self::show_osd(OsdMessage::volume(volume, muted));
                     }
                     EventMessage::SeekFinished => {
                         if let Some(player) = player.as_mut() {
                             player.seek_finished();
                         }
+                        if let Some(sounder) = sounder.as_mut() {
+                            sounder.reset_dsp();
+                        }
+                    }
+                    EventMessage::SetLoopA => {
+                        let pts = AUDIO_PTS_MILLIS.load(Ordering::Acquire);
+                        if pts >= 0 {
+                            AB_LOOP.write().unwrap().set_a(pts);
+                        }
+                    }
+                    EventMessage::SetLoopB => {
+                        let pts = AUDIO_PTS_MILLIS.load(Ordering::Acquire);
+                        if pts >= 0 && !AB_LOOP.write().unwrap().set_b(pts) {
+                            warn!("point B is too close to or before point A, ignored");
+                            Self::show_osd(OsdMessage::error("Point B is too close to point A"));
+                        }
+                    }
+                    EventMessage::ClearLoop => {
+                        AB_LOOP.write().unwrap().clear();
+                    }
+                    EventMessage::NudgeLoopA(delta) => {
+                        AB_LOOP.write().unwrap().nudge_a(delta);
+                    }
+                    EventMessage::NudgeLoopB(delta) => {
+                        AB_LOOP.write().unwrap().nudge_b(delta);
+                    }
+                    EventMessage::ExportLoop => {
+                        let section = AB_LOOP.read().unwrap().section();
+                        if let (Some(section), Some(path)) = (section, media_path.as_ref()) {
+                            let mut w = SETTINGS.write().unwrap();
+                            let sections = w
+                                .ab_sections
+                                .entry(path.to_string_lossy().to_string())
+                                .or_default();
+                            if !sections.contains(&section) {
+                                sections.push(section);
+                            }
+                            handle_result(settings::save(&w));
+                        }
+                    }
+                    EventMessage::AdjustAudioDelay(delta) => {
+                        if let Some(path) = media_path.as_ref() {
+                            let delay = Self::adjust_delay(path, |d| d.adjust_audio(delta));
+                            let text = format!("Audio delay: {} ms", delay.audio);
+                            Self::show_osd(OsdMessage::info(text));
+                        }
+                    }
+                    EventMessage::NextEqPreset => {
+                        Self::update_dsp(sounder.as_mut(), |dsp| {
+                            dsp.eq_preset = dsp.eq_preset.next();
+                        });
+                    }
+                    EventMessage::AdjustEqGain(band, delta) => {
+                        if band < BAND_COUNT {
+                            let mut gain = 0.0;
+                            Self::update_dsp(sounder.as_mut(), |dsp| {
+                                // Start from the gains of current preset
+                                let mut gains = dsp.band_gains();
+                                gains[band] =
+                                    (gains[band] + delta).clamp(-MAX_BAND_GAIN, MAX_BAND_GAIN);
+                                gain = gains[band];
+                                dsp.eq_preset = EqPreset::Custom;
+                                dsp.eq_gains = gains;
+                            });
+
+                            let frequency = BAND_FREQUENCIES[band];
+                            let frequency = if frequency >= 1000.0 {
+                                format!("{}kHz", frequency / 1000.0)
+                            } else {
+                                format!("{}Hz", frequency)
+                            };
+                            let text = format!("EQ {}: {:+.0} dB", frequency, gain);
+                            Self::show_osd(OsdMessage::info(text));
+                        }
+                    }
+                    EventMessage::ToggleNightMode => {
+                        Self::update_dsp(sounder.as_mut(), |dsp| {
+                            dsp.night_mode = !dsp.night_mode;
+                        });
+                    }
+                    EventMessage::AdjustBalance(delta) => {
+                        Self::update_dsp(sounder.as_mut(), |dsp| {
+                            // Round to avoid the accumulated error of float
+                            let balance = ((dsp.balance + delta) * 100.0).round() / 100.0;
+                            dsp.balance = balance.clamp(-1.0, 1.0);
+                        });
+                    }
+                    EventMessage::ToggleChannelSwap => {
+                        Self::update_dsp(sounder.as_mut(), |dsp| {
+                            dsp.swap_channels = !dsp.swap_channels;
+                        });
+                    }
+                    EventMessage::NextNormalizationMode => {
+                        {
+                            let mut w = SETTINGS.write().unwrap();
+                            w.normalization = w.normalization.next();
+                        }
+                        if let (Some(sounder), Some(path)) = (sounder.as_mut(), media_path.as_ref())
+                        {
+                            Self::apply_normalization(
+                                sounder,
+                                path,
+                                &loudness_cache,
+                                &loudness_scanner,
+                            );
+                        }
+                    }
+                    EventMessage::ScanLoudness(paths) => {
+                        if SETTINGS.read().unwrap().analyze_loudness {
+                            for path in paths {
+                                loudness_scanner.scan(path, &loudness_cache);
+                            }
+                        }
+                    }
+                    EventMessage::LoudnessAnalyzed(path, info) => {
+                        loudness_cache.insert(&path, info);
+                        handle_result(loudness::save(&loudness_cache));
+
+                        // The playing media may be analyzed after it started
+                        if let Some(sounder) = sounder.as_mut() {
+                            if media_path.as_ref() == Some(&path) {
+                                Self::apply_normalization(
+                                    sounder,
+                                    &path,
+                                    &loudness_cache,
+                                    &loudness_scanner,
+                                );
+                            }
+                        }
+                    }
+                    EventMessage::NextAudioDevice => {
+                        // The default device is the first one, then the devices in system order
+                        let mut devices = vec![None];
+                        devices.extend(
+                            output::output_devices(&self.audio_subsystem)
+                                .into_iter()
+                                .map(Some),
+                        );
+
+                        let device = {
+                            let mut w = SETTINGS.write().unwrap();
+                            let index = devices
+                                .iter()
+                                .position(|d| *d == w.audio_device)
+                                .map_or(0, |i| (i + 1) % devices.len());
+                            w.audio_device = devices[index].clone();
+                            w.audio_device.clone()
+                        };
+                        info!("switch audio device to {:?}", device);
+
+                        if let Some(sounder) = sounder.as_mut() {
+                            sounder.set_device(device);
+                        }
+                    }
+                    EventMessage::AudioDevicesChanged => {
+                        if let Some(sounder) = sounder.as_mut() {
+                            sounder.check_device();
+                        }
+                    }
+                    EventMessage::NextScaleMode => {
+                        let mode = {
+                            let mut w = SETTINGS.write().unwrap();
+                            w.scale_mode = w.scale_mode.next();
+                            w.scale_mode
+                        };
+                        if let Some(wind) = self.video_window.as_mut() {
+                            wind.set_scale_mode(mode);
+                        }
+                    }
+                    EventMessage::NextAspectRatio => {
+                        let aspect_ratio = {
+                            let mut w = SETTINGS.write().unwrap();
+                            w.aspect_ratio = w.aspect_ratio.next();
+                            w.aspect_ratio
+                        };
+                        if let Some(wind) = self.video_window.as_mut() {
+                            wind.set_aspect_ratio(aspect_ratio);
+                        }
+                    }
+                    EventMessage::ResetZoom => {
+                        if let Some(wind) = self.video_window.as_mut() {
+                            wind.reset_zoom();
+                        }
+                    }
+                    EventMessage::RotateVideo(degrees) => {
+                        if let Some(wind) = self.video_window.as_mut() {
+                            wind.rotate(degrees);
+                        }
+                    }
+                    EventMessage::FlipHorizontal => {
+                        if let Some(wind) = self.video_window.as_mut() {
+                            wind.flip_horizontal();
+                        }
+                    }
+                    EventMessage::FlipVertical => {
+                        if let Some(wind) = self.video_window.as_mut() {
+                            wind.flip_vertical();
+                        }
+                    }
+                    // The decoder reads the settings for every frame, so they take effect at once
+                    EventMessage::NextDeinterlaceMode => {
+                        let mut w = SETTINGS.write().unwrap();
+                        w.video_filter.deinterlace = w.video_filter.deinterlace.next();
+                        info!("deinterlace mode: {:?}", w.video_filter.deinterlace);
+                    }
+                    EventMessage::AdjustVideoFilter(param, steps) => {
+                        let mut w = SETTINGS.write().unwrap();
+                        w.video_filter.adjust(param, steps);
+                    }
+                    EventMessage::ResetVideoFilters => {
+                        let mut w = SETTINGS.write().unwrap();
+                        w.video_filter.reset_picture();
+                    }
+                    EventMessage::TakeScreenshot => {
+                        if let (Some(wind), Some(path)) =
+                            (self.video_window.as_ref(), media_path.as_ref())
+                        {
+                            let dir = SETTINGS.read().unwrap().screenshot_dir.clone();
+                            let media_name = path
+                                .file_stem()
+                                .map(|n| n.to_string_lossy().to_string())
+                                .unwrap_or_default();
+                            if let Some(Some(saved)) =
+                                handle_result(wind.screenshot(&dir, &media_name))
+                            {
+                                info!("screenshot saved: {}", saved.display());
+                                let text = format!("Screenshot saved: {}", saved.display());
+                                Self::show_osd(OsdMessage::info(text));
+                            }
+                        }
+                    }
+                    EventMessage::ExportClip => {
+                        let format = ClipFormat::Video(SETTINGS.read().unwrap().clip_mode);
+                        Self::export_clip(&mut clip_exporter, media_path.as_deref(), format);
+                    }
+                    EventMessage::ExportFrames => {
+                        let format = ClipFormat::Sequence(SETTINGS.read().unwrap().frame_export);
+                        Self::export_clip(&mut clip_exporter, media_path.as_deref(), format);
+                    }
+                    EventMessage::CancelClipExport => {
+                        clip_exporter.cancel();
+                    }
+                    EventMessage::ClipProgress(value) => {
+                        info!("exporting clip: {:.0}%", value * 100.0);
+                    }
+                    EventMessage::ClipExported(path) => {
+                        info!("clip saved: {}", path.display());
+                        Self::show_osd(OsdMessage::info(format!("Clip saved: {}", path.display())));
+                    }
+                    EventMessage::RequestThumbnail(millis) => {
+                        if let Some(path) = media_path.as_ref() {
+                            thumbnailer.request(path.clone(), millis);
+                        }
+                    }
+                    EventMessage::ThumbnailReady(path, millis, thumbnail) => {
+                        // The thumbnails of previous media are dropped
+                        if media_path.as_ref() == Some(&path) {
+                            if let Some(wind) = self.video_window.as_mut() {
+                                wind.update_thumbnail(millis, thumbnail);
+                            }
+                        }
                     }
                     EventMessage::ExitVideoWindow => {
                         // Stop the decoder and player before leave video window
@@ -237,14 +759,153 @@ impl NtApp {
                 }
             }
 
+            // Repeat the A-B section if both markers are set
+            let pts = AUDIO_PTS_MILLIS.load(Ordering::Acquire);
+            if let Some(start) = AB_LOOP.write().unwrap().check(pts) {
+                safe_send(sender.send(EventMessage::SeekTo(start)));
+            }
+
             // Refresh video window on display
             if let Some(wind) = self.video_window.as_mut() {
                 wind.render()?;
             }
         }
 
+        // Keep settings for next session
+        if let Err(err) = settings::save(&SETTINGS.read().unwrap()) {
+            error!("failed to save settings: {}", err);
+        }
+
         Ok(())
     }
+
+    /// Set the global volume, any change of volume cancels the mute state.
+    /// It's saved at once, so that it's kept even if the player doesn't exit normally
+    fn set_volume(volume: i16) {
+        let volume = volume.clamp(0, MAX_VOLUME);
+        VOLUME.store(volume, Ordering::Release);
+        MUTED.store(false, Ordering::Release);
+
+        let mut w = SETTINGS.write().unwrap();
+        // Dragging the volume bar sends the same volume repeatedly
+        if w.volume != volume || w.muted {
+            w.volume = volume;
+            w.muted = false;
+            handle_result(settings::save(&w));
+        }
+
+        Self::show_osd(OsdMessage::volume(volume, false));
+    }
+
+    /// Set the normalization gain of the playing media, ReplayGain tags are preferred.
+    /// If there are neither tags nor cached result, the media is queued to analyze
+    fn apply_normalization(
+        sounder: &mut Sounder,
+        path: &Path,
+        cache: &LoudnessCache,
+        scanner: &LoudnessScanner,
+    ) {
+        let r = SETTINGS.read().unwrap();
+        let replay_gain = MEDIA_SUMMARY
+            .read()
+            .unwrap()
+            .as_ref()
+            .and_then(|s| ReplayGain::from_tags(&s.tags));
+        let measured = cache.get(path);
+
+        if replay_gain.is_none() && r.analyze_loudness && r.normalization != NormalizationMode::Off
+        {
+            scanner.scan(path.to_path_buf(), cache);
+        }
+
+        let gain =
+            loudness::normalization_gain(r.normalization, replay_gain.as_ref(), measured.as_ref());
+        sounder.set_normalization_gain(gain);
+    }
+
+    /// Export the A-B section of the playing media in background
+    fn export_clip(exporter: &mut ClipExporter, path: Option<&Path>, format: ClipFormat) {
+        let section = AB_LOOP.read().unwrap().section();
+        if let (Some(section), Some(path)) = (section, path) {
+            let dir = SETTINGS.read().unwrap().clip_dir.clone();
+            exporter.export(ClipRequest::new(path, &dir, section, format));
+        } else {
+            warn!("both A and B points should be set to export a clip");
+            Self::show_osd(OsdMessage::error(
+                "Set both A and B points to export a clip",
+            ));
+        }
+    }
+
+    /// Find the chapter to go with the play position in chapters of current media
+    /// # Returns
+    /// The start and title of the chapter
+    fn chapter_start(pts: i64, find: fn(&[Chapter], i64) -> Option<i64>) -> Option<(i64, String)> {
+        let r = MEDIA_SUMMARY.read().unwrap();
+        let chapters = &r.as_ref()?.chapters;
+        let start = find(chapters, pts)?;
+        let (index, chapter) = chapter_at(chapters, start)?;
+
+        Some((start, chapter.display_title(index)))
+    }
+
+    /// Apply the delays remembered for the media
+    fn restore_delay(path: &Path) {
+        let key = path.to_string_lossy().to_string();
+        let r = SETTINGS.read().unwrap();
+        Self::apply_delay(r.delays.get(&key).copied().unwrap_or_default());
+    }
+
+    /// Change the delays of media and remember them in settings
+    fn adjust_delay(path: &Path, adjust: impl FnOnce(&mut MediaDelay)) -> MediaDelay {
+        let key = path.to_string_lossy().to_string();
+        let mut w = SETTINGS.write().unwrap();
+        let mut delay = w.delays.get(&key).copied().unwrap_or_default();
+        adjust(&mut delay);
+
+        // The media which is in sync again is not kept
+        if delay == MediaDelay::default() {
+            w.delays.remove(&key);
+        } else {
+            w.delays.insert(key, delay);
+        }
+        Self::apply_delay(delay);
+
+        delay
+    }
+
+    fn apply_delay(delay: MediaDelay) {
+        AUDIO_DELAY_MILLIS.store(delay.audio, Ordering::Release);
+    }
+
+    /// Show the transient message above the video
+    fn show_osd(msg: OsdMessage) {
+        safe_send(EVENT_CHANNEL.0.send(EventMessage::ShowOsd(msg)));
+    }
+
+    /// Show the position to play and the duration of current media
+    fn show_seek_osd(millis: i64) {
+        let r = MEDIA_SUMMARY.read().unwrap();
+        let duration = r.as_ref().map_or(0, |s| s.duration_millis);
+        Self::show_osd(OsdMessage::seek(millis, duration));
+    }
+
+    /// Show the bookmarks of current media on the progress bar
+    fn show_bookmarks(wind: &mut VideoWindow, bookmarks: &[Bookmark]) {
+        let positions = bookmarks.iter().map(|b| b.position as u64).collect();
+        wind.set_bookmarks(positions);
+    }
+
+    /// Modify the options of audio effects, the new options are saved
+    /// in settings and applied to the sounder if it's running
+    fn update_dsp<F: FnOnce(&mut DspSettings)>(sounder: Option<&mut Sounder>, f: F) {
+        let mut w = SETTINGS.write().unwrap();
+        f(&mut w.dsp);
+
+        if let Some(sounder) = sounder {
+            sounder.apply_dsp_settings(&w.dsp);
+        }
+    }
 }
 
 #[derive(Clone, PartialEq, Eq)]
@@ -1,6 +1,7 @@
 use sdl2::{
+    clipboard::ClipboardUtil,
     event::{Event, WindowEvent},
-    keyboard::Keycode,
+    keyboard::{KeyboardUtil, Keycode, Mod},
     video::FullscreenType,
     EventPump, Sdl,
 };
@@ -8,24 +9,40 @@ use sdl2::{
 use super::MainLoopState;
 use crate::{
     entity::EventMessage,
+    media::{bookmark::BookmarkFormat, filter::FilterParam},
     ui::{
-        components::{MouseDownParam, MouseMotionParam, MouseUpParam, MouseWheelParam},
+        components::{
+            KeyDownParam, MouseDownParam, MouseMotionParam, MouseUpParam, MouseWheelParam,
+            TextEditingParam, TextInputParam,
+        },
         start_window::StartWindow,
         video_window::VideoWindow,
     },
     util::error::{safe_send, SuperError},
-    EVENT_CHANNEL,
+    AB_NUDGE_STEP, BALANCE_STEP, DELAY_STEP, EQ_GAIN_STEP, EVENT_CHANNEL,
 };
 
 pub(in crate::app) struct SdlEvents {
     event_pump: EventPump,
+    keyboard: KeyboardUtil,
+    /// The text in clipboard is taken as the note of bookmark
+    clipboard: ClipboardUtil,
+    /// The latest position of cursor, since wheel events do not contain it
+    cursor: (i32, i32),
 }
 
 impl SdlEvents {
     pub(in crate::app) fn new(ctx: &Sdl) -> Result<Self, SuperError> {
         let event_pump = ctx.event_pump()?;
+        let keyboard = ctx.keyboard();
+        let clipboard = ctx.video()?.clipboard();
 
-        Ok(Self { event_pump })
+        Ok(Self {
+            event_pump,
+            keyboard,
+            clipboard,
+            cursor: (0, 0),
+        })
     }
 
     /// Handler for sdl events, if the return value is Ok(false),
@@ -38,10 +55,39 @@ impl SdlEvents {
         let sender = &EVENT_CHANNEL.0;
 
         for event in self.event_pump.poll_iter() {
+            // The focused control takes the key before the shortcuts
+            if let Event::KeyDown {
+                timestamp,
+                window_id,
+                keycode,
+                keymod,
+                repeat,
+                ..
+            } = event
+            {
+                if let Some(wind) = video_window.as_mut() {
+                    if window_id == wind.id {
+                        let params = KeyDownParam {
+                            timestamp,
+                            window_id,
+                            keycode,
+                            keymod,
+                            repeat,
+                        };
+                        if wind.on_key_down(&params)? {
+                            continue;
+                        }
+                    }
+                }
+            }
+
             match event {
                 Event::Quit { .. } => return Ok(MainLoopState::Quit),
                 Event::KeyDown {
-                    keycode, window_id, ..
+                    keycode,
+                    keymod,
+                    window_id,
+                    ..
                 } => match keycode {
                     Some(Keycode::Escape) => {
                         if window_id == start_window.id {
@@ -75,11 +121,315 @@ impl SdlEvents {
                             safe_send(sender.send(EventMessage::Forward));
                         }
                     }
+                    Some(Keycode::A) => {
+                        if video_window.is_some() {
+                            safe_send(sender.send(EventMessage::SetLoopA));
+                        }
+                    }
+                    Some(Keycode::B) => {
+                        if video_window.is_some() {
+                            safe_send(sender.send(EventMessage::SetLoopB));
+                        }
+                    }
+                    Some(Keycode::Backspace) => {
+                        if video_window.is_some() {
+                            safe_send(sender.send(EventMessage::ClearLoop));
+                        }
+                    }
+                    Some(Keycode::LeftBracket) | Some(Keycode::RightBracket) => {
+                        if video_window.is_some() {
+                            let delta = if keycode == Some(Keycode::LeftBracket) {
+                                -AB_NUDGE_STEP
+                            } else {
+                                AB_NUDGE_STEP
+                            };
+                            // Nudge point B with shift key pressed, otherwise nudge point A
+                            if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+                                safe_send(sender.send(EventMessage::NudgeLoopB(delta)));
+                            } else {
+                                safe_send(sender.send(EventMessage::NudgeLoopA(delta)));
+                            }
+                        }
+                    }
+                    Some(Keycode::Minus) | Some(Keycode::Equals) => {
+                        if video_window.is_some() {
+                            let delta = if keycode == Some(Keycode::Minus) {
+                                -DELAY_STEP
+                            } else {
+                                DELAY_STEP
+                            };
+                            safe_send(sender.send(EventMessage::AdjustAudioDelay(delta)));
+                        }
+                    }
+                    Some(Keycode::L) => {
+                        if video_window.is_some() {
+                            safe_send(sender.send(EventMessage::ExportLoop));
+                        }
+                    }
+                    Some(Keycode::M) => {
+                        if video_window.is_some() {
+                            safe_send(sender.send(EventMessage::ToggleMute));
+                        }
+                    }
+                    Some(Keycode::E) => {
+                        if video_window.is_some() {
+                            safe_send(sender.send(EventMessage::NextEqPreset));
+                        }
+                    }
+                    Some(Keycode::N) => {
+                        if video_window.is_some() {
+                            safe_send(sender.send(EventMessage::ToggleNightMode));
+                        }
+                    }
+                    Some(Keycode::W) => {
+                        if video_window.is_some() {
+                            safe_send(sender.send(EventMessage::ToggleChannelSwap));
+                        }
+                    }
+                    Some(Keycode::D) => {
+                        if video_window.is_some() {
+                            safe_send(sender.send(EventMessage::NextAudioDevice));
+                        }
+                    }
+                    Some(Keycode::V) => {
+                        // Change the aspect ratio with shift key pressed, otherwise the scale mode
+                        if video_window.is_some() {
+                            if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+                                safe_send(sender.send(EventMessage::NextAspectRatio));
+                            } else {
+                                safe_send(sender.send(EventMessage::NextScaleMode));
+                            }
+                        }
+                    }
+                    // Alt with the number keys raises the gain of an equalizer band,
+                    // lowers it with shift key pressed
+                    Some(key)
+                        if video_window.is_some()
+                            && keymod.intersects(Mod::LALTMOD | Mod::RALTMOD)
+                            && eq_band(key).is_some() =>
+                    {
+                        if let Some(band) = eq_band(key) {
+                            let delta = if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+                                -EQ_GAIN_STEP
+                            } else {
+                                EQ_GAIN_STEP
+                            };
+                            safe_send(sender.send(EventMessage::AdjustEqGain(band, delta)));
+                        }
+                    }
+                    Some(Keycode::Num0) | Some(Keycode::Kp0) => {
+                        if video_window.is_some() {
+                            safe_send(sender.send(EventMessage::ResetZoom));
+                        }
+                    }
+                    Some(Keycode::R) => {
+                        // Rotate counterclockwise with shift key pressed
+                        if video_window.is_some() {
+                            let degrees = if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+                                -90
+                            } else {
+                                90
+                            };
+                            safe_send(sender.send(EventMessage::RotateVideo(degrees)));
+                        }
+                    }
+                    Some(Keycode::H) => {
+                        // Flip vertically with shift key pressed, otherwise horizontally
+                        if video_window.is_some() {
+                            if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+                                safe_send(sender.send(EventMessage::FlipVertical));
+                            } else {
+                                safe_send(sender.send(EventMessage::FlipHorizontal));
+                            }
+                        }
+                    }
+                    Some(Keycode::O) => {
+                        if video_window.is_some() {
+                            safe_send(sender.send(EventMessage::NextNormalizationMode));
+                        }
+                    }
+                    Some(Keycode::Comma) | Some(Keycode::Period) => {
+                        // Move the balance with shift key pressed, aka "<" and ">"
+                        if video_window.is_some()
+                            && keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD)
+                        {
+                            let delta = if keycode == Some(Keycode::Comma) {
+                                -BALANCE_STEP
+                            } else {
+                                BALANCE_STEP
+                            };
+                            safe_send(sender.send(EventMessage::AdjustBalance(delta)));
+                        } else if video_window.is_some() {
+                            let msg = if keycode == Some(Keycode::Comma) {
+                                EventMessage::PreviousBookmark
+                            } else {
+                                EventMessage::NextBookmark
+                            };
+                            safe_send(sender.send(msg));
+                        }
+                    }
+                    Some(Keycode::K) => {
+                        // Remove the bookmark with ctrl key pressed,
+                        // or take the text in clipboard as note with shift key pressed
+                        if video_window.is_some() {
+                            if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) {
+                                safe_send(sender.send(EventMessage::RemoveBookmark));
+                            } else if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+                                let note = self.clipboard.clipboard_text().ok();
+                                safe_send(sender.send(EventMessage::AddBookmark(note)));
+                            } else {
+                                safe_send(sender.send(EventMessage::AddBookmark(None)));
+                            }
+                        }
+                    }
+                    Some(Keycode::I) => {
+                        if video_window.is_some() {
+                            safe_send(sender.send(EventMessage::ToggleStats));
+                        }
+                    }
+                    Some(Keycode::T) => {
+                        if video_window.is_some() {
+                            safe_send(sender.send(EventMessage::NextTheme));
+                        }
+                    }
+                    Some(Keycode::J) => {
+                        if video_window.is_some() {
+                            safe_send(sender.send(EventMessage::ListBookmarks));
+                        }
+                    }
+                    Some(Keycode::F9) => {
+                        // Export as JSON with shift key pressed, otherwise CSV
+                        if video_window.is_some() {
+                            let format = if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+                                BookmarkFormat::Json
+                            } else {
+                                BookmarkFormat::Csv
+                            };
+                            safe_send(sender.send(EventMessage::ExportBookmarks(format)));
+                        }
+                    }
+                    Some(Keycode::S) => {
+                        if video_window.is_some() {
+                            safe_send(sender.send(EventMessage::TakeScreenshot));
+                        }
+                    }
+                    Some(Keycode::X) => {
+                        // Cancel the exporting clip with shift key pressed
+                        if video_window.is_some() {
+                            if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+                                safe_send(sender.send(EventMessage::CancelClipExport));
+                            } else {
+                                safe_send(sender.send(EventMessage::ExportClip));
+                            }
+                        }
+                    }
+                    Some(Keycode::G) => {
+                        if video_window.is_some() {
+                            safe_send(sender.send(EventMessage::ExportFrames));
+                        }
+                    }
+                    Some(Keycode::PageUp) => {
+                        if video_window.is_some() {
+                            safe_send(sender.send(EventMessage::PreviousChapter));
+                        }
+                    }
+                    Some(Keycode::PageDown) => {
+                        if video_window.is_some() {
+                            safe_send(sender.send(EventMessage::NextChapter));
+                        }
+                    }
+                    Some(Keycode::F5) => {
+                        if video_window.is_some() {
+                            safe_send(sender.send(EventMessage::NextDeinterlaceMode));
+                        }
+                    }
+                    Some(Keycode::F6) | Some(Keycode::F7) => {
+                        // Decrease with shift key pressed, otherwise increase
+                        if video_window.is_some() {
+                            let param = if keycode == Some(Keycode::F6) {
+                                FilterParam::Denoise
+                            } else {
+                                FilterParam::Sharpen
+                            };
+                            let steps = if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+                                -1
+                            } else {
+                                1
+                            };
+                            safe_send(sender.send(EventMessage::AdjustVideoFilter(param, steps)));
+                        }
+                    }
+                    Some(Keycode::F8) => {
+                        if video_window.is_some() {
+                            safe_send(sender.send(EventMessage::ResetVideoFilters));
+                        }
+                    }
+                    // Each pair of keys decreases and increases a picture parameter
+                    Some(
+                        key @ (Keycode::Num1
+                        | Keycode::Num2
+                        | Keycode::Num3
+                        | Keycode::Num4
+                        | Keycode::Num5
+                        | Keycode::Num6
+                        | Keycode::Num7
+                        | Keycode::Num8),
+                    ) => {
+                        if video_window.is_some() {
+                            let (param, steps) = match key {
+                                Keycode::Num1 => (FilterParam::Brightness, -1),
+                                Keycode::Num2 => (FilterParam::Brightness, 1),
+                                Keycode::Num3 => (FilterParam::Contrast, -1),
+                                Keycode::Num4 => (FilterParam::Contrast, 1),
+                                Keycode::Num5 => (FilterParam::Saturation, -1),
+                                Keycode::Num6 => (FilterParam::Saturation, 1),
+                                Keycode::Num7 => (FilterParam::Gamma, -1),
+                                _ => (FilterParam::Gamma, 1),
+                            };
+                            safe_send(sender.send(EventMessage::AdjustVideoFilter(param, steps)));
+                        }
+                    }
                     Some(Keycode::F4) => {
                         return Ok(MainLoopState::Quit);
                     }
                     _ => {}
                 },
+                Event::TextInput {
+                    timestamp,
+                    window_id,
+                    text,
+                } => {
+                    if let Some(window) = video_window {
+                        if window_id == window.id {
+                            let params = TextInputParam {
+                                timestamp,
+                                window_id,
+                                text,
+                            };
+                            window.on_text_input(&params)?;
+                        }
+                    }
+                }
+                Event::TextEditing {
+                    timestamp,
+                    window_id,
+                    text,
+                    start,
+                    length,
+                } => {
+                    if let Some(window) = video_window {
+                        if window_id == window.id {
+                            let params = TextEditingParam {
+                                timestamp,
+                                window_id,
+                                text,
+                                start,
+                                length,
+                            };
+                            window.on_text_editing(&params)?;
+                        }
+                    }
+                }
                 Event::MouseMotion {
                     timestamp,
                     window_id,
@@ -90,6 +440,7 @@ impl SdlEvents {
                     xrel,
                     yrel,
                 } => {
+                    self.cursor = (x, y);
                     let params = MouseMotionParam {
                         timestamp,
                         window_id,
@@ -180,6 +531,9 @@ impl SdlEvents {
                         x,
                         y,
                         direction,
+                        mouse_x: self.cursor.0,
+                        mouse_y: self.cursor.1,
+                        keymod: self.keyboard.mod_state(),
                     };
                     if window_id == start_window.id {
                         start_window.on_mouse_wheel(&params)?;
@@ -204,9 +558,36 @@ impl SdlEvents {
                     }
                     _ => {}
                 },
+                // Only output devices are concerned
+                Event::AudioDeviceAdded { iscapture, .. }
+                | Event::AudioDeviceRemoved { iscapture, .. } => {
+                    if !iscapture {
+                        safe_send(sender.send(EventMessage::AudioDevicesChanged));
+                    }
+                }
                 _ => return Ok(MainLoopState::Continue),
             }
         }
         Ok(MainLoopState::Continue)
     }
 }
+
+/// The equalizer band adjusted by the number key,
+/// the keys from 1 to 9 are the lower bands and 0 is the highest band
+fn eq_band(keycode: Keycode) -> Option<usize> {
+    let band = match keycode {
+        Keycode::Num1 => 0,
+        Keycode::Num2 => 1,
+        Keycode::Num3 => 2,
+        Keycode::Num4 => 3,
+        Keycode::Num5 => 4,
+        Keycode::Num6 => 5,
+        Keycode::Num7 => 6,
+        Keycode::Num8 => 7,
+        Keycode::Num9 => 8,
+        Keycode::Num0 => 9,
+        _ => return None,
+    };
+
+    Some(band)
+}
@@ -1,6 +1,14 @@
 use std::path::PathBuf;
 
-use crate::media::decoder::{AudioFrame, SubtitleFrame, VideoFrame};
+use crate::{
+    media::{
+        bookmark::BookmarkFormat,
+        decoder::{AudioFrame, SubtitleFrame, VideoFrame},
+        filter::FilterParam,
+    },
+    sound::loudness::LoudnessInfo,
+    ui::video_window::osd::OsdMessage,
+};
 
 /// Message types for application related events
 pub enum EventMessage {
@@ -9,6 +17,10 @@ pub enum EventMessage {
     /// Exit video window and return back to the start window
     ExitVideoWindow,
 
+    /// Show a transient message above the video, for the feedback which needs no dialog
+    ShowOsd(OsdMessage),
+    /// Show or hide the statistics of playing media
+    ToggleStats,
     /// Show error dialog
     ShowError(String),
 
@@ -20,10 +32,47 @@ pub enum EventMessage {
     Forward,
     Rewind,
     SeekTo(i64),
+    /// Seek to the key frame near the position while dragging the progress bar
+    ScrubTo(i64),
+    /// Play the next file of the play list, or go to the next chapter if it's the last one
+    NextMedia,
+    /// Play the previous file of the play list, or go to the previous chapter if it's the first one
+    PreviousMedia,
+    /// Go to the start of next chapter
+    NextChapter,
+    /// Go to the start of previous chapter, or restart the current one
+    PreviousChapter,
+
+    // Bookmarks
+    /// Add a bookmark at the play position with an optional note
+    AddBookmark(Option<String>),
+    /// Remove the bookmark at the play position
+    RemoveBookmark,
+    NextBookmark,
+    PreviousBookmark,
+    /// Show all the bookmarks of current media
+    ListBookmarks,
+    /// Save the bookmarks of current media to a file
+    ExportBookmarks(BookmarkFormat),
 
     // Indicate that forward or rewind operation has been completed
     SeekFinished,
 
+    // A-B loop
+    SetLoopA,
+    SetLoopB,
+    ClearLoop,
+    /// Move point A by the specified milliseconds
+    NudgeLoopA(i64),
+    /// Move point B by the specified milliseconds
+    NudgeLoopB(i64),
+    /// Save current A-B section to settings
+    ExportLoop,
+
+    // Synchronization
+    /// Delay the audio of playing media by the specified milliseconds
+    AdjustAudioDelay(i64),
+
     // File
     FileOpened(PathBuf),
     DirOpened(Vec<PathBuf>),
@@ -32,6 +81,8 @@ pub enum EventMessage {
     RenderVideo(VideoFrame),
     RenderAudio(AudioFrame),
     RenderSubtitle(SubtitleFrame),
+    /// Show the cover of audio media
+    RenderCover(VideoFrame),
 
     // UI layout
     Resize((u32, u32)),
@@ -41,6 +92,78 @@ pub enum EventMessage {
     // Volume control
     UpVolume,
     DownVolume,
+    /// Set volume in percent
+    SetVolume(i16),
+    ToggleMute,
+
+    // Audio effects
+    /// Switch to the next equalizer preset
+    NextEqPreset,
+    /// Move the gain of an equalizer band by the specified dB, the preset becomes custom
+    AdjustEqGain(usize, f32),
+    ToggleNightMode,
+    /// Move the stereo balance by the specified value, negative value moves to left
+    AdjustBalance(f32),
+    ToggleChannelSwap,
+
+    // Loudness normalization
+    /// Switch to the next normalization mode
+    NextNormalizationMode,
+    /// Analyze the loudness of files in background, e.g. files in play list
+    ScanLoudness(Vec<PathBuf>),
+    /// The loudness of the file has been analyzed
+    LoudnessAnalyzed(PathBuf, LoudnessInfo),
+
+    // Audio device
+    /// Switch to the next audio output device, the default device is included
+    NextAudioDevice,
+    /// Audio devices are added or removed
+    AudioDevicesChanged,
+
+    // Video scaling
+    /// Switch to the next scale mode
+    NextScaleMode,
+    /// Switch to the next forced aspect ratio
+    NextAspectRatio,
+    /// Zoom out to the original size and remove the pan
+    ResetZoom,
+    /// Rotate the video clockwise by degrees, negative value rotates counterclockwise
+    RotateVideo(i32),
+    /// Mirror the video from left to right
+    FlipHorizontal,
+    /// Mirror the video from top to bottom
+    FlipVertical,
+
+    // Video filters
+    /// Switch to the next deinterlacing mode
+    NextDeinterlaceMode,
+    /// Adjust the parameter of video filters by the number of steps
+    AdjustVideoFilter(FilterParam, i32),
+    /// Remove the picture adjustments, deinterlacing is kept
+    ResetVideoFilters,
+    /// Save the displayed frame as picture
+    TakeScreenshot,
+
+    // Theme
+    /// Switch to the next theme, the custom one is skipped if it's not defined
+    NextTheme,
+
+    // Clip export
+    /// Export the A-B section of current media as a clip
+    ExportClip,
+    /// Export the A-B section of current media as an animated gif or pictures
+    ExportFrames,
+    CancelClipExport,
+    /// Progress of the exporting clip, from 0 to 1
+    ClipProgress(f32),
+    /// The clip has been saved to the path
+    ClipExported(PathBuf),
+
+    // Progress preview
+    /// Generate the thumbnail of the playing media at the position in milliseconds
+    RequestThumbnail(i64),
+    /// The thumbnail of the media at the position in milliseconds has been generated
+    ThumbnailReady(PathBuf, i64, VideoFrame),
 }
 
 pub struct MediaSelectedData {
@@ -6,19 +6,23 @@ mod config;
 mod entity;
 mod filemanager;
 mod media;
+mod settings;
 mod sound;
 mod ui;
 mod util;
 
 use std::{sync::{
-    atomic::{AtomicI16, AtomicI64},
+    atomic::{AtomicBool, AtomicI16, AtomicI64},
     RwLock,
 }, iter::Map};
 
 use crossbeam::channel::{unbounded, Receiver, Sender};
 use entity::EventMessage;
+use media::ab_loop::AbLoop;
 use media::decoder::{AudioBuffer, SubtitleBuffer, VideoBuffer};
-use media::decoder::{AudioSummary, SubtitleSummary, VideoSummary};
+use media::decoder::{AudioSummary, MediaSummary, SubtitleSummary, VideoSummary};
+use media::stats::PlaybackStats;
+use settings::Settings;
 use static_init::dynamic;
 
 /**
@@ -35,15 +39,31 @@ const INIT_HEIGHT: u32 = 768;
 
 /// Forward or rewind amount each time, Unit: milliseconds
 const FR_STEP: i64 = 10000;
-
-/// Global volume, modify this value will affect to the play volume
-static VOLUME: AtomicI16 = AtomicI16::new(0);
-static VOLUME_STEP: i16 = 10;
-const MAX_VOLUME: i16 = 2000;
-const VOLUME_BENCHMARK: f32 = 50.0;
+/// Nudge amount of A-B markers each time, Unit: milliseconds
+const AB_NUDGE_STEP: i64 = 100;
+/// Adjust amount of audio delay each time, Unit: milliseconds
+const DELAY_STEP: i64 = 50;
+/// The step to move stereo balance
+const BALANCE_STEP: f32 = 0.1;
+/// Adjust amount of the gain of an equalizer band each time, Unit: dB
+const EQ_GAIN_STEP: f32 = 1.0;
+
+/// Global volume in percent, modify this value will affect to the play volume
+static VOLUME: AtomicI16 = AtomicI16::new(UNITY_VOLUME);
+/// Global mute state, the volume is kept while muted so that it can be restored
+static MUTED: AtomicBool = AtomicBool::new(false);
+static VOLUME_STEP: i16 = 5;
+/// The volume which keeps the original loudness
+const UNITY_VOLUME: i16 = 100;
+const MAX_VOLUME: i16 = 200;
+
+/// Container level information of the playing media
+static MEDIA_SUMMARY: RwLock<Option<MediaSummary>> = RwLock::new(None);
 
 /// Global play timestamp, unit milliseconds+
 static AUDIO_PTS_MILLIS: AtomicI64 = AtomicI64::new(0);
+/// Delay of audio adjusted for the playing media, unit milliseconds
+static AUDIO_DELAY_MILLIS: AtomicI64 = AtomicI64::new(0);
 static AUDIO_SUMMARY: RwLock<Option<AudioSummary>> = RwLock::new(None);
 // It's bettrer to give more buffers for audio,
 // becuase humans are more sensitive to sound than video.
@@ -57,6 +77,15 @@ static VIDEO_PTS_MILLIS: AtomicI64 = AtomicI64::new(0);
 static VIDEO_SUMMARY: RwLock<Option<VideoSummary>> = RwLock::new(None);
 #[dynamic]
 static VIDEO_BUFFER: VideoBuffer = VideoBuffer::new(10);
+/// Counters of video playback, they are shown in the stats overlay
+static PLAYBACK_STATS: PlaybackStats = PlaybackStats::new();
+
+/// A-B markers of the playing media
+static AB_LOOP: RwLock<AbLoop> = RwLock::new(AbLoop::new());
+
+/// User settings, loaded from disk while starting
+#[dynamic]
+static SETTINGS: RwLock<Settings> = RwLock::new(settings::load_or_default());
 
 static SUBTITLE_SUMMARY: RwLock<Option<SubtitleSummary>> = RwLock::new(None);
 #[dynamic]
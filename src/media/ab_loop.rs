@@ -0,0 +1,227 @@
+use serde::{Deserialize, Serialize};
+
+/// The minimum length of an A-B section, unit: milliseconds
+const MIN_SECTION_MILLIS: i64 = 500;
+
+/// A section of media between point A and point B
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AbSection {
+    /// Position of point A, unit: milliseconds
+    pub start: i64,
+    /// Position of point B, unit: milliseconds
+    pub end: i64,
+}
+
+/// A-B markers of the playing media,
+/// once both markers are set, the playback repeats between them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AbLoop {
+    /// Position of point A, unit: milliseconds
+    a: Option<i64>,
+    /// Position of point B, unit: milliseconds
+    b: Option<i64>,
+    /// Indicate that a seek back to point A has been requested,
+    /// it's reset once the play position is back before point B
+    seek_requested: bool,
+}
+
+impl AbLoop {
+    pub const fn new() -> Self {
+        Self {
+            a: None,
+            b: None,
+            seek_requested: false,
+        }
+    }
+
+    /// Set point A at the specified position,
+    /// point B is removed if it's not far enough after the new point A
+    pub fn set_a(&mut self, millis: i64) {
+        let a = millis.max(0);
+        if let Some(b) = self.b {
+            if b - a < MIN_SECTION_MILLIS {
+                self.b = None;
+            }
+        }
+
+        self.a = Some(a);
+        self.seek_requested = false;
+    }
+
+    /// Set point B at the specified position,
+    /// if point A is not set yet, the start of media will be taken as point A
+    /// # Returns
+    /// * `true` if point B is set
+    /// * `false` if the position is too close to or before point A
+    pub fn set_b(&mut self, millis: i64) -> bool {
+        let a = self.a.unwrap_or(0);
+        if millis - a < MIN_SECTION_MILLIS {
+            return false;
+        }
+
+        self.a = Some(a);
+        self.b = Some(millis);
+        self.seek_requested = false;
+
+        true
+    }
+
+    /// Remove both markers
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Move point A by `delta` milliseconds,
+    /// point A is kept between the start of media and point B
+    pub fn nudge_a(&mut self, delta: i64) {
+        if let Some(a) = self.a {
+            let mut a = (a + delta).max(0);
+            if let Some(b) = self.b {
+                a = a.min(b - MIN_SECTION_MILLIS).max(0);
+            }
+            self.a = Some(a);
+            self.seek_requested = false;
+        }
+    }
+
+    /// Move point B by `delta` milliseconds,
+    /// point B is kept after point A
+    pub fn nudge_b(&mut self, delta: i64) {
+        if let Some(b) = self.b {
+            let min = self.a.unwrap_or(0) + MIN_SECTION_MILLIS;
+            self.b = Some((b + delta).max(min));
+            self.seek_requested = false;
+        }
+    }
+
+    /// Get the markers, unit: milliseconds
+    /// # Returns
+    /// (a, b)
+    pub fn markers(&self) -> (Option<i64>, Option<i64>) {
+        (self.a, self.b)
+    }
+
+    /// Get the section between point A and point B, if both of them are set
+    pub fn section(&self) -> Option<AbSection> {
+        match (self.a, self.b) {
+            (Some(start), Some(end)) => Some(AbSection { start, end }),
+            _ => None,
+        }
+    }
+
+    /// Check the current play position against point B
+    /// # Arguments
+    /// * `pts_millis` - The current play position, negative value means seeking
+    /// # Returns
+    /// * `Some(start)` - the position of point A that should be seeked to
+    /// * `None` - nothing to do
+    pub fn check(&mut self, pts_millis: i64) -> Option<i64> {
+        if pts_millis < 0 {
+            return None;
+        }
+
+        let section = self.section()?;
+        if pts_millis < section.end {
+            self.seek_requested = false;
+            return None;
+        }
+
+        if self.seek_requested {
+            return None;
+        }
+
+        self.seek_requested = true;
+
+        Some(section.start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_boundary() {
+        let mut ab = AbLoop::new();
+        ab.set_a(1000);
+        assert_eq!(true, ab.set_b(5000));
+
+        assert_eq!(None, ab.check(4999));
+        assert_eq!(Some(1000), ab.check(5000));
+        // Seek is already requested, don't request again
+        assert_eq!(None, ab.check(5100));
+        // Seeking, the position is unknown
+        assert_eq!(None, ab.check(-1));
+        assert_eq!(None, ab.check(5200));
+        // Back in the section, next time reaching point B seeks again
+        assert_eq!(None, ab.check(1000));
+        assert_eq!(Some(1000), ab.check(5001));
+    }
+
+    #[test]
+    fn test_check_without_section() {
+        let mut ab = AbLoop::new();
+        assert_eq!(None, ab.check(10000));
+
+        ab.set_a(1000);
+        assert_eq!(None, ab.check(10000));
+    }
+
+    #[test]
+    fn test_set_b() {
+        let mut ab = AbLoop::new();
+        // Point A is the start of media if it's not set
+        assert_eq!(true, ab.set_b(3000));
+        assert_eq!((Some(0), Some(3000)), ab.markers());
+
+        ab.set_a(2000);
+        assert_eq!(false, ab.set_b(2200));
+        assert_eq!(false, ab.set_b(1000));
+        assert_eq!(Some(AbSection { start: 2000, end: 3000 }), ab.section());
+    }
+
+    #[test]
+    fn test_set_a_after_b() {
+        let mut ab = AbLoop::new();
+        ab.set_a(1000);
+        ab.set_b(3000);
+
+        ab.set_a(2800);
+        assert_eq!((Some(2800), None), ab.markers());
+    }
+
+    #[test]
+    fn test_nudge() {
+        let mut ab = AbLoop::new();
+        ab.set_a(1000);
+        ab.set_b(3000);
+
+        ab.nudge_a(-2000);
+        assert_eq!(Some(0), ab.markers().0);
+        ab.nudge_a(5000);
+        assert_eq!(Some(3000 - MIN_SECTION_MILLIS), ab.markers().0);
+
+        ab.nudge_b(-5000);
+        assert_eq!(Some(3000), ab.markers().1);
+        ab.nudge_b(1000);
+        assert_eq!(Some(4000), ab.markers().1);
+
+        ab.clear();
+        ab.nudge_a(100);
+        ab.nudge_b(100);
+        assert_eq!((None, None), ab.markers());
+    }
+
+    #[test]
+    fn test_nudge_a_seeks_again() {
+        let mut ab = AbLoop::new();
+        ab.set_a(1000);
+        ab.set_b(3000);
+        assert_eq!(Some(1000), ab.check(3000));
+        assert_eq!(None, ab.check(3100));
+
+        // The loop seeks back to the moved point A without playing before B again
+        ab.nudge_a(500);
+        assert_eq!(Some(1500), ab.check(3200));
+    }
+}
@@ -0,0 +1,345 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::util::error::SuperError;
+
+const FILE_NAME: &str = "bookmarks.yml";
+/// The default directory to save exported bookmarks
+pub const DEFAULT_DIR: &str = "bookmarks";
+/// A bookmark closer than this to the play position is taken as the current one,
+/// so that jumping does not stay at it, unit: milliseconds
+const JUMP_TOLERANCE: i64 = 1000;
+
+/// A moment of media marked by user
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Bookmark {
+    /// Position in media, unit: milliseconds
+    pub position: i64,
+    /// Note of the moment if any
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+impl Bookmark {
+    pub fn new(position: i64, note: Option<String>) -> Self {
+        // A blank note is the same as no note
+        let note = note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+
+        Self {
+            position: position.max(0),
+            note,
+        }
+    }
+}
+
+/// The format of exported bookmarks
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookmarkFormat {
+    Csv,
+    Json,
+}
+
+impl BookmarkFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            BookmarkFormat::Csv => "csv",
+            BookmarkFormat::Json => "json",
+        }
+    }
+}
+
+/// Bookmarks of all the media, they are sorted by position in each media
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BookmarkStore {
+    /// The key is the path of media
+    entries: BTreeMap<String, Vec<Bookmark>>,
+}
+
+impl BookmarkStore {
+    /// The bookmarks of media sorted by position
+    pub fn get(&self, path: &Path) -> &[Bookmark] {
+        self.entries
+            .get(&path.to_string_lossy().to_string())
+            .map_or(&[], |b| b.as_slice())
+    }
+
+    /// Add a bookmark to media, the one at the same position is replaced
+    pub fn add(&mut self, path: &Path, bookmark: Bookmark) {
+        let bookmarks = self
+            .entries
+            .entry(path.to_string_lossy().to_string())
+            .or_default();
+        match bookmarks.binary_search_by_key(&bookmark.position, |b| b.position) {
+            Ok(index) => bookmarks[index] = bookmark,
+            Err(index) => bookmarks.insert(index, bookmark),
+        }
+    }
+
+    /// Remove the bookmark of media at the play position, if there are several,
+    /// the closest one is removed
+    pub fn remove_near(&mut self, path: &Path, millis: i64) -> Option<Bookmark> {
+        let key = path.to_string_lossy().to_string();
+        let bookmarks = self.entries.get_mut(&key)?;
+        let (index, _) = bookmarks
+            .iter()
+            .enumerate()
+            .map(|(i, b)| (i, (b.position - millis).abs()))
+            .filter(|&(_, distance)| distance <= JUMP_TOLERANCE)
+            .min_by_key(|&(_, distance)| distance)?;
+
+        let removed = bookmarks.remove(index);
+        if bookmarks.is_empty() {
+            self.entries.remove(&key);
+        }
+
+        Some(removed)
+    }
+}
+
+/// The first bookmark after the play position
+pub fn next_bookmark(bookmarks: &[Bookmark], millis: i64) -> Option<&Bookmark> {
+    bookmarks
+        .iter()
+        .find(|b| b.position > millis + JUMP_TOLERANCE)
+}
+
+/// The last bookmark before the play position
+pub fn previous_bookmark(bookmarks: &[Bookmark], millis: i64) -> Option<&Bookmark> {
+    bookmarks
+        .iter()
+        .rev()
+        .find(|b| b.position < millis - JUMP_TOLERANCE)
+}
+
+/// Convert milliseconds to "HH:mm:ss.SSS" format
+pub fn format_position(millis: i64) -> String {
+    let millis = millis.max(0);
+    let (hours, minutes) = (millis / 3_600_000, millis / 60_000 % 60);
+    let (secs, millis) = (millis / 1000 % 60, millis % 1000);
+
+    format!("{:02}:{:02}:{:02}.{:03}", hours, minutes, secs, millis)
+}
+
+/// Format bookmarks as CSV, the columns are position in milliseconds, time and note
+pub fn to_csv(bookmarks: &[Bookmark]) -> String {
+    let mut csv = String::from("position,time,note\n");
+    for bookmark in bookmarks {
+        let note = bookmark.note.as_deref().unwrap_or_default();
+        csv.push_str(&format!(
+            "{},{},{}\n",
+            bookmark.position,
+            format_position(bookmark.position),
+            escape_csv(note)
+        ));
+    }
+
+    csv
+}
+
+/// Quote the field if it contains separators, quotes or line breaks
+fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// The exported bookmarks of a media in JSON
+#[derive(Serialize)]
+struct JsonExport<'a> {
+    media: String,
+    bookmarks: Vec<JsonBookmark<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonBookmark<'a> {
+    position: i64,
+    time: String,
+    note: Option<&'a str>,
+}
+
+/// Format bookmarks of the media as JSON
+pub fn to_json(media: &Path, bookmarks: &[Bookmark]) -> Result<String, SuperError> {
+    let export = JsonExport {
+        media: media.to_string_lossy().to_string(),
+        bookmarks: bookmarks
+            .iter()
+            .map(|b| JsonBookmark {
+                position: b.position,
+                time: format_position(b.position),
+                note: b.note.as_deref(),
+            })
+            .collect(),
+    };
+
+    Ok(serde_json::to_string_pretty(&export)?)
+}
+
+/// Save bookmarks of the media to a file in the directory,
+/// the file is named after the media, e.g. "movie_bookmarks.csv"
+/// # Returns
+/// The path of saved file
+pub fn export(
+    media: &Path,
+    bookmarks: &[Bookmark],
+    dir: &Path,
+    format: BookmarkFormat,
+) -> Result<PathBuf, SuperError> {
+    let content = match format {
+        BookmarkFormat::Csv => to_csv(bookmarks),
+        BookmarkFormat::Json => to_json(media, bookmarks)?,
+    };
+
+    let stem = media
+        .file_stem()
+        .map_or("media".to_string(), |s| s.to_string_lossy().to_string());
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{}_bookmarks.{}", stem, format.extension()));
+    fs::write(&path, content)?;
+
+    Ok(path)
+}
+
+/// Load the bookmarks from disk
+pub fn load() -> Result<BookmarkStore, SuperError> {
+    // If the file does not exist, there are no bookmarks yet
+    if !Path::new(FILE_NAME).try_exists()? {
+        return Ok(BookmarkStore::default());
+    }
+
+    let f = File::open(FILE_NAME)?;
+    let store = serde_yaml::from_reader(f)?;
+
+    Ok(store)
+}
+
+/// Load the bookmarks from disk, if any error occurred, log the error and use an empty store
+pub fn load_or_default() -> BookmarkStore {
+    match load() {
+        Ok(store) => store,
+        Err(err) => {
+            error!("failed to load {}: {}", FILE_NAME, err);
+            BookmarkStore::default()
+        }
+    }
+}
+
+/// Save the bookmarks to disk
+pub fn save(store: &BookmarkStore) -> Result<(), SuperError> {
+    let mut f = File::create(FILE_NAME)?;
+    serde_yaml::to_writer(&mut f, store)?;
+    f.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bookmarks() -> Vec<Bookmark> {
+        vec![
+            Bookmark::new(10_000, None),
+            Bookmark::new(60_000, Some("intro ends".to_string())),
+            Bookmark::new(90_500, None),
+        ]
+    }
+
+    #[test]
+    fn test_add_sorted() {
+        let path = Path::new("movie.mkv");
+        let mut store = BookmarkStore::default();
+        store.add(path, Bookmark::new(60_000, None));
+        store.add(path, Bookmark::new(10_000, Some("  ".to_string())));
+        store.add(path, Bookmark::new(60_000, Some("replaced".to_string())));
+
+        let positions: Vec<_> = store.get(path).iter().map(|b| b.position).collect();
+        assert_eq!(vec![10_000, 60_000], positions);
+        assert_eq!(None, store.get(path)[0].note);
+        assert_eq!(Some("replaced"), store.get(path)[1].note.as_deref());
+        assert_eq!(true, store.get(Path::new("other.mkv")).is_empty());
+
+        // Only the bookmark at the play position is removed
+        assert_eq!(None, store.remove_near(path, 12_000));
+        let removed = store.remove_near(path, 10_500).map(|b| b.position);
+        assert_eq!(Some(10_000), removed);
+        assert_eq!(1, store.get(path).len());
+    }
+
+    #[test]
+    fn test_jump() {
+        let bookmarks = bookmarks();
+        assert_eq!(
+            Some(60_000),
+            next_bookmark(&bookmarks, 10_000).map(|b| b.position)
+        );
+        // The bookmark which is just jumped to is skipped
+        assert_eq!(
+            Some(90_500),
+            next_bookmark(&bookmarks, 60_200).map(|b| b.position)
+        );
+        assert_eq!(None, next_bookmark(&bookmarks, 90_500));
+
+        assert_eq!(
+            Some(10_000),
+            previous_bookmark(&bookmarks, 60_500).map(|b| b.position)
+        );
+        assert_eq!(
+            Some(60_000),
+            previous_bookmark(&bookmarks, 75_000).map(|b| b.position)
+        );
+        assert_eq!(None, previous_bookmark(&bookmarks, 10_000));
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let mut bookmarks = bookmarks();
+        bookmarks.push(Bookmark::new(
+            3_723_004,
+            Some("say \"hi\", bye".to_string()),
+        ));
+
+        let csv = to_csv(&bookmarks);
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!("position,time,note", lines[0]);
+        assert_eq!("10000,00:00:10.000,", lines[1]);
+        assert_eq!("60000,00:01:00.000,intro ends", lines[2]);
+        assert_eq!("3723004,01:02:03.004,\"say \"\"hi\"\", bye\"", lines[4]);
+    }
+
+    #[test]
+    fn test_to_json() {
+        let json = to_json(Path::new("movie.mkv"), &bookmarks()).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!("movie.mkv", value["media"]);
+        assert_eq!(3, value["bookmarks"].as_array().unwrap().len());
+        assert_eq!(60_000, value["bookmarks"][1]["position"]);
+        assert_eq!("00:01:00.000", value["bookmarks"][1]["time"]);
+        assert_eq!("intro ends", value["bookmarks"][1]["note"]);
+        assert_eq!(serde_json::Value::Null, value["bookmarks"][0]["note"]);
+    }
+
+    #[test]
+    fn test_export() {
+        let dir = std::env::temp_dir().join("ntplayer_bookmark_test");
+        let media = Path::new("/videos/movie.mkv");
+
+        let saved = export(media, &bookmarks(), &dir, BookmarkFormat::Csv).unwrap();
+        assert_eq!(dir.join("movie_bookmarks.csv"), saved);
+        assert_eq!(to_csv(&bookmarks()), fs::read_to_string(&saved).unwrap());
+
+        let saved = export(media, &bookmarks(), &dir, BookmarkFormat::Json).unwrap();
+        assert_eq!(dir.join("movie_bookmarks.json"), saved);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,172 @@
+use std::{collections::BTreeMap, slice};
+
+use rsmpeg::{
+    avformat::AVFormatContextInput,
+    ffi::{av_rescale_q, AVRational},
+};
+
+use super::decoder::MediaDecoder;
+
+/// Going to the previous chapter restarts the current one instead,
+/// if it has been played longer than this, unit: milliseconds
+const RESTART_THRESHOLD: i64 = 3000;
+
+/// Time base of milliseconds
+const MILLIS_BASE: AVRational = AVRational { num: 1, den: 1000 };
+
+/// A chapter of media, such as the ones in MKV and MP4 files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chapter {
+    /// Start position, unit: milliseconds
+    pub start: i64,
+    /// End position, unit: milliseconds
+    pub end: i64,
+    /// Title tag if any
+    pub title: Option<String>,
+}
+
+impl Chapter {
+    /// The text to display for the chapter, falls back to its number
+    /// # Arguments
+    /// * `index` - The index of chapter in media, starts from 0
+    pub fn display_title(&self, index: usize) -> String {
+        match self.title.as_ref() {
+            Some(title) if !title.trim().is_empty() => title.clone(),
+            _ => format!("Chapter {}", index + 1),
+        }
+    }
+}
+
+/// Read the chapters of media, they are sorted by the start position
+pub fn read_chapters(ctx: &AVFormatContextInput) -> Vec<Chapter> {
+    if ctx.chapters.is_null() || ctx.nb_chapters == 0 {
+        return vec![];
+    }
+
+    let raw_chapters = unsafe { slice::from_raw_parts(ctx.chapters, ctx.nb_chapters as usize) };
+    let mut chapters: Vec<Chapter> = raw_chapters
+        .iter()
+        .filter(|c| !c.is_null())
+        .map(|&c| {
+            let chapter = unsafe { &*c };
+            let mut tags = BTreeMap::new();
+            MediaDecoder::read_tags(chapter.metadata, &mut tags);
+
+            Chapter {
+                start: unsafe { av_rescale_q(chapter.start, chapter.time_base, MILLIS_BASE) },
+                end: unsafe { av_rescale_q(chapter.end, chapter.time_base, MILLIS_BASE) },
+                title: tags.remove("title"),
+            }
+        })
+        .collect();
+    chapters.sort_by_key(|c| c.start);
+
+    chapters
+}
+
+/// The chapter which the position is in, `None` if it's before the first chapter
+/// # Returns
+/// The index and the chapter
+pub fn chapter_at(chapters: &[Chapter], millis: i64) -> Option<(usize, &Chapter)> {
+    chapters
+        .iter()
+        .enumerate()
+        .take_while(|(_, c)| c.start <= millis)
+        .last()
+}
+
+/// The start of the chapter after the position, `None` if it's in the last chapter
+pub fn next_chapter_start(chapters: &[Chapter], millis: i64) -> Option<i64> {
+    chapters
+        .iter()
+        .map(|c| c.start)
+        .find(|&start| start > millis)
+}
+
+/// The start of the chapter before the position.
+/// If the current chapter has been played for a while, its start is returned
+pub fn previous_chapter_start(chapters: &[Chapter], millis: i64) -> Option<i64> {
+    let (index, current) = chapter_at(chapters, millis)?;
+    if millis - current.start > RESTART_THRESHOLD || index == 0 {
+        return Some(current.start);
+    }
+
+    Some(chapters[index - 1].start)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn chapters() -> Vec<Chapter> {
+        [(0, 60_000), (60_000, 150_000), (150_000, 200_000)]
+            .into_iter()
+            .map(|(start, end)| Chapter {
+                start,
+                end,
+                title: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_chapter_at() {
+        let chapters = chapters();
+        assert_eq!(Some(0), chapter_at(&chapters, 0).map(|(i, _)| i));
+        assert_eq!(Some(1), chapter_at(&chapters, 60_000).map(|(i, _)| i));
+        assert_eq!(Some(2), chapter_at(&chapters, 199_999).map(|(i, _)| i));
+        assert_eq!(None, chapter_at(&chapters, -1));
+        assert_eq!(None, chapter_at(&[], 1000));
+    }
+
+    #[test]
+    fn test_next_chapter() {
+        let chapters = chapters();
+        assert_eq!(Some(60_000), next_chapter_start(&chapters, 0));
+        assert_eq!(Some(150_000), next_chapter_start(&chapters, 60_000));
+        assert_eq!(None, next_chapter_start(&chapters, 150_000));
+    }
+
+    #[test]
+    fn test_previous_chapter() {
+        let chapters = chapters();
+        // Just after the start of chapter, go to the previous one
+        assert_eq!(Some(0), previous_chapter_start(&chapters, 61_000));
+        // Otherwise restart the current chapter
+        assert_eq!(Some(60_000), previous_chapter_start(&chapters, 90_000));
+        // The first chapter is restarted
+        assert_eq!(Some(0), previous_chapter_start(&chapters, 1000));
+        assert_eq!(None, previous_chapter_start(&[], 1000));
+    }
+
+    #[test]
+    fn test_read_chapters() {
+        let dir = std::env::temp_dir().join("ntplayer_chapter_test");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("chapters.ffmeta");
+        // The chapters are out of order, and the second one has no title
+        fs::write(
+            &path,
+            ";FFMETADATA1\n\
+             title=Chapters\n\
+             [CHAPTER]\nTIMEBASE=1/1000\nSTART=90000\nEND=120000\ntitle=Ending\n\
+             [CHAPTER]\nTIMEBASE=1/1000\nSTART=30000\nEND=90000\n\
+             [CHAPTER]\nTIMEBASE=1/90000\nSTART=0\nEND=2700000\ntitle=Opening\n",
+        )
+        .unwrap();
+
+        let ctx = MediaDecoder::get_media_context(path.to_str().unwrap()).unwrap();
+        let chapters = read_chapters(&ctx);
+
+        assert_eq!(3, chapters.len());
+        assert_eq!((0, 30_000), (chapters[0].start, chapters[0].end));
+        assert_eq!("Opening", chapters[0].display_title(0));
+        assert_eq!((30_000, 90_000), (chapters[1].start, chapters[1].end));
+        assert_eq!("Chapter 2", chapters[1].display_title(1));
+        assert_eq!(Some("Ending".to_string()), chapters[2].title);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
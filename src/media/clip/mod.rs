@@ -0,0 +1,470 @@
+mod reencode;
+mod remux;
+mod sequence;
+
+pub use sequence::{SequenceFormat, SequenceOptions};
+
+use std::{
+    ffi::CString,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+use log::{info, warn};
+use rsmpeg::{
+    avcodec::{AVCodec, AVCodecContext},
+    avformat::{AVFormatContextOutput, AVStreamRef},
+    avutil::AVFrame,
+    ffi::{
+        av_rescale_q, av_seek_frame, AVRational, AVSEEK_FLAG_BACKWARD, AV_NOPTS_VALUE, AV_TIME_BASE,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use super::{ab_loop::AbSection, decoder::MediaDecoder};
+use crate::{
+    entity::EventMessage,
+    util::error::{safe_send, SuperError},
+    EVENT_CHANNEL,
+};
+
+/// The directory of clips if it's not configured
+pub const DEFAULT_DIR: &str = "clips";
+
+/// Time base of milliseconds
+const MILLIS_BASE: AVRational = AVRational { num: 1, den: 1000 };
+
+/// How the clip is exported
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipMode {
+    /// Copy the packets without decoding, it's fast and lossless,
+    /// but the clip starts from the key frame before the start point
+    Remux,
+    /// Encode the video again so that the clip starts exactly at the start point,
+    /// other streams are copied
+    Reencode,
+}
+
+/// What the clip is exported as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipFormat {
+    /// A media file with the same container as source
+    Video(ClipMode),
+    /// An animated gif or numbered pictures
+    Sequence(SequenceOptions),
+}
+
+/// What to export
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClipRequest {
+    pub source: PathBuf,
+    /// The exported file, or the directory of pictures
+    pub output: PathBuf,
+    /// The range to export, unit: milliseconds
+    pub section: AbSection,
+    pub format: ClipFormat,
+}
+
+impl ClipRequest {
+    /// Create the request whose output is in `dir`, see `output_path`
+    pub fn new(source: &Path, dir: &Path, section: AbSection, format: ClipFormat) -> Self {
+        Self {
+            source: source.to_path_buf(),
+            output: output_path(dir, source, &section, &format),
+            section,
+            format,
+        }
+    }
+}
+
+/// The path of clip: "{dir}/{source name}_{start}-{end}.{extension}",
+/// the positions are in milliseconds. The container of source is kept for videos,
+/// since the copied streams may not be supported by other containers.
+/// Pictures are saved to the directory without extension
+pub fn output_path(dir: &Path, source: &Path, section: &AbSection, format: &ClipFormat) -> PathBuf {
+    let stem = source
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "clip".to_string());
+    let name = format!("{}_{}-{}", stem, section.start, section.end);
+
+    let extension = match format {
+        ClipFormat::Video(_) => source
+            .extension()
+            .map(|e| e.to_string_lossy().to_string())
+            .unwrap_or_else(|| "mkv".to_string()),
+        ClipFormat::Sequence(options) => match options.format {
+            SequenceFormat::Gif => "gif".to_string(),
+            SequenceFormat::Png => return dir.join(name),
+        },
+    };
+
+    dir.join(format!("{}.{}", name, extension))
+}
+
+/// Report the progress of export, the callback is only invoked while the percent is changed
+pub struct Progress<F: FnMut(f32)> {
+    section: AbSection,
+    percent: i32,
+    callback: F,
+}
+
+impl<F: FnMut(f32)> Progress<F> {
+    pub fn new(section: AbSection, callback: F) -> Self {
+        Self {
+            section,
+            percent: -1,
+            callback,
+        }
+    }
+
+    /// Update with the position which has been written, unit: milliseconds
+    pub fn update(&mut self, millis: i64) {
+        let length = (self.section.end - self.section.start).max(1);
+        let value = ((millis - self.section.start) as f32 / length as f32).clamp(0.0, 1.0);
+        let percent = (value * 100.0) as i32;
+        if percent > self.percent {
+            self.percent = percent;
+            (self.callback)(value);
+        }
+    }
+}
+
+/// Export clips in a background thread, only one clip is exported at a time.
+/// `EventMessage::ClipProgress` is sent while exporting,
+/// and `EventMessage::ClipExported` is sent once it's finished
+#[derive(Default)]
+pub struct ClipExporter {
+    cancel_flag: Arc<AtomicBool>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl ClipExporter {
+    pub fn new() -> Self {
+        Self {
+            cancel_flag: Arc::new(AtomicBool::new(false)),
+            task: None,
+        }
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.task.as_ref().map_or(false, |task| !task.is_finished())
+    }
+
+    /// Start exporting, it's ignored if another clip is being exported
+    pub fn export(&mut self, request: ClipRequest) {
+        if self.is_busy() {
+            warn!("a clip is being exported, {:?} is ignored", request.output);
+            return;
+        }
+
+        self.cancel_flag.store(false, Ordering::Release);
+        let cancel_flag = self.cancel_flag.clone();
+        self.task = Some(thread::spawn(move || {
+            let sender = &EVENT_CHANNEL.0;
+            info!("exporting clip: {:?}", request);
+            let mut progress = Progress::new(request.section, |value| {
+                safe_send(sender.send(EventMessage::ClipProgress(value)));
+            });
+
+            match export(&request, &cancel_flag, &mut progress) {
+                Ok(true) => {
+                    info!("clip exported: {:?}", request.output);
+                    safe_send(sender.send(EventMessage::ClipExported(request.output)));
+                }
+                Ok(false) => {
+                    info!("clip export is canceled: {:?}", request.output);
+                    remove_output(&request.output);
+                }
+                Err(err) => {
+                    remove_output(&request.output);
+                    let message = format!("failed to export clip: {}", err);
+                    safe_send(sender.send(EventMessage::ShowError(message)));
+                }
+            }
+        }));
+    }
+
+    /// Cancel the exporting clip, the unfinished file is removed
+    pub fn cancel(&self) {
+        self.cancel_flag.store(true, Ordering::Release);
+    }
+}
+
+impl Drop for ClipExporter {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Export the clip in current thread
+/// # Returns
+/// `false` if it's canceled
+pub fn export<F: FnMut(f32)>(
+    request: &ClipRequest,
+    cancel_flag: &AtomicBool,
+    progress: &mut Progress<F>,
+) -> Result<bool, SuperError> {
+    let section = request.section;
+    if section.end <= section.start {
+        return Err("the end of clip should be after the start".into());
+    }
+    if let Some(dir) = request.output.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let source = request.source.to_str().ok_or("path is not valid unicode")?;
+    let mut input = MediaDecoder::get_media_context(source)?;
+    // Seek to the key frame before start point, the frames before it cannot be decoded
+    let time_base = AVRational {
+        num: 1,
+        den: AV_TIME_BASE as i32,
+    };
+    let position = millis_to_ts(section.start, time_base);
+    let flags = AVSEEK_FLAG_BACKWARD as i32;
+    let ret = unsafe { av_seek_frame(input.as_mut_ptr(), -1, position, flags) };
+    if ret < 0 {
+        return Err("failed to seek to the start of clip".into());
+    }
+
+    let mode = match request.format {
+        ClipFormat::Video(mode) => mode,
+        ClipFormat::Sequence(options) => {
+            return sequence::export(
+                input,
+                &request.output,
+                section,
+                options,
+                cancel_flag,
+                progress,
+            )
+        }
+    };
+    let output = create_output(&request.output)?;
+    match mode {
+        ClipMode::Remux => remux::remux(input, output, section, cancel_flag, progress),
+        ClipMode::Reencode => reencode::reencode(input, output, section, cancel_flag, progress),
+    }
+}
+
+/// Create the output whose container is guessed from the path
+fn create_output(path: &Path) -> Result<AVFormatContextOutput, SuperError> {
+    let path = path.to_str().ok_or("path is not valid unicode")?;
+
+    Ok(AVFormatContextOutput::create(&CString::new(path)?, None)?)
+}
+
+/// Open the decoder of the stream
+fn open_decoder(stream: &AVStreamRef) -> Result<AVCodecContext, SuperError> {
+    let codecpar = stream.codecpar();
+    let decoder = AVCodec::find_decoder(codecpar.codec_id).ok_or("no decoder for the stream")?;
+    let mut decoder_ctx = AVCodecContext::new(&decoder);
+    decoder_ctx.apply_codecpar(&codecpar)?;
+    decoder_ctx.open(None)?;
+
+    Ok(decoder_ctx)
+}
+
+/// The timestamp of decoded frame, the best effort one is preferred
+fn frame_timestamp(frame: &AVFrame) -> i64 {
+    if frame.best_effort_timestamp != AV_NOPTS_VALUE {
+        frame.best_effort_timestamp
+    } else {
+        frame.pts
+    }
+}
+
+/// Probe the duration of media, unit: milliseconds
+pub fn probe_duration(path: &Path) -> Result<i64, SuperError> {
+    let path = path.to_str().ok_or("path is not valid unicode")?;
+    let input = MediaDecoder::get_media_context(path)?;
+
+    Ok(input.duration * 1000 / AV_TIME_BASE as i64)
+}
+
+fn remove_output(path: &Path) {
+    let result = if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else if path.exists() {
+        fs::remove_file(path)
+    } else {
+        Ok(())
+    };
+    if let Err(err) = result {
+        warn!("failed to remove {:?}: {}", path, err);
+    }
+}
+
+/// Convert the timestamp with the time base to milliseconds
+fn ts_to_millis(ts: i64, time_base: AVRational) -> i64 {
+    unsafe { av_rescale_q(ts, time_base, MILLIS_BASE) }
+}
+
+/// Convert milliseconds to the timestamp with the time base
+fn millis_to_ts(millis: i64, time_base: AVRational) -> i64 {
+    unsafe { av_rescale_q(millis, MILLIS_BASE, time_base) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_path() {
+        let section = AbSection {
+            start: 1500,
+            end: 4000,
+        };
+
+        let video = ClipFormat::Video(ClipMode::Remux);
+        assert_eq!(
+            PathBuf::from("clips/movie_1500-4000.mp4"),
+            output_path(
+                Path::new("clips"),
+                Path::new("/videos/movie.mp4"),
+                &section,
+                &video
+            )
+        );
+        assert_eq!(
+            PathBuf::from("clips/movie_1500-4000.mkv"),
+            output_path(Path::new("clips"), Path::new("movie"), &section, &video)
+        );
+
+        let gif = ClipFormat::Sequence(SequenceOptions {
+            format: SequenceFormat::Gif,
+            ..Default::default()
+        });
+        assert_eq!(
+            PathBuf::from("clips/movie_1500-4000.gif"),
+            output_path(Path::new("clips"), Path::new("movie.mp4"), &section, &gif)
+        );
+        let png = ClipFormat::Sequence(SequenceOptions {
+            format: SequenceFormat::Png,
+            ..Default::default()
+        });
+        assert_eq!(
+            PathBuf::from("clips/movie_1500-4000"),
+            output_path(Path::new("clips"), Path::new("movie.mp4"), &section, &png)
+        );
+    }
+
+    #[test]
+    fn test_progress() {
+        let mut values = vec![];
+        let section = AbSection {
+            start: 1000,
+            end: 3000,
+        };
+        let mut progress = Progress::new(section, |value| values.push(value));
+        progress.update(0);
+        // Less than a percent since last report, it's not reported
+        progress.update(1010);
+        progress.update(2000);
+        progress.update(5000);
+
+        assert_eq!(vec![0.0, 0.5, 1.0], values);
+    }
+
+    #[test]
+    fn test_invalid_section() {
+        let section = AbSection {
+            start: 2000,
+            end: 1000,
+        };
+        let request = ClipRequest::new(
+            Path::new("movie.mkv"),
+            &std::env::temp_dir(),
+            section,
+            ClipFormat::Video(ClipMode::Remux),
+        );
+        let mut progress = Progress::new(section, |_| {});
+
+        assert_eq!(
+            true,
+            export(&request, &AtomicBool::new(false), &mut progress).is_err()
+        );
+    }
+
+    /// Export from a generated clip at 25 fps, which has a key frame every second.
+    /// The clip is in its own temporary directory, which should be removed by the caller
+    /// # Arguments
+    /// * `name` - Distinguishes the directory of the test
+    /// * `seconds` - Duration of the generated clip
+    pub(super) fn export_generated(
+        name: &str,
+        seconds: i32,
+        section: AbSection,
+        format: ClipFormat,
+    ) -> ClipRequest {
+        let dir = std::env::temp_dir().join(format!("ntplayer_clip_test_{}", name));
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("source.mkv");
+        reencode::tests::generate_video(&source, seconds, 25).unwrap();
+
+        let request = ClipRequest::new(&source, &dir, section, format);
+        let mut progress = Progress::new(section, |_| {});
+        let exported = export(&request, &AtomicBool::new(false), &mut progress).unwrap();
+        assert_eq!(true, exported);
+
+        request
+    }
+
+    /// Export from a generated clip of 4 seconds, and probe the duration of exported clip
+    fn exported_duration(mode: ClipMode, section: AbSection) -> i64 {
+        let name = format!("{:?}", mode);
+        let request = export_generated(&name, 4, section, ClipFormat::Video(mode));
+        let duration = probe_duration(&request.output).unwrap();
+
+        fs::remove_dir_all(request.output.parent().unwrap()).unwrap();
+        duration
+    }
+
+    #[test]
+    fn test_remux() {
+        // The start point is on a key frame
+        let section = AbSection {
+            start: 1000,
+            end: 3000,
+        };
+        let duration = exported_duration(ClipMode::Remux, section);
+
+        assert!((duration - 2000).abs() <= 80, "duration: {}", duration);
+    }
+
+    #[test]
+    fn test_reencode() {
+        // The start point is between key frames, it's exact only if encoded again
+        let section = AbSection {
+            start: 1400,
+            end: 3000,
+        };
+        let duration = exported_duration(ClipMode::Reencode, section);
+
+        assert!((duration - 1600).abs() <= 80, "duration: {}", duration);
+    }
+
+    #[test]
+    fn test_cancel() {
+        let dir = std::env::temp_dir().join("ntplayer_clip_test_cancel");
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("source.mkv");
+        reencode::tests::generate_video(&source, 2, 25).unwrap();
+
+        let section = AbSection {
+            start: 0,
+            end: 2000,
+        };
+        let request = ClipRequest::new(&source, &dir, section, ClipFormat::Video(ClipMode::Remux));
+        let mut progress = Progress::new(section, |_| {});
+        let exported = export(&request, &AtomicBool::new(true), &mut progress).unwrap();
+        assert_eq!(false, exported);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
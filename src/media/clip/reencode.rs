@@ -0,0 +1,338 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use log::warn;
+use rsmpeg::{
+    avcodec::{AVCodec, AVCodecContext},
+    avformat::{AVFormatContextInput, AVFormatContextOutput},
+    avutil::AVFrame,
+    error::RsmpegError,
+    ffi::{
+        av_rescale_q, AVCodecID, AVMediaType_AVMEDIA_TYPE_VIDEO as AVMEDIATYPE_AVMEDIA_TYPE_VIDEO,
+        AVPictureType_AV_PICTURE_TYPE_NONE as AV_PICTURE_TYPE_NONE,
+        AVPixelFormat_AV_PIX_FMT_YUV420P as AVPIXELFORMAT_AV_PIX_FMT_YUV420P, AVRational,
+        AVFMT_GLOBALHEADER, AV_CODEC_FLAG_GLOBAL_HEADER, AV_DISPOSITION_ATTACHED_PIC,
+    },
+};
+
+use super::{
+    frame_timestamp, millis_to_ts, open_decoder,
+    remux::{copy_stream, is_copied, PacketCopier},
+    ts_to_millis, Progress,
+};
+use crate::{
+    media::ab_loop::AbSection,
+    util::{error::SuperError, pixel_format::convert_frame},
+};
+
+/// The frame rate of encoder if it's unknown in the stream
+const DEFAULT_FRAME_RATE: AVRational = AVRational { num: 25, den: 1 };
+/// Bits per pixel of the encoded video, it's about the quality of a DVD for mpeg4
+const BITS_PER_PIXEL: i64 = 4;
+
+/// Encode the video in the section again, so that it starts exactly at the start point.
+/// Other streams are copied, the input should have been sought
+/// # Returns
+/// `false` if it's canceled
+pub(super) fn reencode<F: FnMut(f32)>(
+    input: AVFormatContextInput,
+    output: AVFormatContextOutput,
+    section: AbSection,
+    cancel_flag: &AtomicBool,
+    progress: &mut Progress<F>,
+) -> Result<bool, SuperError> {
+    let mut input = input;
+    let mut output = output;
+
+    // The first video stream is encoded, the others are copied
+    let mut mapping = vec![];
+    let mut video: Option<VideoTranscoder> = None;
+    for stream in input.streams() {
+        let codecpar = stream.codecpar();
+        let is_cover = stream.disposition & AV_DISPOSITION_ATTACHED_PIC as i32 != 0;
+        if video.is_none() && codecpar.codec_type == AVMEDIATYPE_AVMEDIA_TYPE_VIDEO && !is_cover {
+            let decoder = open_decoder(&stream)?;
+            let frame_rate = [stream.avg_frame_rate, stream.r_frame_rate]
+                .into_iter()
+                .find(|r| r.num > 0 && r.den > 0)
+                .unwrap_or(DEFAULT_FRAME_RATE);
+            let encoder = open_video_encoder(
+                &output,
+                default_video_codec(&output),
+                codecpar.width,
+                codecpar.height,
+                frame_rate,
+                stream.sample_aspect_ratio,
+            )?;
+            let mut out_stream = output.new_stream();
+            out_stream.set_codecpar(encoder.extract_codecpar());
+            out_stream.set_time_base(encoder.time_base);
+
+            video = Some(VideoTranscoder {
+                index: stream.index,
+                out_index: out_stream.index as usize,
+                in_time_base: stream.time_base,
+                decoder,
+                encoder,
+                last_pts: None,
+                finished: false,
+            });
+            mapping.push(None);
+        } else if is_copied(&stream) {
+            mapping.push(Some(copy_stream(&mut output, &stream)));
+        } else {
+            mapping.push(None);
+        }
+    }
+    let mut video = video.ok_or("no video stream to encode")?;
+
+    // All the streams start at the start point, the same as the encoded video
+    let mut copier = PacketCopier::new(&input, mapping, section);
+    copier.set_offset(section.start);
+    output.write_header(&mut None)?;
+    copier.read_output_time_bases(&output);
+    let out_time_base = copier.out_time_base(video.out_index);
+
+    while let Some(packet) = input.read_packet()? {
+        if cancel_flag.load(Ordering::Acquire) {
+            return Ok(false);
+        }
+
+        if packet.stream_index == video.index {
+            if video.finished {
+                continue;
+            }
+            if let Err(err) = video.decoder.send_packet(Some(&packet)) {
+                warn!("send packet to context error: {}", err);
+                continue;
+            }
+            if let Some(millis) = video.transcode(&mut output, section, out_time_base)? {
+                progress.update(millis);
+            }
+        } else if let Some(millis) = copier.write(&mut output, packet)? {
+            progress.update(millis);
+        }
+
+        if video.finished && copier.is_finished() {
+            break;
+        }
+    }
+
+    // Take out the frames which are kept in decoder and encoder
+    if !video.finished {
+        video.decoder.send_packet(None)?;
+        video.transcode(&mut output, section, out_time_base)?;
+    }
+    encode(
+        &mut video.encoder,
+        None,
+        &mut output,
+        video.out_index,
+        out_time_base,
+    )?;
+    output.write_trailer()?;
+    progress.update(section.end);
+
+    Ok(true)
+}
+
+/// Decode the video stream and encode the frames in section
+struct VideoTranscoder {
+    /// Index of input stream
+    index: i32,
+    /// Index of output stream
+    out_index: usize,
+    in_time_base: AVRational,
+    decoder: AVCodecContext,
+    encoder: AVCodecContext,
+    /// The pts of last encoded frame, in the time base of encoder
+    last_pts: Option<i64>,
+    /// Whether the frames reach the end of section
+    finished: bool,
+}
+
+impl VideoTranscoder {
+    /// Encode all the decoded frames in the section
+    /// # Returns
+    /// The position of the last encoded frame in milliseconds
+    fn transcode(
+        &mut self,
+        output: &mut AVFormatContextOutput,
+        section: AbSection,
+        out_time_base: AVRational,
+    ) -> Result<Option<i64>, SuperError> {
+        let mut position = None;
+        loop {
+            let mut frame = match self.decoder.receive_frame() {
+                Ok(frame) => frame,
+                Err(RsmpegError::DecoderDrainError) | Err(RsmpegError::DecoderFlushedError) => {
+                    break
+                }
+                Err(err) => return Err(err.into()),
+            };
+
+            let ts = frame_timestamp(&frame);
+            let millis = ts_to_millis(ts, self.in_time_base);
+            // The frames before start point are only decoded for the later ones
+            if millis < section.start {
+                continue;
+            }
+            if millis >= section.end {
+                self.finished = true;
+                break;
+            }
+
+            // Shift to start from 0, and keep the pts increasing for the encoder
+            let start = millis_to_ts(section.start, self.in_time_base);
+            let time_base = self.encoder.time_base;
+            let mut pts = unsafe { av_rescale_q(ts - start, self.in_time_base, time_base) };
+            if let Some(last_pts) = self.last_pts {
+                pts = pts.max(last_pts + 1);
+            }
+            self.last_pts = Some(pts);
+
+            if frame.format != self.encoder.pix_fmt {
+                frame = convert_frame(&frame, self.encoder.pix_fmt)
+                    .ok_or("failed to convert the pixel format")?;
+            }
+            frame.set_pts(pts);
+            // The type of decoded frame may force the encoder to make a key frame
+            unsafe { (*frame.as_mut_ptr()).pict_type = AV_PICTURE_TYPE_NONE };
+            encode(
+                &mut self.encoder,
+                Some(&frame),
+                output,
+                self.out_index,
+                out_time_base,
+            )?;
+            position = Some(millis);
+        }
+
+        Ok(position)
+    }
+}
+
+/// The default video codec of the output container
+fn default_video_codec(output: &AVFormatContextOutput) -> AVCodecID {
+    unsafe { (*output.oformat).video_codec }
+}
+
+/// Open the encoder for the output container
+/// # Arguments
+/// * `frame_rate` - Frames per second, the time base of encoder is its reciprocal
+/// * `sar` - Sample aspect ratio
+pub(super) fn open_video_encoder(
+    output: &AVFormatContextOutput,
+    codec_id: AVCodecID,
+    width: i32,
+    height: i32,
+    frame_rate: AVRational,
+    sar: AVRational,
+) -> Result<AVCodecContext, SuperError> {
+    let codec = AVCodec::find_encoder(codec_id).ok_or("no video encoder")?;
+    // Use yuv420p if possible, which is supported by most players
+    let pix_fmt = match codec.pix_fmts() {
+        Some(formats) if !formats.contains(&AVPIXELFORMAT_AV_PIX_FMT_YUV420P) => formats[0],
+        _ => AVPIXELFORMAT_AV_PIX_FMT_YUV420P,
+    };
+
+    let mut encoder = AVCodecContext::new(&codec);
+    encoder.set_width(width);
+    encoder.set_height(height);
+    encoder.set_pix_fmt(pix_fmt);
+    encoder.set_sample_aspect_ratio(sar);
+    encoder.set_framerate(frame_rate);
+    encoder.set_time_base(AVRational {
+        num: frame_rate.den,
+        den: frame_rate.num,
+    });
+    // A key frame every second
+    encoder.set_gop_size((frame_rate.num / frame_rate.den).max(1));
+    encoder.set_bit_rate(width as i64 * height as i64 * BITS_PER_PIXEL);
+    // Some containers, such as mp4, need the codec headers in the container header
+    if unsafe { (*output.oformat).flags } & AVFMT_GLOBALHEADER as i32 != 0 {
+        encoder.set_flags(encoder.flags | AV_CODEC_FLAG_GLOBAL_HEADER as i32);
+    }
+    encoder.open(None)?;
+
+    Ok(encoder)
+}
+
+/// Send the frame to encoder and write all the encoded packets,
+/// `None` frame flushes the encoder
+pub(super) fn encode(
+    encoder: &mut AVCodecContext,
+    frame: Option<&AVFrame>,
+    output: &mut AVFormatContextOutput,
+    out_index: usize,
+    out_time_base: AVRational,
+) -> Result<(), SuperError> {
+    encoder.send_frame(frame)?;
+    loop {
+        let mut packet = match encoder.receive_packet() {
+            Ok(packet) => packet,
+            Err(RsmpegError::EncoderDrainError) | Err(RsmpegError::EncoderFlushedError) => break,
+            Err(err) => return Err(err.into()),
+        };
+
+        packet.rescale_ts(encoder.time_base, out_time_base);
+        packet.set_stream_index(out_index as i32);
+        output.interleaved_write_frame(&mut packet)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use std::{ffi::CString, path::Path, slice};
+
+    use rsmpeg::ffi::AVCodecID_AV_CODEC_ID_MPEG4 as AV_CODEC_ID_MPEG4;
+
+    use super::*;
+
+    /// Generate a small video whose brightness changes in every frame,
+    /// it has a key frame every second
+    pub fn generate_video(path: &Path, seconds: i32, fps: i32) -> Result<(), SuperError> {
+        let (width, height) = (64, 48);
+        let path = CString::new(path.to_str().unwrap())?;
+        let mut output = AVFormatContextOutput::create(&path, None)?;
+        let mut encoder = open_video_encoder(
+            &output,
+            AV_CODEC_ID_MPEG4,
+            width,
+            height,
+            AVRational { num: fps, den: 1 },
+            AVRational { num: 1, den: 1 },
+        )?;
+        {
+            let mut stream = output.new_stream();
+            stream.set_codecpar(encoder.extract_codecpar());
+            stream.set_time_base(encoder.time_base);
+        }
+        output.write_header(&mut None)?;
+        let out_time_base = output.streams().into_iter().next().unwrap().time_base;
+
+        for n in 0..seconds * fps {
+            let mut frame = AVFrame::new();
+            frame.set_width(width);
+            frame.set_height(height);
+            frame.set_format(AVPIXELFORMAT_AV_PIX_FMT_YUV420P);
+            frame.alloc_buffer()?;
+
+            // Luma changes with the frame number, chroma is neutral
+            for (plane, value) in [(0, (16 + n % 200) as u8), (1, 128), (2, 128)] {
+                let rows = if plane == 0 { height } else { height / 2 };
+                let size = (frame.linesize[plane] * rows) as usize;
+                let data = unsafe { slice::from_raw_parts_mut(frame.data[plane], size) };
+                data.fill(value);
+            }
+            frame.set_pts(n as i64);
+
+            encode(&mut encoder, Some(&frame), &mut output, 0, out_time_base)?;
+        }
+        encode(&mut encoder, None, &mut output, 0, out_time_base)?;
+        output.write_trailer()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,189 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use rsmpeg::{
+    avcodec::AVPacket,
+    avformat::{AVFormatContextInput, AVFormatContextOutput, AVStreamRef},
+    ffi::{
+        AVMediaType_AVMEDIA_TYPE_AUDIO as AVMEDIATYPE_AVMEDIA_TYPE_AUDIO,
+        AVMediaType_AVMEDIA_TYPE_SUBTITLE as AVMEDIATYPE_AVMEDIA_TYPE_SUBTITLE,
+        AVMediaType_AVMEDIA_TYPE_VIDEO as AVMEDIATYPE_AVMEDIA_TYPE_VIDEO, AVRational,
+        AV_DISPOSITION_ATTACHED_PIC, AV_NOPTS_VALUE,
+    },
+};
+
+use super::{millis_to_ts, ts_to_millis, Progress};
+use crate::{media::ab_loop::AbSection, util::error::SuperError};
+
+/// Copy the packets in the section to output, the input should have been sought
+/// # Returns
+/// `false` if it's canceled
+pub(super) fn remux<F: FnMut(f32)>(
+    input: AVFormatContextInput,
+    output: AVFormatContextOutput,
+    section: AbSection,
+    cancel_flag: &AtomicBool,
+    progress: &mut Progress<F>,
+) -> Result<bool, SuperError> {
+    let mut input = input;
+    let mut output = output;
+
+    // The index of output stream for each input stream, `None` means the stream is skipped
+    let mut mapping = vec![];
+    for stream in input.streams() {
+        mapping.push(if is_copied(&stream) {
+            Some(copy_stream(&mut output, &stream))
+        } else {
+            None
+        });
+    }
+    let mut copier = PacketCopier::new(&input, mapping, section);
+    output.write_header(&mut None)?;
+    copier.read_output_time_bases(&output);
+
+    while let Some(packet) = input.read_packet()? {
+        if cancel_flag.load(Ordering::Acquire) {
+            return Ok(false);
+        }
+
+        if let Some(millis) = copier.write(&mut output, packet)? {
+            progress.update(millis);
+        }
+        if copier.is_finished() {
+            break;
+        }
+    }
+    output.write_trailer()?;
+    progress.update(section.end);
+
+    Ok(true)
+}
+
+/// Whether the stream can be copied, data streams and covers are skipped
+pub(super) fn is_copied(stream: &AVStreamRef) -> bool {
+    let codec_type = stream.codecpar().codec_type;
+    let is_cover = stream.disposition & AV_DISPOSITION_ATTACHED_PIC as i32 != 0;
+
+    !is_cover
+        && (codec_type == AVMEDIATYPE_AVMEDIA_TYPE_VIDEO
+            || codec_type == AVMEDIATYPE_AVMEDIA_TYPE_AUDIO
+            || codec_type == AVMEDIATYPE_AVMEDIA_TYPE_SUBTITLE)
+}
+
+/// Create an output stream with the same parameters as the input one
+/// # Returns
+/// The index of output stream
+pub(super) fn copy_stream(output: &mut AVFormatContextOutput, stream: &AVStreamRef) -> usize {
+    let mut out_stream = output.new_stream();
+    out_stream.set_codecpar(stream.codecpar().clone());
+    // The tag of input container may be invalid in output container, let the muxer choose
+    out_stream.codecpar_mut().set_codec_tag(0);
+    out_stream.set_time_base(stream.time_base);
+
+    out_stream.index as usize
+}
+
+/// Write the copied packets to output, their timestamps are shifted to start from 0
+pub(super) struct PacketCopier {
+    section: AbSection,
+    /// The index of output stream for each input stream
+    mapping: Vec<Option<usize>>,
+    in_time_bases: Vec<AVRational>,
+    /// Time bases of output streams, they may be changed by the muxer while writing header
+    out_time_bases: Vec<AVRational>,
+    /// The timestamp all the packets are shifted by, unit: milliseconds
+    offset: Option<i64>,
+    /// Whether the packets of each input stream reach the end of section
+    finished: Vec<bool>,
+}
+
+impl PacketCopier {
+    pub(super) fn new(
+        input: &AVFormatContextInput,
+        mapping: Vec<Option<usize>>,
+        section: AbSection,
+    ) -> Self {
+        let in_time_bases = input.streams().into_iter().map(|s| s.time_base).collect();
+        // The skipped streams are taken as finished
+        let finished = mapping.iter().map(|m| m.is_none()).collect();
+
+        Self {
+            section,
+            mapping,
+            in_time_bases,
+            out_time_bases: vec![],
+            offset: None,
+            finished,
+        }
+    }
+
+    /// It should be called after the header is written
+    pub(super) fn read_output_time_bases(&mut self, output: &AVFormatContextOutput) {
+        self.out_time_bases = output.streams().into_iter().map(|s| s.time_base).collect();
+    }
+
+    /// The time base of output stream, it should be called after the header is written
+    pub(super) fn out_time_base(&self, out_index: usize) -> AVRational {
+        self.out_time_bases[out_index]
+    }
+
+    /// Shift the timestamps by the specified position instead of the first packet
+    pub(super) fn set_offset(&mut self, millis: i64) {
+        self.offset = Some(millis);
+    }
+
+    /// Whether all the copied streams reach the end of section
+    pub(super) fn is_finished(&self) -> bool {
+        self.finished.iter().all(|f| *f)
+    }
+
+    /// Write the packet if it's in a copied stream and not after the end of section
+    /// # Returns
+    /// The position of packet in milliseconds, `None` if it's not written
+    pub(super) fn write(
+        &mut self,
+        output: &mut AVFormatContextOutput,
+        packet: AVPacket,
+    ) -> Result<Option<i64>, SuperError> {
+        let mut packet = packet;
+        let index = packet.stream_index as usize;
+        let out_index = match self.mapping.get(index).copied().flatten() {
+            Some(out_index) => out_index,
+            None => return Ok(None),
+        };
+
+        let in_time_base = self.in_time_bases[index];
+        let ts = if packet.dts != AV_NOPTS_VALUE {
+            packet.dts
+        } else {
+            packet.pts
+        };
+        if ts == AV_NOPTS_VALUE {
+            return Ok(None);
+        }
+        let millis = ts_to_millis(ts, in_time_base);
+        if millis >= self.section.end {
+            self.finished[index] = true;
+            return Ok(None);
+        }
+
+        // Packets before the first one are dropped, they cannot have negative timestamps
+        let offset = *self.offset.get_or_insert(millis);
+        if millis < offset {
+            return Ok(None);
+        }
+        let shift = millis_to_ts(offset, in_time_base);
+        if packet.pts != AV_NOPTS_VALUE {
+            packet.set_pts(packet.pts - shift);
+        }
+        if packet.dts != AV_NOPTS_VALUE {
+            packet.set_dts(packet.dts - shift);
+        }
+
+        packet.rescale_ts(in_time_base, self.out_time_bases[out_index]);
+        packet.set_stream_index(out_index as i32);
+        packet.set_pos(-1);
+        output.interleaved_write_frame(&mut packet)?;
+
+        Ok(Some(millis))
+    }
+}
@@ -0,0 +1,448 @@
+use std::{
+    fs,
+    ops::Range,
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use log::warn;
+use rsmpeg::{
+    avcodec::{AVCodec, AVCodecContext},
+    avformat::{AVFormatContextInput, AVFormatContextOutput},
+    avutil::AVFrame,
+    error::RsmpegError,
+    ffi::{
+        AVCodecID, AVCodecID_AV_CODEC_ID_GIF as AV_CODEC_ID_GIF,
+        AVCodecID_AV_CODEC_ID_PNG as AV_CODEC_ID_PNG,
+        AVMediaType_AVMEDIA_TYPE_VIDEO as AVMEDIATYPE_AVMEDIA_TYPE_VIDEO,
+        AVPixelFormat_AV_PIX_FMT_PAL8 as AVPIXELFORMAT_AV_PIX_FMT_PAL8,
+        AVPixelFormat_AV_PIX_FMT_RGB24 as AVPIXELFORMAT_AV_PIX_FMT_RGB24, AVRational,
+        AV_DISPOSITION_ATTACHED_PIC,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    create_output, frame_timestamp, open_decoder, reencode::encode, ts_to_millis, Progress,
+};
+use crate::{
+    media::{ab_loop::AbSection, filter::VideoFilterGraph},
+    util::{error::SuperError, pixel_format::scale_frame},
+};
+
+/// Generate the palette from all the frames, then map the frames to it,
+/// which looks much better than the fixed palette of gif encoder
+const PALETTE_FILTERS: &str =
+    "split[a][b];[a]palettegen=stats_mode=full[p];[b][p]paletteuse=dither=sierra2_4a";
+/// File names of pictures in the directory, they are numbered from 1
+const PICTURE_PATTERN: &str = "%05d.png";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceFormat {
+    /// An animated gif which loops forever
+    Gif,
+    /// Numbered png pictures in a directory
+    Png,
+}
+
+/// Options of exporting a range as pictures, which are persisted in settings
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct SequenceOptions {
+    pub format: SequenceFormat,
+    /// Frames per second of output
+    pub fps: u32,
+    /// Width of output, the height keeps the aspect ratio.
+    /// 0 keeps the width of source
+    pub width: u32,
+}
+
+impl Default for SequenceOptions {
+    fn default() -> Self {
+        Self {
+            format: SequenceFormat::Gif,
+            fps: 10,
+            width: 480,
+        }
+    }
+}
+
+/// Export the frames in section as pictures, the input should have been sought
+/// # Arguments
+/// * `output` - The gif file, or the directory of png pictures
+/// # Returns
+/// `false` if it's canceled
+pub(super) fn export<F: FnMut(f32)>(
+    input: AVFormatContextInput,
+    output: &Path,
+    section: AbSection,
+    options: SequenceOptions,
+    cancel_flag: &AtomicBool,
+    progress: &mut Progress<F>,
+) -> Result<bool, SuperError> {
+    let mut input = input;
+
+    let (index, in_time_base, mut decoder, size) = {
+        let stream = input
+            .streams()
+            .into_iter()
+            .find(|s| {
+                s.codecpar().codec_type == AVMEDIATYPE_AVMEDIA_TYPE_VIDEO
+                    && s.disposition & AV_DISPOSITION_ATTACHED_PIC as i32 == 0
+            })
+            .ok_or("no video stream to export")?;
+        let codecpar = stream.codecpar();
+        let size = output_size(
+            codecpar.width,
+            codecpar.height,
+            stream.sample_aspect_ratio,
+            options.width,
+        );
+
+        (stream.index, stream.time_base, open_decoder(&stream)?, size)
+    };
+
+    let (path, codec_id, pix_fmt) = match options.format {
+        SequenceFormat::Gif => (
+            output.to_path_buf(),
+            AV_CODEC_ID_GIF,
+            AVPIXELFORMAT_AV_PIX_FMT_PAL8,
+        ),
+        SequenceFormat::Png => {
+            fs::create_dir_all(output)?;
+            (
+                output.join(PICTURE_PATTERN),
+                AV_CODEC_ID_PNG,
+                AVPIXELFORMAT_AV_PIX_FMT_RGB24,
+            )
+        }
+    };
+    let fps = options.fps.max(1) as i32;
+    let mut writer = SequenceWriter::new(
+        create_output(&path)?,
+        codec_id,
+        pix_fmt,
+        size,
+        fps,
+        options.format == SequenceFormat::Gif,
+    )?;
+
+    let mut finished = false;
+    while let Some(packet) = input.read_packet()? {
+        if cancel_flag.load(Ordering::Acquire) {
+            return Ok(false);
+        }
+        if packet.stream_index != index {
+            continue;
+        }
+
+        if let Err(err) = decoder.send_packet(Some(&packet)) {
+            warn!("send packet to context error: {}", err);
+            continue;
+        }
+        finished = writer.receive(&mut decoder, in_time_base, section, progress)?;
+        if finished {
+            break;
+        }
+    }
+
+    // Take out the frames which are kept in decoder
+    if !finished {
+        decoder.send_packet(None)?;
+        writer.receive(&mut decoder, in_time_base, section, progress)?;
+    }
+    writer.finish()?;
+    progress.update(section.end);
+
+    Ok(true)
+}
+
+/// Scale, quantize and encode the sampled frames
+struct SequenceWriter {
+    output: AVFormatContextOutput,
+    encoder: AVCodecContext,
+    out_time_base: AVRational,
+    /// Size of pictures, `(width, height)`
+    size: (i32, i32),
+    sampler: FrameSampler,
+    /// Whether the frames are mapped to a generated palette
+    use_palette: bool,
+    /// The palette filters, it's created with the first frame
+    palette: Option<VideoFilterGraph>,
+}
+
+impl SequenceWriter {
+    fn new(
+        output: AVFormatContextOutput,
+        codec_id: AVCodecID,
+        pix_fmt: i32,
+        size: (i32, i32),
+        fps: i32,
+        use_palette: bool,
+    ) -> Result<Self, SuperError> {
+        let mut output = output;
+        let codec = AVCodec::find_encoder(codec_id).ok_or("no encoder for pictures")?;
+        let mut encoder = AVCodecContext::new(&codec);
+        encoder.set_width(size.0);
+        encoder.set_height(size.1);
+        encoder.set_pix_fmt(pix_fmt);
+        encoder.set_time_base(AVRational { num: 1, den: fps });
+        encoder.open(None)?;
+
+        {
+            let mut stream = output.new_stream();
+            stream.set_codecpar(encoder.extract_codecpar());
+            stream.set_time_base(encoder.time_base);
+        }
+        output.write_header(&mut None)?;
+        // The muxer may change the time base while writing header
+        let out_time_base = output
+            .streams()
+            .into_iter()
+            .next()
+            .ok_or("output stream is not found")?
+            .time_base;
+
+        Ok(Self {
+            output,
+            encoder,
+            out_time_base,
+            size,
+            sampler: FrameSampler::new(fps),
+            use_palette,
+            palette: None,
+        })
+    }
+
+    /// Take out the decoded frames and write the ones in section
+    /// # Returns
+    /// Whether the frames reach the end of section
+    fn receive<F: FnMut(f32)>(
+        &mut self,
+        decoder: &mut AVCodecContext,
+        in_time_base: AVRational,
+        section: AbSection,
+        progress: &mut Progress<F>,
+    ) -> Result<bool, SuperError> {
+        loop {
+            let frame = match decoder.receive_frame() {
+                Ok(frame) => frame,
+                Err(RsmpegError::DecoderDrainError) | Err(RsmpegError::DecoderFlushedError) => {
+                    return Ok(false)
+                }
+                Err(err) => return Err(err.into()),
+            };
+
+            let millis = ts_to_millis(frame_timestamp(&frame), in_time_base);
+            if millis < section.start {
+                continue;
+            }
+            if millis >= section.end {
+                return Ok(true);
+            }
+
+            // A frame is repeated if the output frame rate is higher than source
+            for number in self.sampler.take(millis - section.start) {
+                let (width, height) = self.size;
+                let mut picture =
+                    scale_frame(&frame, width, height, AVPIXELFORMAT_AV_PIX_FMT_RGB24)
+                        .ok_or("failed to scale the frame")?;
+                picture.set_pts(number);
+
+                if self.use_palette {
+                    if self.palette.is_none() {
+                        let time_base = (1, self.encoder.time_base.den);
+                        let graph = VideoFilterGraph::new(PALETTE_FILTERS, &picture, time_base)?;
+                        self.palette = Some(graph);
+                    }
+                    // The palette is generated at the end, nothing is output until then
+                    let frames = self.palette.as_mut().unwrap().filter(picture)?;
+                    for frame in frames {
+                        self.write(Some(&frame))?;
+                    }
+                } else {
+                    self.write(Some(&picture))?;
+                }
+            }
+            progress.update(millis);
+        }
+    }
+
+    /// Flush the filters and encoder, then close the output
+    fn finish(mut self) -> Result<(), SuperError> {
+        if let Some(mut palette) = self.palette.take() {
+            for frame in palette.flush()? {
+                self.write(Some(&frame))?;
+            }
+        }
+        self.write(None)?;
+        self.output.write_trailer()?;
+
+        Ok(())
+    }
+
+    fn write(&mut self, frame: Option<&AVFrame>) -> Result<(), SuperError> {
+        encode(
+            &mut self.encoder,
+            frame,
+            &mut self.output,
+            0,
+            self.out_time_base,
+        )
+    }
+}
+
+/// Pick the frames for the output frame rate,
+/// each output frame takes the first source frame at or after its time
+struct FrameSampler {
+    fps: i64,
+    /// Number of the next output frame
+    next: i64,
+}
+
+impl FrameSampler {
+    fn new(fps: i32) -> Self {
+        Self {
+            fps: fps.max(1) as i64,
+            next: 0,
+        }
+    }
+
+    /// The numbers of output frames which take the source frame
+    /// # Arguments
+    /// * `millis` - Time of source frame from the start of output
+    fn take(&mut self, millis: i64) -> Range<i64> {
+        let first = self.next;
+        // Output frame n is at n / fps seconds
+        while self.next * 1000 <= millis * self.fps {
+            self.next += 1;
+        }
+
+        first..self.next
+    }
+}
+
+/// The size of pictures, the display aspect ratio of source is kept
+/// # Arguments
+/// * `sar` - Sample aspect ratio of source
+/// * `width` - The expected width, 0 means the display width of source
+fn output_size(src_width: i32, src_height: i32, sar: AVRational, width: u32) -> (i32, i32) {
+    let display_width = if sar.num > 0 && sar.den > 0 {
+        (src_width as i64 * sar.num as i64 / sar.den as i64) as i32
+    } else {
+        src_width
+    }
+    .max(1);
+    let width = if width == 0 {
+        display_width
+    } else {
+        width as i32
+    };
+    let height = (width as f64 * src_height as f64 / display_width as f64).round() as i32;
+
+    (width, height.max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use sdl2::{image::LoadSurface, surface::Surface};
+
+    use super::*;
+    use crate::media::{
+        clip::{tests::export_generated, ClipFormat, ClipRequest},
+        decoder::MediaDecoder,
+    };
+
+    #[test]
+    fn test_output_size() {
+        let square = AVRational { num: 1, den: 1 };
+        assert_eq!((32, 24), output_size(64, 48, square, 32));
+        assert_eq!((64, 48), output_size(64, 48, square, 0));
+        assert_eq!(
+            (64, 48),
+            output_size(64, 48, AVRational { num: 0, den: 1 }, 0)
+        );
+
+        // PAL 16:9, whose pixels are wider than high
+        let wide = AVRational { num: 64, den: 45 };
+        assert_eq!((1024, 576), output_size(720, 576, wide, 0));
+        assert_eq!((512, 288), output_size(720, 576, wide, 512));
+    }
+
+    #[test]
+    fn test_sampler_drops_frames() {
+        // 25 fps to 10 fps
+        let mut sampler = FrameSampler::new(10);
+        let count: usize = (0..25).map(|n| sampler.take(n * 40).count()).sum();
+        assert_eq!(10, count);
+
+        let mut sampler = FrameSampler::new(10);
+        assert_eq!(0..1, sampler.take(0));
+        assert_eq!(1..1, sampler.take(40));
+        assert_eq!(1..1, sampler.take(80));
+        assert_eq!(1..2, sampler.take(120));
+    }
+
+    #[test]
+    fn test_sampler_repeats_frames() {
+        // 5 fps to 10 fps
+        let mut sampler = FrameSampler::new(10);
+        assert_eq!(0..1, sampler.take(0));
+        assert_eq!(1..3, sampler.take(200));
+        assert_eq!(3..5, sampler.take(400));
+    }
+
+    /// Export a second from a generated clip of 2 seconds at 10 fps, 32 pixels wide
+    fn export_sequence(format: SequenceFormat) -> ClipRequest {
+        let section = AbSection {
+            start: 500,
+            end: 1500,
+        };
+        let options = SequenceOptions {
+            format,
+            fps: 10,
+            width: 32,
+        };
+        let name = format!("sequence_{:?}", format);
+
+        export_generated(&name, 2, section, ClipFormat::Sequence(options))
+    }
+
+    #[test]
+    fn test_export_gif() {
+        let request = export_sequence(SequenceFormat::Gif);
+
+        let path = request.output.to_str().unwrap();
+        let mut input = MediaDecoder::get_media_context(path).unwrap();
+        let (width, height) = {
+            let stream = input.streams().into_iter().next().unwrap();
+            let codecpar = stream.codecpar();
+            (codecpar.width, codecpar.height)
+        };
+        let mut frames = 0;
+        while input.read_packet().unwrap().is_some() {
+            frames += 1;
+        }
+
+        assert_eq!((32, 24), (width, height));
+        assert_eq!(10, frames);
+        fs::remove_dir_all(request.output.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_export_png() {
+        let request = export_sequence(SequenceFormat::Png);
+
+        let mut pictures: Vec<_> = fs::read_dir(&request.output)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        pictures.sort();
+        assert_eq!(10, pictures.len());
+        assert_eq!(request.output.join("00001.png"), pictures[0]);
+
+        let surface = Surface::from_file(&pictures[0]).unwrap();
+        assert_eq!((32, 24), surface.size());
+        fs::remove_dir_all(request.output.parent().unwrap()).unwrap();
+    }
+}
@@ -1,13 +1,15 @@
 use std::{
+    collections::BTreeMap,
     error::Error,
-    ffi::CString,
+    ffi::{CStr, CString},
     ops::{Deref, DerefMut},
+    ptr,
     sync::{
         atomic::{AtomicBool, AtomicI64, Ordering},
         Arc,
     },
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use crossbeam::queue::ArrayQueue;
@@ -16,34 +18,60 @@ use rsmpeg::{
     avcodec::{AVCodec, AVCodecContext, AVPacket},
     avformat::AVFormatContextInput,
     ffi::{
-        av_seek_frame, AVFormatContext,
+        av_dict_get, av_display_rotation_get, av_packet_ref, av_seek_frame,
+        av_stream_get_side_data, AVDictionary, AVFormatContext,
         AVMediaType_AVMEDIA_TYPE_ATTACHMENT as AVMEDIATYPE_AVMEDIA_TYPE_ATTACHMENT,
         AVMediaType_AVMEDIA_TYPE_AUDIO as AVMEDIATYPE_AVMEDIA_TYPE_AUDIO,
         AVMediaType_AVMEDIA_TYPE_DATA as AVMEDIATYPE_AVMEDIA_TYPE_DATA,
         AVMediaType_AVMEDIA_TYPE_NB as AVMEDIATYPE_AVMEDIA_TYPE_NB,
         AVMediaType_AVMEDIA_TYPE_SUBTITLE as AVMEDIATYPE_AVMEDIA_TYPE_SUBTITLE,
-        AVMediaType_AVMEDIA_TYPE_VIDEO as AVMEDIATYPE_AVMEDIA_TYPE_VIDEO, AVSEEK_FLAG_FRAME,
+        AVMediaType_AVMEDIA_TYPE_VIDEO as AVMEDIATYPE_AVMEDIA_TYPE_VIDEO, AVPacket as RawAVPacket,
+        AVPacketSideDataType_AV_PKT_DATA_DISPLAYMATRIX as AV_PKT_DATA_DISPLAYMATRIX, AVStream,
+        AVSEEK_FLAG_BACKWARD, AV_DICT_IGNORE_SUFFIX, AV_DISPOSITION_ATTACHED_PIC, AV_TIME_BASE,
     },
 };
 
 use crate::{
     entity::EventMessage,
-    util::{error::safe_send, pixel_format::parse_video_frame, sample_format},
+    media::{
+        chapter::{self, Chapter},
+        filter::VideoFilter,
+    },
+    util::{
+        error::safe_send,
+        pixel_format::{parse_picture, parse_video_frame},
+        sample_format,
+    },
     {
-        AUDIO_BUFFER, AUDIO_SUMMARY, EVENT_CHANNEL, SUBTITLE_BUFFER, SUBTITLE_SUMMARY,
-        VIDEO_BUFFER, VIDEO_SUMMARY,
+        AUDIO_BUFFER, AUDIO_SUMMARY, EVENT_CHANNEL, MEDIA_SUMMARY, PLAYBACK_STATS, SETTINGS,
+        SUBTITLE_BUFFER, SUBTITLE_SUMMARY, VIDEO_BUFFER, VIDEO_SUMMARY,
     },
 };
 
 /// The wait duration if buffer queues are full
 const BUFFER_FULL_SLEEP_DURATION: Duration = Duration::from_millis(200);
-/// The maximum number of frames that will be dropped after seek
-const MAX_SKIP_FRAMES: u8 = 5;
+/// The play interval of video if it cannot be computed from stream, unit: milliseconds
+const DEFAULT_VIDEO_INTERVAL: u64 = 40;
+/// The play interval of audio if it cannot be computed from stream, unit: milliseconds
+const DEFAULT_AUDIO_INTERVAL: u64 = 20;
+
+/// How to seek, see `MediaDecoder::seek`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeekMode {
+    /// Show from the key frame before the position, it's fast enough for scrubbing
+    Keyframe,
+    /// Decode from the key frame before the position,
+    /// and drop the frames until the position is reached
+    Accurate,
+}
 
 pub struct MediaDecoder {
     stop_flag: Arc<AtomicBool>,
     audio_seek_to: Arc<AtomicI64>,
     video_seek_to: Arc<AtomicI64>,
+    /// The frames before this position are dropped after seek, unit: milliseconds.
+    /// -1 means the decoding starts from the key frame
+    seek_target: Arc<AtomicI64>,
 }
 
 impl MediaDecoder {
@@ -51,36 +79,57 @@ impl MediaDecoder {
         let stop_flag = Arc::new(AtomicBool::new(false));
         let audio_seek_to = Arc::new(AtomicI64::new(-1));
         let video_seek_to = Arc::new(AtomicI64::new(-1));
+        let seek_target = Arc::new(AtomicI64::new(-1));
 
         let ctx = MediaDecoder::get_media_context(&path)?;
+        Self::save_media_summary(&ctx);
         let streams = Self::get_streams(&ctx);
 
-        Self::start_task(ctx, streams, &stop_flag, &audio_seek_to, &video_seek_to);
+        Self::start_task(
+            ctx,
+            streams,
+            &stop_flag,
+            &audio_seek_to,
+            &video_seek_to,
+            &seek_target,
+        );
 
         Ok(Self {
             stop_flag,
             video_seek_to: video_seek_to.clone(),
             audio_seek_to: audio_seek_to.clone(),
+            seek_target,
         })
     }
 
-    /// Seek to the specified position
+    /// Seek to the specified position accurately
     /// `position` is the position to seek to, unit: milliseconds
     pub fn seek_to(&mut self, position: i64) {
-        let mut position = position;
-        if position < 0 {
-            position = 0;
-        }
+        self.seek(position, SeekMode::Accurate);
+    }
+
+    /// Seek to the specified position, unit: milliseconds.
+    /// The request is handled by the decoding thread, if another one comes before that,
+    /// only the latest one is handled
+    pub fn seek(&mut self, position: i64, mode: SeekMode) {
+        let position = position.max(0);
 
         let vr = VIDEO_SUMMARY.read().unwrap();
         let ar = AUDIO_SUMMARY.read().unwrap();
 
+        // The target is set before the position, so that it's ready while the seek is handled
+        let target = match mode {
+            SeekMode::Keyframe => -1,
+            SeekMode::Accurate => position,
+        };
+        self.seek_target.store(target, Ordering::Release);
+
         if let Some(summary) = ar.as_ref() {
-            let start = position / 1000 * summary.timebase_inverse as i64;
+            let start = millis_to_timestamp(position, summary.timebase_num, summary.timebase_den);
             self.audio_seek_to.store(start, Ordering::Release);
         }
         if let Some(summary) = vr.as_ref() {
-            let start = position / 1000 * summary.timebase_inverse as i64;
+            let start = millis_to_timestamp(position, summary.timebase_num, summary.timebase_den);
             self.video_seek_to.store(start, Ordering::Release);
         }
     }
@@ -95,10 +144,12 @@ impl MediaDecoder {
         stop_flag: &Arc<AtomicBool>,
         audio_seek_to: &Arc<AtomicI64>,
         video_seek_to: &Arc<AtomicI64>,
+        seek_target: &Arc<AtomicI64>,
     ) {
         let stop_flag = stop_flag.clone();
         let audio_seek_to = audio_seek_to.clone();
         let video_seek_to = video_seek_to.clone();
+        let seek_target = seek_target.clone();
         let sender = &EVENT_CHANNEL.0;
         thread::spawn({
             move || {
@@ -112,10 +163,12 @@ impl MediaDecoder {
                 let mut ctx = ctx;
                 // The pointer of AVFormatContext
                 let ctx_ptr = ctx.as_mut_ptr();
-                // The number of audio frames that have bee dropped after seek
-                let mut audio_dropped_frames = u8::MAX;
-                // The number of Video frames that have been dropped after seek
-                let mut video_dropped_frames = u8::MAX;
+                // The audio frames before this position are dropped after seek, -1 means none
+                let mut audio_skip_until = -1;
+                // The video frames before this position are dropped after seek, -1 means none
+                let mut video_skip_until = -1;
+                // Filters between decoding and rendering, such as deinterlacing
+                let mut video_filter = VideoFilter::new();
 
                 loop {
                     if stop_flag.load(std::sync::atomic::Ordering::SeqCst) {
@@ -133,9 +186,13 @@ impl MediaDecoder {
                     };
 
                     if seeked {
-                        // Set the skip number to 0 to indicate some frames need to be dropped later
-                        audio_dropped_frames = 0;
-                        video_dropped_frames = 0;
+                        // The decoding starts from the key frame before the position,
+                        // drop the frames before the position if it's an accurate seek
+                        let target = seek_target.load(Ordering::Acquire);
+                        audio_skip_until = target;
+                        video_skip_until = target;
+                        // Frames buffered in filters are out of date
+                        video_filter.reset();
                         // Clear old data
                         Self::clear_buffer();
                         // Send seek finish status
@@ -160,7 +217,7 @@ impl MediaDecoder {
                                         let dctx = Self::decode_audio(
                                             dctx,
                                             &packet,
-                                            &mut audio_dropped_frames,
+                                            &mut audio_skip_until,
                                         );
                                         Some(dctx)
                                     })
@@ -175,7 +232,8 @@ impl MediaDecoder {
                                         let dctx = Self::decode_video(
                                             dctx,
                                             &packet,
-                                            &mut video_dropped_frames,
+                                            &mut video_skip_until,
+                                            &mut video_filter,
                                         );
                                         Some(dctx)
                                     })
@@ -211,14 +269,15 @@ impl MediaDecoder {
         seek_to: &Arc<AtomicI64>,
         stream_index: i32,
     ) -> bool {
-        let position = seek_to.load(Ordering::Acquire);
+        // Take the latest request, the superseded ones are dropped while dragging.
+        // A request that comes during the seek is kept for the next loop
+        let position = seek_to.swap(-1, Ordering::AcqRel);
         if position < 0 {
             return false;
         }
 
-        unsafe { av_seek_frame(ctx_ptr, stream_index, position, AVSEEK_FLAG_FRAME as i32) };
-
-        seek_to.store(-1, Ordering::Release);
+        // Seek to the key frame before the position, the later frames can be decoded from it
+        unsafe { av_seek_frame(ctx_ptr, stream_index, position, AVSEEK_FLAG_BACKWARD as i32) };
 
         return true;
     }
@@ -227,7 +286,7 @@ impl MediaDecoder {
     fn decode_audio(
         dctx: AVCodecContext,
         packet: &AVPacket,
-        dropped_frames: &mut u8,
+        skip_until: &mut i64,
     ) -> AVCodecContext {
         let mut dctx = dctx;
         if let Err(err) = dctx.send_packet(Some(packet)) {
@@ -237,19 +296,12 @@ impl MediaDecoder {
 
         match dctx.receive_frame() {
             Ok(mut frame) => {
-                // If value of SKIPPED_FRAMES is smaller than MAX_SKIP_FRAMES,
-                // seek action is did a moment before, current frame may not be the correct one,
-                // drop some frames to get the correct one.
-                // Once dropped frames reached the max number, stop dropping.
-                if *dropped_frames < MAX_SKIP_FRAMES {
-                    *dropped_frames += 1;
+                let mut audio_frame = sample_format::parse_audio_frame(&mut frame);
+                // An accurate seek was done a moment before, drop the frames before the position
+                if Self::is_skipped(audio_frame.pts_millis, skip_until) {
                     return dctx;
-                } else {
-                    *dropped_frames = u8::MAX;
                 }
 
-                let mut audio_frame = sample_format::parse_audio_frame(&mut frame);
-
                 // Push frame to buffer until succeeded
                 while let Err(f) = AUDIO_BUFFER.push(audio_frame) {
                     audio_frame = f;
@@ -268,9 +320,11 @@ impl MediaDecoder {
     fn decode_video(
         dctx: AVCodecContext,
         packet: &AVPacket,
-        dropped_frames: &mut u8,
+        skip_until: &mut i64,
+        filter: &mut VideoFilter,
     ) -> AVCodecContext {
         let mut dctx = dctx;
+        let decode_start = Instant::now();
         if let Err(err) = dctx.send_packet(Some(packet)) {
             error!("send packet to context error: {}", err);
             return dctx;
@@ -278,22 +332,30 @@ impl MediaDecoder {
 
         match dctx.receive_frame() {
             Ok(frame) => {
-                // If value of SKIPPED_FRAMES is smaller than MAX_SKIP_FRAMES,
-                // seek action is did a moment before, current frame may not be the correct one,
-                // drop some frames to get the correct one.
-                // Once dropped frames reached the max number, stop dropping.
-                if *dropped_frames < MAX_SKIP_FRAMES {
-                    *dropped_frames += 1;
-                    return dctx;
-                } else {
-                    *dropped_frames = u8::MAX;
-                }
+                PLAYBACK_STATS.record_decoded(decode_start.elapsed());
+                // Settings are read for every frame, so that filters can be changed while playing
+                let settings = SETTINGS.read().unwrap().video_filter.clone();
+                let time_base = VIDEO_SUMMARY
+                    .read()
+                    .unwrap()
+                    .as_ref()
+                    .map_or((1, AV_TIME_BASE as i32), |s| {
+                        (s.timebase_num as i32, s.timebase_den as i32)
+                    });
 
-                let mut vf = parse_video_frame(&frame);
-                // Push frame to buffer until succeeded
-                while let Err(f) = VIDEO_BUFFER.push(vf) {
-                    vf = f;
-                    thread::sleep(BUFFER_FULL_SLEEP_DURATION);
+                // All the frames pass the filters, since some filters depend on previous frames
+                for frame in filter.process(frame, &settings, time_base) {
+                    let mut vf = parse_video_frame(&frame);
+                    // An accurate seek was done a moment before, drop the frames before the position
+                    if Self::is_skipped(vf.pts_millis, skip_until) {
+                        PLAYBACK_STATS.record_skipped(1);
+                        continue;
+                    }
+                    // Push frame to buffer until succeeded
+                    while let Err(f) = VIDEO_BUFFER.push(vf) {
+                        vf = f;
+                        thread::sleep(BUFFER_FULL_SLEEP_DURATION);
+                    }
                 }
             }
             Err(err) => {
@@ -304,6 +366,17 @@ impl MediaDecoder {
         dctx
     }
 
+    /// Whether the frame is before the position of accurate seek,
+    /// the position is cleared once it's reached
+    fn is_skipped(pts_millis: i64, skip_until: &mut i64) -> bool {
+        if pts_millis < *skip_until {
+            return true;
+        }
+
+        *skip_until = -1;
+        false
+    }
+
     /// Notice! DemuxerWithStreamInfo do not support multiple threads, so you have to create
     /// a new object for every thread which `DemuxerWithStreamInfo` will be used
     pub fn get_media_context(path: &str) -> Result<AVFormatContextInput, Box<dyn Error>> {
@@ -313,9 +386,141 @@ impl MediaDecoder {
         Ok(ctx)
     }
 
+    /// Read the container level information, such as duration and metadata tags
+    fn save_media_summary(ctx: &AVFormatContextInput) {
+        let mut tags = BTreeMap::new();
+        Self::read_tags(ctx.metadata, &mut tags);
+        // Some formats keep tags in the stream instead of container, e.g. ogg
+        for stream in ctx.streams() {
+            if stream.codecpar().codec_type == AVMEDIATYPE_AVMEDIA_TYPE_AUDIO {
+                Self::read_tags(stream.metadata, &mut tags);
+            }
+        }
+
+        let duration_millis = if ctx.duration > 0 {
+            ctx.duration as u64 * 1000 / AV_TIME_BASE as u64
+        } else {
+            0
+        };
+        let format_name = if ctx.iformat.is_null() {
+            String::default()
+        } else {
+            unsafe { CStr::from_ptr((*ctx.iformat).name) }
+                .to_string_lossy()
+                .to_string()
+        };
+
+        let summary = MediaSummary {
+            duration_millis,
+            format_name,
+            title: tags.get("title").cloned(),
+            artist: tags.get("artist").cloned(),
+            album: tags.get("album").cloned(),
+            tags,
+            chapters: chapter::read_chapters(ctx),
+        };
+
+        // Save media summary to static
+        let mut w = MEDIA_SUMMARY.write().unwrap();
+        *w = Some(summary);
+    }
+
+    /// Read all the entries of metadata dictionary, the keys are converted to lower case.
+    /// Existing keys are not overwritten.
+    pub(super) fn read_tags(dict: *mut AVDictionary, tags: &mut BTreeMap<String, String>) {
+        if dict.is_null() {
+            return;
+        }
+
+        let mut entry = ptr::null_mut();
+        loop {
+            // Empty key with `AV_DICT_IGNORE_SUFFIX` matches every entry
+            entry = unsafe {
+                av_dict_get(
+                    dict,
+                    b"\0".as_ptr() as _,
+                    entry,
+                    AV_DICT_IGNORE_SUFFIX as i32,
+                )
+            };
+            if entry.is_null() {
+                break;
+            }
+
+            let (key, value) = unsafe {
+                (
+                    CStr::from_ptr((*entry).key)
+                        .to_string_lossy()
+                        .to_lowercase(),
+                    CStr::from_ptr((*entry).value).to_string_lossy().to_string(),
+                )
+            };
+            tags.entry(key).or_insert(value);
+        }
+    }
+
+    /// Read the clockwise rotation of video stream in degrees, which is one of 0, 90, 180 and 270.
+    /// The display matrix is preferred, the "rotate" tag of old muxers is the fallback
+    fn read_rotation(stream: &AVStream) -> u32 {
+        let mut size = 0;
+        let matrix =
+            unsafe { av_stream_get_side_data(stream, AV_PKT_DATA_DISPLAYMATRIX, &mut size) };
+        // The display matrix has 9 elements of i32
+        let degrees = if !matrix.is_null() && size >= 9 * 4 {
+            // The matrix rotates counterclockwise
+            let angle = unsafe { av_display_rotation_get(matrix as *const i32) };
+            if angle.is_nan() {
+                0.0
+            } else {
+                -angle
+            }
+        } else {
+            let mut tags = BTreeMap::new();
+            Self::read_tags(stream.metadata, &mut tags);
+            tags.get("rotate")
+                .and_then(|r| r.trim().parse::<f64>().ok())
+                .unwrap_or(0.0)
+        };
+
+        ((degrees / 90.0).round() as i32 * 90).rem_euclid(360) as u32
+    }
+
+    /// Decode the attached picture of stream, which is usually the cover of audio media
+    fn decode_cover(dctx: AVCodecContext, picture: &RawAVPacket) -> Option<VideoFrame> {
+        let mut dctx = dctx;
+        let mut packet = AVPacket::new();
+        if unsafe { av_packet_ref(packet.as_mut_ptr(), picture) } < 0 {
+            error!("failed to reference attached picture");
+            return None;
+        }
+
+        if let Err(err) = dctx.send_packet(Some(&packet)) {
+            error!("send packet to context error: {}", err);
+            return None;
+        }
+
+        match dctx.receive_frame() {
+            Ok(frame) => parse_picture(&frame),
+            Err(err) => {
+                error!("{}", err);
+                None
+            }
+        }
+    }
+
     fn get_streams(ctx: &AVFormatContextInput) -> MediaStreams {
         let streams = ctx.streams();
 
+        // Clear the summaries of previous media, the media may not contain all types of streams
+        *AUDIO_SUMMARY.write().unwrap() = None;
+        *VIDEO_SUMMARY.write().unwrap() = None;
+        *SUBTITLE_SUMMARY.write().unwrap() = None;
+        let media_duration_millis = MEDIA_SUMMARY
+            .read()
+            .unwrap()
+            .as_ref()
+            .map_or(0, |s| s.duration_millis);
+
         let mut audio_stream = StreamInfo::default();
         let mut video_stream = StreamInfo::default();
         let mut subtitle_stream = StreamInfo::default();
@@ -325,10 +530,6 @@ impl MediaDecoder {
         let mut unknown_streams = Vec::<StreamInfo>::new();
 
         for stream in streams {
-            if stream.nb_frames <= 0 {
-                continue;
-            }
-
             let codecpar = stream.codecpar();
             let codec_type = stream.codecpar().codec_type;
 
@@ -347,18 +548,50 @@ impl MediaDecoder {
 
                 Some(decoder_ctx)
             });
+
+            // Attached picture is stored as a video stream with only one packet,
+            // it's the cover of audio media rather than a real video
+            if stream.disposition & AV_DISPOSITION_ATTACHED_PIC as i32 != 0 {
+                let cover =
+                    decoder_ctx.and_then(|dctx| Self::decode_cover(dctx, &stream.attached_pic));
+                if let Some(cover) = cover {
+                    safe_send(EVENT_CHANNEL.0.send(EventMessage::RenderCover(cover)));
+                }
+                continue;
+            }
+
             let stream_info = StreamInfo {
                 decoder_ctx,
                 index: Some(stream.index),
             };
 
-            let duration = stream.duration as u64;
-            let frames = stream.nb_frames as u64;
+            // Duration and number of frames are not always available,
+            // e.g. mp3 and flac files, compute them in other ways
+            let duration = stream.duration.max(0) as u64;
+            let frames = stream.nb_frames.max(0) as u64;
             let timebase_num = stream.time_base.num as u64;
             let timebase_den = stream.time_base.den as u64;
             let timebase_inverse = timebase_den / timebase_num;
-            let duration_millis = 1000 * duration / timebase_inverse;
-            let play_interval = duration_millis / frames;
+            let duration_millis = if duration > 0 {
+                1000 * duration / timebase_inverse
+            } else {
+                media_duration_millis
+            };
+            let play_interval = if frames > 0 {
+                duration_millis / frames
+            } else if codec_type == AVMEDIATYPE_AVMEDIA_TYPE_AUDIO
+                && codecpar.frame_size > 0
+                && codecpar.sample_rate > 0
+            {
+                1000 * codecpar.frame_size as u64 / codecpar.sample_rate as u64
+            } else if codec_type == AVMEDIATYPE_AVMEDIA_TYPE_VIDEO && stream.avg_frame_rate.num > 0
+            {
+                1000 * stream.avg_frame_rate.den as u64 / stream.avg_frame_rate.num as u64
+            } else if codec_type == AVMEDIATYPE_AVMEDIA_TYPE_AUDIO {
+                DEFAULT_AUDIO_INTERVAL
+            } else {
+                DEFAULT_VIDEO_INTERVAL
+            };
 
             match codec_type {
                 AVMEDIATYPE_AVMEDIA_TYPE_AUDIO => {
@@ -384,6 +617,12 @@ impl MediaDecoder {
                 }
                 AVMEDIATYPE_AVMEDIA_TYPE_VIDEO => {
                     video_stream = stream_info;
+                    // The stream level ratio overrides the codec level one, 0 means unknown
+                    let sample_aspect_ratio =
+                        [stream.sample_aspect_ratio, codecpar.sample_aspect_ratio]
+                            .into_iter()
+                            .find(|r| r.num > 0 && r.den > 0)
+                            .map_or((1, 1), |r| (r.num, r.den));
                     let video_summary = Some(VideoSummary {
                         decoder_name,
                         duration,
@@ -395,6 +634,9 @@ impl MediaDecoder {
                         play_interval,
                         width: codecpar.width as u32,
                         height: codecpar.height as u32,
+                        sample_aspect_ratio,
+                        rotation: Self::read_rotation(&stream),
+                        frame_rate: (stream.avg_frame_rate.num, stream.avg_frame_rate.den),
                     });
 
                     // Save video summary to static
@@ -440,9 +682,12 @@ impl MediaDecoder {
             AUDIO_BUFFER.pop();
         }
 
-        while !VIDEO_BUFFER.is_empty() {
-            VIDEO_BUFFER.pop();
+        // The buffered video frames will never be shown
+        let mut flushed = 0;
+        while VIDEO_BUFFER.pop().is_some() {
+            flushed += 1;
         }
+        PLAYBACK_STATS.record_skipped(flushed);
 
         while !SUBTITLE_BUFFER.is_empty() {
             SUBTITLE_BUFFER.pop();
@@ -472,6 +717,12 @@ pub struct VideoSummary {
     pub width: u32,
     /// Height of video
     pub height: u32,
+    /// The shape of pixels, `(numerator, denominator)`
+    pub sample_aspect_ratio: (i32, i32),
+    /// Clockwise rotation from the container in degrees, one of 0, 90, 180 and 270
+    pub rotation: u32,
+    /// Nominal frame rate, `(numerator, denominator)`, the numerator is 0 if it's unknown
+    pub frame_rate: (i32, i32),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -505,6 +756,42 @@ pub struct AudioSummary {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SubtitleSummary;
 
+/// Container level information of media
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MediaSummary {
+    /// Duration of whole media in milliseconds, 0 if unknown
+    pub duration_millis: u64,
+    /// Short name of the container format
+    pub format_name: String,
+    /// Title tag if any
+    pub title: Option<String>,
+    /// Artist tag if any
+    pub artist: Option<String>,
+    /// Album tag if any
+    pub album: Option<String>,
+    /// All the metadata tags, keys are in lower case
+    pub tags: BTreeMap<String, String>,
+    /// Chapters sorted by the start position, empty if there are none
+    pub chapters: Vec<Chapter>,
+}
+
+impl MediaSummary {
+    /// The text to display for the media,
+    /// formatted as "artist - title (album)", falls back to the specified name
+    pub fn display_title(&self, fallback: &str) -> String {
+        let mut text = match (self.artist.as_ref(), self.title.as_ref()) {
+            (Some(artist), Some(title)) => format!("{} - {}", artist, title),
+            (None, Some(title)) => title.clone(),
+            _ => fallback.to_string(),
+        };
+        if let Some(album) = self.album.as_ref() {
+            text = format!("{} ({})", text, album);
+        }
+
+        text
+    }
+}
+
 struct StreamInfo {
     decoder_ctx: Option<AVCodecContext>,
     index: Option<i32>,
@@ -646,3 +933,37 @@ impl DerefMut for SubtitleBuffer {
         &mut self.inner
     }
 }
+
+/// Convert the position in milliseconds to the timestamp in the time base of stream
+fn millis_to_timestamp(millis: i64, timebase_num: u64, timebase_den: u64) -> i64 {
+    if timebase_num == 0 {
+        return 0;
+    }
+
+    (millis as i128 * timebase_den as i128 / (1000 * timebase_num as i128)) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_millis_to_timestamp() {
+        // The milliseconds are kept, not only the seconds
+        assert_eq!(1_500, millis_to_timestamp(1_500, 1, 1000));
+        assert_eq!(135_000, millis_to_timestamp(1_500, 1, 90_000));
+        assert_eq!(44, millis_to_timestamp(1_500, 1001, 30_000));
+        assert_eq!(0, millis_to_timestamp(1_500, 0, 0));
+    }
+
+    #[test]
+    fn test_is_skipped() {
+        let mut skip_until = 2_000;
+        assert_eq!(true, MediaDecoder::is_skipped(1_960, &mut skip_until));
+        assert_eq!(false, MediaDecoder::is_skipped(2_000, &mut skip_until));
+
+        // Dropping stops once the position is reached
+        assert_eq!(-1, skip_until);
+        assert_eq!(false, MediaDecoder::is_skipped(1_960, &mut skip_until));
+    }
+}
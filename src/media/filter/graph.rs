@@ -0,0 +1,193 @@
+use std::ffi::{CStr, CString};
+
+use log::{error, info};
+use rsmpeg::{
+    avfilter::{AVFilter, AVFilterGraph, AVFilterInOut},
+    avutil::AVFrame,
+    error::RsmpegError,
+};
+
+use super::VideoFilterSettings;
+use crate::util::error::SuperError;
+
+/// Names of the source and sink of graph
+const SOURCE_NAME: &[u8] = b"in\0";
+const SINK_NAME: &[u8] = b"out\0";
+
+/// A configured libavfilter graph from `buffer` to `buffersink`
+pub struct VideoFilterGraph {
+    graph: AVFilterGraph,
+    /// The description of filters between source and sink
+    spec: String,
+    /// The frame parameters the graph is configured with, `(width, height, format)`
+    input: (i32, i32, i32),
+}
+
+impl VideoFilterGraph {
+    /// Create the graph for the frames which have the same parameters as `frame`
+    /// # Arguments
+    /// * `spec` - Description of filters, such as "yadif,format=yuv420p"
+    /// * `time_base` - Time base of the stream, `(numerator, denominator)`
+    pub fn new(spec: &str, frame: &AVFrame, time_base: (i32, i32)) -> Result<Self, SuperError> {
+        let source_name = CStr::from_bytes_with_nul(SOURCE_NAME)?;
+        let sink_name = CStr::from_bytes_with_nul(SINK_NAME)?;
+        let buffer = AVFilter::get_by_name(CStr::from_bytes_with_nul(b"buffer\0")?)
+            .ok_or("filter buffer is not found")?;
+        let buffersink = AVFilter::get_by_name(CStr::from_bytes_with_nul(b"buffersink\0")?)
+            .ok_or("filter buffersink is not found")?;
+
+        let sar = frame.sample_aspect_ratio;
+        let (sar_num, sar_den) = if sar.num > 0 && sar.den > 0 {
+            (sar.num, sar.den)
+        } else {
+            (1, 1)
+        };
+        let args = CString::new(format!(
+            "video_size={}x{}:pix_fmt={}:time_base={}/{}:pixel_aspect={}/{}",
+            frame.width, frame.height, frame.format, time_base.0, time_base.1, sar_num, sar_den
+        ))?;
+
+        let graph = AVFilterGraph::new();
+        {
+            let mut source = graph.create_filter_context(&buffer, source_name, Some(&args))?;
+            let mut sink = graph.create_filter_context(&buffersink, sink_name, None)?;
+
+            // The output of source is the input of filters, and vice versa
+            let outputs = AVFilterInOut::new(source_name, &mut source, 0);
+            let inputs = AVFilterInOut::new(sink_name, &mut sink, 0);
+            let filters = CString::new(spec)?;
+            graph.parse_ptr(&filters, Some(inputs), Some(outputs))?;
+        }
+        graph.config()?;
+
+        Ok(Self {
+            graph,
+            spec: spec.to_string(),
+            input: (frame.width, frame.height, frame.format),
+        })
+    }
+
+    /// Whether the graph can be reused for the filters and frame
+    pub fn matches(&self, spec: &str, frame: &AVFrame) -> bool {
+        self.spec == spec && self.input == (frame.width, frame.height, frame.format)
+    }
+
+    /// Feed a frame to the graph and take out all the available frames,
+    /// the result may be empty because some filters need more frames, e.g. deinterlacing
+    pub fn filter(&mut self, frame: AVFrame) -> Result<Vec<AVFrame>, SuperError> {
+        self.send(Some(frame))
+    }
+
+    /// Mark the end of input and take out the remaining frames,
+    /// some filters only output at the end, e.g. palettegen
+    pub fn flush(&mut self) -> Result<Vec<AVFrame>, SuperError> {
+        self.send(None)
+    }
+
+    fn send(&mut self, frame: Option<AVFrame>) -> Result<Vec<AVFrame>, SuperError> {
+        let source_name = CStr::from_bytes_with_nul(SOURCE_NAME)?;
+        let sink_name = CStr::from_bytes_with_nul(SINK_NAME)?;
+
+        {
+            let mut source = self
+                .graph
+                .get_filter(source_name)
+                .ok_or("source of filter graph is not found")?;
+            source.buffersrc_add_frame(frame, None)?;
+        }
+
+        let mut sink = self
+            .graph
+            .get_filter(sink_name)
+            .ok_or("sink of filter graph is not found")?;
+        let mut frames = vec![];
+        loop {
+            match sink.buffersink_get_frame(None) {
+                Ok(frame) => frames.push(frame),
+                Err(RsmpegError::BufferSinkDrainError) | Err(RsmpegError::BufferSinkEofError) => {
+                    break
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(frames)
+    }
+}
+
+/// Apply the video filters in settings to decoded frames,
+/// the graph is rebuilt while the settings or the frame parameters are changed
+#[derive(Default)]
+pub struct VideoFilter {
+    graph: Option<VideoFilterGraph>,
+    /// Whether any interlaced frame has been found, it enables the auto deinterlacing
+    interlaced: bool,
+    /// The filters which failed to be created, they are not retried until changed
+    failed_spec: Option<String>,
+}
+
+impl VideoFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Filter the frame, the frame is returned as is if no filter is needed or the graph fails
+    /// # Arguments
+    /// * `time_base` - Time base of the stream, `(numerator, denominator)`
+    pub fn process(
+        &mut self,
+        frame: AVFrame,
+        settings: &VideoFilterSettings,
+        time_base: (i32, i32),
+    ) -> Vec<AVFrame> {
+        if frame.interlaced_frame != 0 && !self.interlaced {
+            info!("interlaced frame found");
+            self.interlaced = true;
+        }
+
+        let spec = match settings.filter_spec(self.interlaced) {
+            Some(spec) => spec,
+            None => {
+                self.graph = None;
+                return vec![frame];
+            }
+        };
+        if self.failed_spec.as_ref() == Some(&spec) {
+            return vec![frame];
+        }
+
+        let reusable = self
+            .graph
+            .as_ref()
+            .map_or(false, |graph| graph.matches(&spec, &frame));
+        if !reusable {
+            match VideoFilterGraph::new(&spec, &frame, time_base) {
+                Ok(graph) => {
+                    info!("video filters: {}", spec);
+                    self.graph = Some(graph);
+                }
+                Err(err) => {
+                    error!("failed to create video filters {}: {}", spec, err);
+                    self.graph = None;
+                    self.failed_spec = Some(spec);
+                    return vec![frame];
+                }
+            }
+        }
+
+        let graph = self.graph.as_mut().unwrap();
+        match graph.filter(frame) {
+            Ok(frames) => frames,
+            Err(err) => {
+                error!("failed to filter video frame: {}", err);
+                self.graph = None;
+                vec![]
+            }
+        }
+    }
+
+    /// Drop the buffered frames, it should be called after seek
+    pub fn reset(&mut self) {
+        self.graph = None;
+    }
+}
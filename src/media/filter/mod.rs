@@ -0,0 +1,269 @@
+mod graph;
+
+pub use graph::{VideoFilter, VideoFilterGraph};
+
+use serde::{Deserialize, Serialize};
+
+/// The pixel format of filtered frames, which is the only format the play box can render
+const OUTPUT_FORMAT: &str = "yuv420p";
+
+/// How the interlaced video is deinterlaced
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeinterlaceMode {
+    Off,
+    /// Deinterlace with yadif once an interlaced frame is found,
+    /// progressive frames pass through
+    Auto,
+    /// Deinterlace all frames with yadif
+    Yadif,
+    /// Deinterlace all frames with bwdif, which is sharper but slower than yadif
+    Bwdif,
+}
+
+impl DeinterlaceMode {
+    pub fn next(&self) -> Self {
+        match self {
+            DeinterlaceMode::Off => DeinterlaceMode::Auto,
+            DeinterlaceMode::Auto => DeinterlaceMode::Yadif,
+            DeinterlaceMode::Yadif => DeinterlaceMode::Bwdif,
+            DeinterlaceMode::Bwdif => DeinterlaceMode::Off,
+        }
+    }
+}
+
+/// The adjustable parameters of video filters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterParam {
+    Denoise,
+    Sharpen,
+    Brightness,
+    Contrast,
+    Saturation,
+    Gamma,
+}
+
+impl FilterParam {
+    /// The amount of each adjustment
+    pub fn step(&self) -> f32 {
+        match self {
+            FilterParam::Denoise => 1.0,
+            FilterParam::Sharpen => 0.25,
+            FilterParam::Brightness => 0.05,
+            FilterParam::Contrast | FilterParam::Saturation | FilterParam::Gamma => 0.1,
+        }
+    }
+
+    /// The valid range, `(min, max)`
+    pub fn range(&self) -> (f32, f32) {
+        match self {
+            FilterParam::Denoise => (0.0, 10.0),
+            FilterParam::Sharpen => (0.0, 2.0),
+            FilterParam::Brightness => (-1.0, 1.0),
+            FilterParam::Contrast => (0.0, 2.0),
+            FilterParam::Saturation => (0.0, 3.0),
+            FilterParam::Gamma => (0.1, 3.0),
+        }
+    }
+}
+
+/// Options of video filters, which are persisted in settings
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct VideoFilterSettings {
+    pub deinterlace: DeinterlaceMode,
+    /// Strength of the denoiser, 0 means disabled
+    pub denoise: f32,
+    /// Amount of sharpening, 0 means disabled
+    pub sharpen: f32,
+    /// 0 keeps the original brightness
+    pub brightness: f32,
+    /// 1 keeps the original contrast
+    pub contrast: f32,
+    /// 1 keeps the original saturation
+    pub saturation: f32,
+    /// 1 keeps the original gamma
+    pub gamma: f32,
+}
+
+impl Default for VideoFilterSettings {
+    fn default() -> Self {
+        Self {
+            deinterlace: DeinterlaceMode::Auto,
+            denoise: 0.0,
+            sharpen: 0.0,
+            brightness: 0.0,
+            contrast: 1.0,
+            saturation: 1.0,
+            gamma: 1.0,
+        }
+    }
+}
+
+impl VideoFilterSettings {
+    /// Adjust the parameter by the specified number of steps, the value is clamped to its range
+    pub fn adjust(&mut self, param: FilterParam, steps: i32) {
+        let (min, max) = param.range();
+        let delta = param.step() * steps as f32;
+        let value = match param {
+            FilterParam::Denoise => &mut self.denoise,
+            FilterParam::Sharpen => &mut self.sharpen,
+            FilterParam::Brightness => &mut self.brightness,
+            FilterParam::Contrast => &mut self.contrast,
+            FilterParam::Saturation => &mut self.saturation,
+            FilterParam::Gamma => &mut self.gamma,
+        };
+        // Round to the step to avoid accumulated errors, e.g. 0.30000001
+        let stepped = ((*value + delta) / param.step()).round() * param.step();
+        *value = stepped.clamp(min, max);
+    }
+
+    /// Reset all the filters except deinterlacing
+    pub fn reset_picture(&mut self) {
+        *self = Self {
+            deinterlace: self.deinterlace,
+            ..Self::default()
+        };
+    }
+
+    /// Build the description of filter graph, `None` means no filter is needed
+    /// # Arguments
+    /// * `interlaced` - Whether any interlaced frame has been found in the stream
+    pub fn filter_spec(&self, interlaced: bool) -> Option<String> {
+        let mut filters = vec![];
+
+        match self.deinterlace {
+            DeinterlaceMode::Off => {}
+            // Only the frames marked as interlaced are processed
+            DeinterlaceMode::Auto if interlaced => {
+                filters.push("yadif=mode=send_frame:parity=auto:deint=interlaced".to_string());
+            }
+            DeinterlaceMode::Auto => {}
+            DeinterlaceMode::Yadif => {
+                filters.push("yadif=mode=send_frame:parity=auto:deint=all".to_string());
+            }
+            DeinterlaceMode::Bwdif => {
+                filters.push("bwdif=mode=send_frame:parity=auto:deint=all".to_string());
+            }
+        }
+
+        if self.denoise > 0.0 {
+            filters.push(format!("hqdn3d=luma_spatial={:.2}", self.denoise));
+        }
+        if self.sharpen > 0.0 {
+            filters.push(format!(
+                "unsharp=luma_msize_x=5:luma_msize_y=5:luma_amount={:.2}",
+                self.sharpen
+            ));
+        }
+
+        let is_default = |value: f32, default: f32| (value - default).abs() < f32::EPSILON;
+        if !is_default(self.brightness, 0.0)
+            || !is_default(self.contrast, 1.0)
+            || !is_default(self.saturation, 1.0)
+            || !is_default(self.gamma, 1.0)
+        {
+            filters.push(format!(
+                "eq=brightness={:.2}:contrast={:.2}:saturation={:.2}:gamma={:.2}",
+                self.brightness, self.contrast, self.saturation, self.gamma
+            ));
+        }
+
+        if filters.is_empty() {
+            return None;
+        }
+        // Filters may output other formats, convert back to the format for rendering
+        filters.push(format!("format={}", OUTPUT_FORMAT));
+
+        Some(filters.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_filter_by_default() {
+        let settings = VideoFilterSettings::default();
+
+        assert_eq!(None, settings.filter_spec(false));
+    }
+
+    #[test]
+    fn test_auto_deinterlace() {
+        let settings = VideoFilterSettings::default();
+
+        assert_eq!(
+            Some("yadif=mode=send_frame:parity=auto:deint=interlaced,format=yuv420p".to_string()),
+            settings.filter_spec(true)
+        );
+
+        let settings = VideoFilterSettings {
+            deinterlace: DeinterlaceMode::Off,
+            ..Default::default()
+        };
+        assert_eq!(None, settings.filter_spec(true));
+    }
+
+    #[test]
+    fn test_forced_deinterlace() {
+        let settings = VideoFilterSettings {
+            deinterlace: DeinterlaceMode::Bwdif,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            Some("bwdif=mode=send_frame:parity=auto:deint=all,format=yuv420p".to_string()),
+            settings.filter_spec(false)
+        );
+    }
+
+    #[test]
+    fn test_filter_chain() {
+        let mut settings = VideoFilterSettings {
+            deinterlace: DeinterlaceMode::Off,
+            ..Default::default()
+        };
+        settings.adjust(FilterParam::Denoise, 4);
+        settings.adjust(FilterParam::Sharpen, 2);
+        settings.adjust(FilterParam::Gamma, 2);
+
+        assert_eq!(
+            Some(
+                "hqdn3d=luma_spatial=4.00,\
+                unsharp=luma_msize_x=5:luma_msize_y=5:luma_amount=0.50,\
+                eq=brightness=0.00:contrast=1.00:saturation=1.00:gamma=1.20,\
+                format=yuv420p"
+                    .to_string()
+            ),
+            settings.filter_spec(false)
+        );
+    }
+
+    #[test]
+    fn test_adjust_is_clamped() {
+        let mut settings = VideoFilterSettings::default();
+        settings.adjust(FilterParam::Brightness, -100);
+        settings.adjust(FilterParam::Denoise, -1);
+        assert_eq!(-1.0, settings.brightness);
+        assert_eq!(0.0, settings.denoise);
+
+        // Back to the default value exactly, so that the filter is removed
+        settings.adjust(FilterParam::Contrast, 3);
+        settings.adjust(FilterParam::Contrast, -3);
+        assert_eq!(1.0, settings.contrast);
+    }
+
+    #[test]
+    fn test_reset_picture() {
+        let mut settings = VideoFilterSettings {
+            deinterlace: DeinterlaceMode::Yadif,
+            ..Default::default()
+        };
+        settings.adjust(FilterParam::Saturation, 5);
+        settings.reset_picture();
+
+        assert_eq!(DeinterlaceMode::Yadif, settings.deinterlace);
+        assert_eq!(1.0, settings.saturation);
+    }
+}
@@ -1,2 +1,10 @@
+pub mod ab_loop;
+pub mod bookmark;
+pub mod chapter;
+pub mod clip;
 pub mod decoder;
-pub mod player;
\ No newline at end of file
+pub mod filter;
+pub mod player;
+pub mod stats;
+pub mod sync;
+pub mod thumbnail;
\ No newline at end of file
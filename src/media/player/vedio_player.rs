@@ -2,7 +2,7 @@ use std::{
     cell::Cell,
     sync::{atomic::Ordering, Arc},
     thread::{self, JoinHandle},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use crossbeam::atomic::AtomicCell;
@@ -10,8 +10,12 @@ use log::info;
 
 use crate::{
     entity::EventMessage,
+    media::sync::{self, schedule_frame, FrameAction},
     util::error::{safe_send, SuperError},
-    {EVENT_CHANNEL, VIDEO_BUFFER, VIDEO_PTS_MILLIS, VIDEO_SUMMARY},
+    {
+        AUDIO_DELAY_MILLIS, AUDIO_PTS_MILLIS, AUDIO_SUMMARY, EVENT_CHANNEL, PLAYBACK_STATS,
+        VIDEO_BUFFER, VIDEO_PTS_MILLIS, VIDEO_SUMMARY,
+    },
 };
 
 use super::Player;
@@ -47,14 +51,23 @@ impl VideoPlayer {
         let sender = &EVENT_CHANNEL.0;
         let summary = summary.as_ref().unwrap();
         info!("Starting video player, summary: {:?}", summary);
-        sender.send(EventMessage::Resize((summary.width, summary.height)))?;
+        // The window has the shape of rotated video
+        let size = if summary.rotation % 180 == 90 {
+            (summary.height, summary.width)
+        } else {
+            (summary.width, summary.height)
+        };
+        sender.send(EventMessage::Resize(size))?;
 
         let state = self.state.clone();
         let sleep_duration = Duration::from_millis(summary.play_interval);
+        let has_audio = AUDIO_SUMMARY.read().unwrap().is_some();
         let tid = thread::spawn({
             move || {
                 const MAX_WAIT_COUNT: u8 = 2;
                 let mut wait_count: u8 = 0;
+                // The frame which is early for the audio clock
+                let mut pending = None;
 
                 state.store(State::Playing);
                 loop {
@@ -85,6 +98,8 @@ impl VideoPlayer {
                             // go on
                         }
                         State::Seeking => {
+                            // The held frame is out of date
+                            pending = None;
                             thread::sleep(sleep_duration);
                             continue;
                         }
@@ -93,15 +108,41 @@ impl VideoPlayer {
                         }
                     }
 
-                    // Play video
-                    if let Some(frame) = VIDEO_BUFFER.pop() {
-                        // Update timestamp of video
-                        VIDEO_PTS_MILLIS.store(frame.pts_millis, Ordering::Release);
-                        // Send video data to UI
-                        safe_send(sender.send(EventMessage::RenderVideo(frame)));
-
-                        thread::sleep(sleep_duration);
+                    // Play video, the held frame goes first
+                    let frame = match pending.take().or_else(|| VIDEO_BUFFER.pop()) {
+                        Some(frame) => frame,
+                        None => continue,
+                    };
+
+                    // Compare with the audio clock, whose position is negative while seeking
+                    let audio_pts = AUDIO_PTS_MILLIS.load(Ordering::Acquire);
+                    let audio_delay = AUDIO_DELAY_MILLIS.load(Ordering::Acquire);
+                    let av_offset = (has_audio && audio_pts >= 0)
+                        .then(|| sync::av_offset(frame.pts_millis, audio_pts, audio_delay));
+                    if let Some(offset) = av_offset {
+                        let can_wait = !VIDEO_BUFFER.is_full();
+                        let can_drop = !VIDEO_BUFFER.is_empty();
+                        match schedule_frame(offset, can_wait, can_drop) {
+                            FrameAction::Wait(wait) => {
+                                pending = Some(frame);
+                                thread::sleep(wait.min(sleep_duration));
+                                continue;
+                            }
+                            FrameAction::Drop => {
+                                PLAYBACK_STATS.record_dropped(1);
+                                continue;
+                            }
+                            FrameAction::Show => {}
+                        }
                     }
+
+                    // Update timestamp of video
+                    VIDEO_PTS_MILLIS.store(frame.pts_millis, Ordering::Release);
+                    PLAYBACK_STATS.record_presented(Instant::now(), av_offset);
+                    // Send video data to UI
+                    safe_send(sender.send(EventMessage::RenderVideo(frame)));
+
+                    thread::sleep(sleep_duration);
                 }
             }
         });
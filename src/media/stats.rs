@@ -0,0 +1,235 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicI64, AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// A presented frame is late if it's behind the audio more than this, unit: milliseconds
+const LATE_THRESHOLD: i64 = 40;
+/// The actual frame rate is measured over this period
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+/// The A/V offset is unknown, e.g. there's no audio
+const NO_OFFSET: i64 = i64::MIN;
+
+/// Counters of video playback for debugging stutter,
+/// they are updated by the decoding and playing threads
+pub struct PlaybackStats {
+    decoded: AtomicU64,
+    /// Frames which are too late to be shown, they are dropped to catch up the audio
+    dropped: AtomicU64,
+    /// Frames which are discarded by seeking, they are not a sign of stutter
+    skipped: AtomicU64,
+    late: AtomicU64,
+    /// Total decoding time of video frames, unit: microseconds
+    decode_micros: AtomicU64,
+    /// The latest difference of video to audio, unit: milliseconds
+    av_offset: AtomicI64,
+    presented: Mutex<FrameRateMeter>,
+}
+
+impl PlaybackStats {
+    pub const fn new() -> Self {
+        Self {
+            decoded: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            skipped: AtomicU64::new(0),
+            late: AtomicU64::new(0),
+            decode_micros: AtomicU64::new(0),
+            av_offset: AtomicI64::new(NO_OFFSET),
+            presented: Mutex::new(FrameRateMeter::new()),
+        }
+    }
+
+    /// A video frame is decoded in the specified time
+    pub fn record_decoded(&self, elapsed: Duration) {
+        self.decoded.fetch_add(1, Ordering::Relaxed);
+        self.decode_micros
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_dropped(&self, frames: u64) {
+        self.dropped.fetch_add(frames, Ordering::Relaxed);
+    }
+
+    /// Video frames are flushed or skipped by seeking
+    pub fn record_skipped(&self, frames: u64) {
+        self.skipped.fetch_add(frames, Ordering::Relaxed);
+    }
+
+    /// A video frame is sent to be shown
+    /// # Arguments
+    /// * `av_offset` - Position of the frame minus the delayed audio position, unit: milliseconds,
+    ///   `None` if there's no audio
+    pub fn record_presented(&self, now: Instant, av_offset: Option<i64>) {
+        if let Some(offset) = av_offset {
+            if offset < -LATE_THRESHOLD {
+                self.late.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.av_offset
+            .store(av_offset.unwrap_or(NO_OFFSET), Ordering::Relaxed);
+        self.presented.lock().unwrap().push(now);
+    }
+
+    /// Clear the counters, it should be called before playing new media
+    pub fn reset(&self) {
+        self.decoded.store(0, Ordering::Relaxed);
+        self.dropped.store(0, Ordering::Relaxed);
+        self.skipped.store(0, Ordering::Relaxed);
+        self.late.store(0, Ordering::Relaxed);
+        self.decode_micros.store(0, Ordering::Relaxed);
+        self.av_offset.store(NO_OFFSET, Ordering::Relaxed);
+        self.presented.lock().unwrap().clear();
+    }
+
+    /// The current values of counters
+    pub fn snapshot(&self, now: Instant) -> StatsSnapshot {
+        let decoded = self.decoded.load(Ordering::Relaxed);
+        let decode_micros = self.decode_micros.load(Ordering::Relaxed);
+        let decode_time = match decoded {
+            0 => Duration::ZERO,
+            _ => Duration::from_micros(decode_micros / decoded),
+        };
+        let av_offset = self.av_offset.load(Ordering::Relaxed);
+
+        StatsSnapshot {
+            decoded,
+            dropped: self.dropped.load(Ordering::Relaxed),
+            skipped: self.skipped.load(Ordering::Relaxed),
+            late: self.late.load(Ordering::Relaxed),
+            decode_time,
+            av_offset: (av_offset != NO_OFFSET).then_some(av_offset),
+            frame_rate: self.presented.lock().unwrap().rate(now),
+        }
+    }
+}
+
+/// The values of playback counters at a moment
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct StatsSnapshot {
+    pub decoded: u64,
+    pub dropped: u64,
+    pub skipped: u64,
+    pub late: u64,
+    /// Average decoding time per video frame
+    pub decode_time: Duration,
+    /// Position of video minus the delayed audio one, unit: milliseconds, `None` if it's unknown
+    pub av_offset: Option<i64>,
+    /// Frames shown per second recently
+    pub frame_rate: f64,
+}
+
+/// Measure the frame rate with the frames shown in the recent period
+struct FrameRateMeter {
+    frames: VecDeque<Instant>,
+}
+
+impl FrameRateMeter {
+    const fn new() -> Self {
+        Self {
+            frames: VecDeque::new(),
+        }
+    }
+
+    fn push(&mut self, now: Instant) {
+        self.frames.push_back(now);
+        while let Some(&first) = self.frames.front() {
+            if now.duration_since(first) <= RATE_WINDOW {
+                break;
+            }
+            self.frames.pop_front();
+        }
+    }
+
+    /// Frames per second in the period before now, 0 if no frames are shown recently
+    fn rate(&self, now: Instant) -> f64 {
+        let mut recent = self
+            .frames
+            .iter()
+            .filter(|&&t| now.saturating_duration_since(t) <= RATE_WINDOW);
+        let (first, last, count) = match recent.next() {
+            Some(&first) => {
+                let (last, count) = recent.fold((first, 1), |(_, n), &t| (t, n + 1));
+                (first, last, count)
+            }
+            None => return 0.0,
+        };
+
+        let elapsed = last.duration_since(first).as_secs_f64();
+        if count < 2 || elapsed <= 0.0 {
+            return 0.0;
+        }
+
+        (count - 1) as f64 / elapsed
+    }
+
+    fn clear(&mut self) {
+        self.frames.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counters() {
+        let now = Instant::now();
+        let stats = PlaybackStats::new();
+        stats.record_decoded(Duration::from_millis(4));
+        stats.record_decoded(Duration::from_millis(6));
+        stats.record_dropped(3);
+        // The frame behind audio is late, the one ahead of audio is not
+        stats.record_presented(now, Some(-100));
+        stats.record_presented(now, Some(100));
+
+        let snapshot = stats.snapshot(now);
+        assert_eq!(2, snapshot.decoded);
+        assert_eq!(3, snapshot.dropped);
+        assert_eq!(1, snapshot.late);
+        assert_eq!(Duration::from_millis(5), snapshot.decode_time);
+        assert_eq!(Some(100), snapshot.av_offset);
+
+        stats.record_presented(now, None);
+        assert_eq!(None, stats.snapshot(now).av_offset);
+
+        stats.reset();
+        assert_eq!(StatsSnapshot::default(), stats.snapshot(now));
+    }
+
+    #[test]
+    fn test_skipped_apart_from_dropped() {
+        let now = Instant::now();
+        let stats = PlaybackStats::new();
+        // Seeking flushes the buffer and skips the frames before the position
+        stats.record_skipped(8);
+        stats.record_skipped(2);
+        stats.record_dropped(1);
+
+        let snapshot = stats.snapshot(now);
+        assert_eq!(10, snapshot.skipped);
+        assert_eq!(1, snapshot.dropped);
+    }
+
+    #[test]
+    fn test_frame_rate() {
+        let start = Instant::now();
+        let mut meter = FrameRateMeter::new();
+        assert_eq!(0.0, meter.rate(start));
+
+        // 25 frames per second for 2 seconds
+        for i in 0..50 {
+            meter.push(start + Duration::from_millis(40 * i));
+        }
+        let now = start + Duration::from_millis(40 * 49);
+        assert!((meter.rate(now) - 25.0).abs() < 0.01, "{}", meter.rate(now));
+        // The frames out of the period are removed
+        assert_eq!(26, meter.frames.len());
+
+        // No frames are shown while paused
+        assert_eq!(0.0, meter.rate(now + Duration::from_secs(2)));
+    }
+}
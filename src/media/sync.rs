@@ -0,0 +1,94 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// The delays are limited to this range, unit: milliseconds
+const MAX_DELAY: i64 = 5000;
+/// A video frame is shown if it's not ahead of the audio clock more than this,
+/// unit: milliseconds
+const SYNC_THRESHOLD: i64 = 15;
+/// A video frame is dropped if it's behind the audio clock more than this,
+/// unit: milliseconds
+const DROP_THRESHOLD: i64 = 200;
+
+/// The delays adjusted by user for the media whose streams are out of sync,
+/// positive values make the stream played later
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct MediaDelay {
+    /// Delay of audio, unit: milliseconds
+    pub audio: i64,
+}
+
+impl MediaDelay {
+    pub fn adjust_audio(&mut self, delta: i64) {
+        self.audio = (self.audio + delta).clamp(-MAX_DELAY, MAX_DELAY);
+    }
+}
+
+/// What to do with the next video frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameAction {
+    Show,
+    /// The frame is early, wait for the audio clock
+    Wait(Duration),
+    /// The frame is too late, skip it to catch up the audio
+    Drop,
+}
+
+/// The position of video minus the position of audio with its delay,
+/// a positive value means the video is early
+pub fn av_offset(video_pts: i64, audio_pts: i64, audio_delay: i64) -> i64 {
+    video_pts - (audio_pts + audio_delay)
+}
+
+/// Decide when to show the video frame by comparing it with the audio clock
+/// # Arguments
+/// * `offset` - The offset of video to audio, see `av_offset`
+/// * `can_wait` - There's room in buffer for the decoder while waiting,
+///   the frame is shown anyway if it's false, so that the audio is not starved
+/// * `can_drop` - There's a newer frame to show instead of the dropped one
+pub fn schedule_frame(offset: i64, can_wait: bool, can_drop: bool) -> FrameAction {
+    if offset > SYNC_THRESHOLD && can_wait {
+        FrameAction::Wait(Duration::from_millis(offset as u64))
+    } else if offset < -DROP_THRESHOLD && can_drop {
+        FrameAction::Drop
+    } else {
+        FrameAction::Show
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adjust_delay() {
+        let mut delay = MediaDelay::default();
+        delay.adjust_audio(-50);
+        assert_eq!(MediaDelay { audio: -50 }, delay);
+
+        delay.adjust_audio(-10_000);
+        assert_eq!(-MAX_DELAY, delay.audio);
+    }
+
+    #[test]
+    fn test_schedule_frame() {
+        // The audio is delayed, so the video frame at 1000 is due at audio position 800
+        assert_eq!(0, av_offset(1000, 800, 200));
+        assert_eq!(FrameAction::Show, schedule_frame(0, true, true));
+
+        let early = av_offset(1000, 700, 200);
+        assert_eq!(
+            FrameAction::Wait(Duration::from_millis(100)),
+            schedule_frame(early, true, true)
+        );
+        // The decoder would be blocked if the frame is held
+        assert_eq!(FrameAction::Show, schedule_frame(early, false, true));
+
+        // The audio is played earlier, the frame at 1000 is far behind audio position 1500
+        let late = av_offset(1000, 1100, 400);
+        assert_eq!(FrameAction::Drop, schedule_frame(late, true, true));
+        assert_eq!(FrameAction::Show, schedule_frame(late, true, false));
+    }
+}
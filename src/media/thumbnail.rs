@@ -0,0 +1,353 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use crossbeam::channel::{unbounded, Receiver, Sender};
+use log::warn;
+use rsmpeg::{
+    avcodec::{AVCodec, AVCodecContext},
+    avformat::AVFormatContextInput,
+    ffi::{
+        av_rescale_q, av_seek_frame, avcodec_flush_buffers,
+        AVMediaType_AVMEDIA_TYPE_VIDEO as AVMEDIATYPE_AVMEDIA_TYPE_VIDEO,
+        AVPixelFormat_AV_PIX_FMT_YUV420P as AVPIXELFORMAT_AV_PIX_FMT_YUV420P, AVRational,
+        AVSEEK_FLAG_BACKWARD, AV_DISPOSITION_ATTACHED_PIC, AV_NOPTS_VALUE,
+    },
+};
+
+use super::decoder::{MediaDecoder, VideoFrame};
+use crate::{
+    entity::EventMessage,
+    util::{
+        error::{safe_send, SuperError},
+        pixel_format::{parse_picture, scale_frame},
+    },
+    EVENT_CHANNEL,
+};
+
+/// Width of thumbnails, the height keeps the aspect ratio of video
+pub const THUMBNAIL_WIDTH: i32 = 160;
+/// The minimum distance between the positions of thumbnails, unit: milliseconds
+const MIN_INTERVAL: i64 = 1000;
+/// The bar is divided into at most this number of thumbnails
+const MAX_THUMBNAILS: i64 = 200;
+/// Packets to read after seeking, the thumbnail is given up if no frame is decoded
+const MAX_PACKETS: usize = 500;
+
+/// Time base of milliseconds
+const MILLIS_BASE: AVRational = AVRational { num: 1, den: 1000 };
+
+/// Generate thumbnails in a background thread with its own decoder,
+/// so that the playback is not disturbed.
+/// Only the latest request is handled, the stale ones are dropped while scrubbing.
+/// `EventMessage::ThumbnailReady` is sent once a thumbnail is generated
+pub struct Thumbnailer {
+    sender: Sender<(PathBuf, i64)>,
+    stop_flag: Arc<AtomicBool>,
+}
+
+impl Thumbnailer {
+    pub fn new() -> Self {
+        let (sender, receiver) = unbounded::<(PathBuf, i64)>();
+        let stop_flag = Arc::new(AtomicBool::new(false));
+
+        thread::spawn({
+            let stop_flag = stop_flag.clone();
+            move || {
+                let mut source: Option<ThumbnailSource> = None;
+                // The loop ends while the thumbnailer is dropped
+                while let Some((path, millis)) = Self::latest_request(&receiver) {
+                    if stop_flag.load(Ordering::Acquire) {
+                        break;
+                    }
+
+                    // The opened media is reused until another one is requested
+                    if source.as_ref().map_or(true, |s| s.path != path) {
+                        source = match ThumbnailSource::open(&path) {
+                            Ok(source) => source,
+                            Err(err) => {
+                                warn!("failed to open {:?} for thumbnails: {}", path, err);
+                                None
+                            }
+                        };
+                    }
+                    let src = match source.as_mut() {
+                        Some(src) => src,
+                        None => continue,
+                    };
+
+                    match src.generate(millis) {
+                        Ok(Some(frame)) => {
+                            let msg = EventMessage::ThumbnailReady(path, millis, frame);
+                            safe_send(EVENT_CHANNEL.0.send(msg));
+                        }
+                        Ok(None) => {}
+                        Err(err) => warn!("failed to generate thumbnail at {}: {}", millis, err),
+                    }
+                }
+            }
+        });
+
+        Self { sender, stop_flag }
+    }
+
+    /// Request the thumbnail of media at the position, unit: milliseconds
+    pub fn request(&self, path: PathBuf, millis: i64) {
+        if let Err(err) = self.sender.send((path, millis)) {
+            warn!("thumbnailer is not running: {}", err);
+        }
+    }
+
+    /// Wait for a request, the queued ones are skipped except the latest.
+    /// `None` is returned if the thumbnailer is dropped
+    fn latest_request(receiver: &Receiver<(PathBuf, i64)>) -> Option<(PathBuf, i64)> {
+        let mut request = receiver.recv().ok()?;
+        while let Ok(newer) = receiver.try_recv() {
+            request = newer;
+        }
+
+        Some(request)
+    }
+}
+
+impl Drop for Thumbnailer {
+    fn drop(&mut self) {
+        self.stop_flag.store(true, Ordering::Release);
+    }
+}
+
+/// The media opened for thumbnails
+struct ThumbnailSource {
+    path: PathBuf,
+    ctx: AVFormatContextInput,
+    /// Index of video stream
+    index: i32,
+    time_base: AVRational,
+    decoder: AVCodecContext,
+}
+
+impl ThumbnailSource {
+    /// `None` is returned if the media has no video stream
+    fn open(path: &Path) -> Result<Option<Self>, SuperError> {
+        let path_str = path.to_str().ok_or("path is not valid unicode")?;
+        let ctx = MediaDecoder::get_media_context(path_str)?;
+
+        // The cover is not counted, it has no frames to seek
+        let video = ctx.streams().into_iter().find(|s| {
+            s.codecpar().codec_type == AVMEDIATYPE_AVMEDIA_TYPE_VIDEO
+                && s.disposition & AV_DISPOSITION_ATTACHED_PIC as i32 == 0
+        });
+        let (index, time_base, decoder) = match video {
+            Some(stream) => {
+                let codecpar = stream.codecpar();
+                let decoder = AVCodec::find_decoder(codecpar.codec_id).ok_or("no video decoder")?;
+                let mut dctx = AVCodecContext::new(&decoder);
+                dctx.apply_codecpar(&codecpar)?;
+                dctx.open(None)?;
+
+                (stream.index, stream.time_base, dctx)
+            }
+            None => return Ok(None),
+        };
+
+        Ok(Some(Self {
+            path: path.to_path_buf(),
+            ctx,
+            index,
+            time_base,
+            decoder,
+        }))
+    }
+
+    /// Decode the key frame before the position, it's much faster than decoding
+    /// to the exact frame, and the difference is invisible in a thumbnail
+    fn generate(&mut self, millis: i64) -> Result<Option<VideoFrame>, SuperError> {
+        let ts = unsafe { av_rescale_q(millis, MILLIS_BASE, self.time_base) };
+        let flags = AVSEEK_FLAG_BACKWARD as i32;
+        if unsafe { av_seek_frame(self.ctx.as_mut_ptr(), self.index, ts, flags) } < 0 {
+            return Err("failed to seek".into());
+        }
+        unsafe { avcodec_flush_buffers(self.decoder.as_mut_ptr()) };
+
+        for _ in 0..MAX_PACKETS {
+            let packet = match self.ctx.read_packet()? {
+                Some(packet) => packet,
+                None => break,
+            };
+            if packet.stream_index != self.index {
+                continue;
+            }
+            if let Err(err) = self.decoder.send_packet(Some(&packet)) {
+                warn!("send packet to context error: {}", err);
+                continue;
+            }
+
+            if let Ok(frame) = self.decoder.receive_frame() {
+                let (width, height) =
+                    thumbnail_size(frame.width, frame.height, frame.sample_aspect_ratio);
+                let scaled = scale_frame(&frame, width, height, AVPIXELFORMAT_AV_PIX_FMT_YUV420P)
+                    .ok_or("failed to scale the frame")?;
+                let mut thumbnail = parse_picture(&scaled).ok_or("failed to parse the frame")?;
+
+                let pts = if frame.best_effort_timestamp != AV_NOPTS_VALUE {
+                    frame.best_effort_timestamp
+                } else {
+                    frame.pts
+                };
+                thumbnail.pts = pts;
+                thumbnail.pts_millis = unsafe { av_rescale_q(pts, self.time_base, MILLIS_BASE) };
+
+                return Ok(Some(thumbnail));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Size of thumbnail for the frame, the display aspect ratio is kept
+/// and the size is even for YUV420P
+fn thumbnail_size(width: i32, height: i32, sar: AVRational) -> (i32, i32) {
+    let display_width = if sar.num > 0 && sar.den > 0 {
+        width as i64 * sar.num as i64 / sar.den as i64
+    } else {
+        width as i64
+    }
+    .max(1);
+    let height = THUMBNAIL_WIDTH as i64 * height as i64 / display_width;
+
+    (THUMBNAIL_WIDTH, (height as i32 / 2 * 2).max(2))
+}
+
+/// The position which the thumbnail is shown for, the positions are quantized
+/// so that the neighboring ones share a thumbnail
+/// # Arguments
+/// * `millis` - The hovered position
+/// * `duration` - Duration of media, unit: milliseconds
+pub fn thumbnail_position(millis: i64, duration: i64) -> i64 {
+    let interval = (duration / MAX_THUMBNAILS).max(MIN_INTERVAL);
+
+    millis.max(0) / interval * interval
+}
+
+/// The generated thumbnails, the least recently inserted ones are removed
+/// if the capacity is exceeded
+pub struct ThumbnailCache {
+    capacity: usize,
+    thumbnails: BTreeMap<i64, VideoFrame>,
+    /// Positions in the order of insertion
+    order: VecDeque<i64>,
+}
+
+impl ThumbnailCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            thumbnails: BTreeMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    pub fn get(&self, millis: i64) -> Option<&VideoFrame> {
+        self.thumbnails.get(&millis)
+    }
+
+    pub fn contains(&self, millis: i64) -> bool {
+        self.thumbnails.contains_key(&millis)
+    }
+
+    pub fn insert(&mut self, millis: i64, thumbnail: VideoFrame) {
+        if self.thumbnails.insert(millis, thumbnail).is_none() {
+            self.order.push_back(millis);
+        }
+
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.thumbnails.remove(&oldest);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.thumbnails.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.thumbnails.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.thumbnails.clear();
+        self.order.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(pts_millis: i64) -> VideoFrame {
+        VideoFrame {
+            format: AVPIXELFORMAT_AV_PIX_FMT_YUV420P,
+            data: Default::default(),
+            width: 0,
+            height: 0,
+            pts: 0,
+            pts_millis,
+        }
+    }
+
+    #[test]
+    fn test_thumbnail_position() {
+        // Short media, a thumbnail per second
+        assert_eq!(0, thumbnail_position(999, 60_000));
+        assert_eq!(1000, thumbnail_position(1999, 60_000));
+        assert_eq!(0, thumbnail_position(-5, 60_000));
+
+        // Long media, at most 200 thumbnails
+        assert_eq!(36_000, thumbnail_position(40_000, 7_200_000));
+        assert_eq!(7_164_000, thumbnail_position(7_199_999, 7_200_000));
+    }
+
+    #[test]
+    fn test_thumbnail_size() {
+        let square = AVRational { num: 1, den: 1 };
+        assert_eq!((160, 90), thumbnail_size(1920, 1080, square));
+        assert_eq!(
+            (160, 120),
+            thumbnail_size(640, 480, AVRational { num: 0, den: 1 })
+        );
+        // The height is rounded down to even
+        assert_eq!((160, 10), thumbnail_size(100, 7, square));
+
+        // Anamorphic PAL 16:9, whose pixels are wider than high
+        let wide = AVRational { num: 64, den: 45 };
+        assert_eq!((160, 90), thumbnail_size(720, 576, wide));
+    }
+
+    #[test]
+    fn test_cache_eviction() {
+        let mut cache = ThumbnailCache::new(2);
+        cache.insert(0, frame(0));
+        cache.insert(1000, frame(1000));
+        cache.insert(2000, frame(2000));
+
+        assert_eq!(2, cache.len());
+        assert_eq!(false, cache.contains(0));
+        assert_eq!(Some(2000), cache.get(2000).map(|f| f.pts_millis));
+
+        // Replacing a thumbnail does not take more space
+        cache.insert(2000, frame(1900));
+        assert_eq!(true, cache.contains(1000));
+        assert_eq!(Some(1900), cache.get(2000).map(|f| f.pts_millis));
+
+        cache.clear();
+        assert_eq!(true, cache.is_empty());
+    }
+}
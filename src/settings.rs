@@ -0,0 +1,129 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    media::{
+        ab_loop::AbSection,
+        bookmark,
+        clip::{self, ClipMode, SequenceOptions},
+        filter::VideoFilterSettings,
+        sync::MediaDelay,
+    },
+    sound::{dsp::DspSettings, loudness::NormalizationMode},
+    ui::{
+        theme::ThemePreset,
+        video_window::scaling::{AspectRatio, ScaleMode},
+    },
+    util::{error::SuperError, screenshot},
+    UNITY_VOLUME,
+};
+
+const FILE_NAME: &str = "settings.yml";
+
+/// User settings which are kept across sessions
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Settings {
+    /// The A-B sections exported by user, the key is the path of media
+    pub ab_sections: BTreeMap<String, Vec<AbSection>>,
+    /// The volume in percent when the application was closed last time
+    pub volume: i16,
+    /// The mute state when the application was closed last time
+    pub muted: bool,
+    /// Options of audio effects
+    pub dsp: DspSettings,
+    /// How to normalize the loudness between files
+    pub normalization: NormalizationMode,
+    /// Analyze the loudness of files without ReplayGain tags
+    pub analyze_loudness: bool,
+    /// The name of audio output device, `None` means the default device
+    pub audio_device: Option<String>,
+    /// How the video is scaled into the window
+    pub scale_mode: ScaleMode,
+    /// The forced aspect ratio of video
+    pub aspect_ratio: AspectRatio,
+    /// Options of video filters, such as deinterlacing
+    pub video_filter: VideoFilterSettings,
+    /// The directory to save screenshots
+    pub screenshot_dir: PathBuf,
+    /// The directory to save exported clips
+    pub clip_dir: PathBuf,
+    /// How the clips are exported
+    pub clip_mode: ClipMode,
+    /// Options of exporting the A-B section as an animated gif or pictures
+    pub frame_export: SequenceOptions,
+    /// The directory to save exported bookmarks
+    pub bookmark_dir: PathBuf,
+    /// The audio delays adjusted by user, the key is the path of media
+    pub delays: BTreeMap<String, MediaDelay>,
+    /// The idle time before the bars and cursor are hidden during playback,
+    /// unit: milliseconds, 0 means never hiding them
+    pub autohide_delay: u64,
+    /// The theme of controls
+    pub theme: ThemePreset,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            ab_sections: BTreeMap::new(),
+            volume: UNITY_VOLUME,
+            muted: false,
+            dsp: DspSettings::default(),
+            normalization: NormalizationMode::Track,
+            analyze_loudness: true,
+            audio_device: None,
+            scale_mode: ScaleMode::Fit,
+            aspect_ratio: AspectRatio::Auto,
+            video_filter: VideoFilterSettings::default(),
+            screenshot_dir: PathBuf::from(screenshot::DEFAULT_DIR),
+            clip_dir: PathBuf::from(clip::DEFAULT_DIR),
+            clip_mode: ClipMode::Remux,
+            frame_export: SequenceOptions::default(),
+            bookmark_dir: PathBuf::from(bookmark::DEFAULT_DIR),
+            delays: BTreeMap::new(),
+            autohide_delay: 2000,
+            theme: ThemePreset::Dark,
+        }
+    }
+}
+
+/// Load the settings from disk
+pub fn load() -> Result<Settings, SuperError> {
+    // If the file does not exist, use the default settings
+    if !Path::new(FILE_NAME).try_exists()? {
+        return Ok(Settings::default());
+    }
+
+    let f = File::open(FILE_NAME)?;
+    let settings = serde_yaml::from_reader(f)?;
+
+    Ok(settings)
+}
+
+/// Load the settings from disk, if any error occurred, log the error and use the default settings
+pub fn load_or_default() -> Settings {
+    match load() {
+        Ok(settings) => settings,
+        Err(err) => {
+            error!("failed to load {}: {}", FILE_NAME, err);
+            Settings::default()
+        }
+    }
+}
+
+/// Save the settings to disk
+pub fn save(settings: &Settings) -> Result<(), SuperError> {
+    let mut f = File::create(FILE_NAME)?;
+    serde_yaml::to_writer(&mut f, settings)?;
+    f.flush()?;
+
+    Ok(())
+}
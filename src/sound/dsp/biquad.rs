@@ -0,0 +1,71 @@
+use std::f32::consts::PI;
+
+/// Second order IIR filter, direct form I
+pub struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    /// `[x1, x2, y1, y2]` of each channel
+    states: Vec<[f32; 4]>,
+}
+
+impl Biquad {
+    /// Create filter with the coefficients of transfer function,
+    /// they are normalized by `a[0]`
+    pub fn new(b: [f32; 3], a: [f32; 3]) -> Self {
+        Self {
+            b0: b[0] / a[0],
+            b1: b[1] / a[0],
+            b2: b[2] / a[0],
+            a1: a[1] / a[0],
+            a2: a[2] / a[0],
+            states: Vec::new(),
+        }
+    }
+
+    /// Peaking filter from "Audio EQ Cookbook" by Robert Bristow-Johnson
+    pub fn peaking(frequency: f32, q: f32, gain_db: f32, sample_rate: f32) -> Self {
+        let a = 10f32.powf(gain_db / 40.0);
+        let w0 = 2.0 * PI * frequency / sample_rate;
+        let alpha = w0.sin() / (2.0 * q);
+        let cos_w0 = w0.cos();
+
+        Self::new(
+            [1.0 + alpha * a, -2.0 * cos_w0, 1.0 - alpha * a],
+            [1.0 + alpha / a, -2.0 * cos_w0, 1.0 - alpha / a],
+        )
+    }
+
+    /// Replace the coefficients but keep the states
+    pub fn set_coefficients(&mut self, other: &Biquad) {
+        self.b0 = other.b0;
+        self.b1 = other.b1;
+        self.b2 = other.b2;
+        self.a1 = other.a1;
+        self.a2 = other.a2;
+    }
+
+    /// Filter the interleaved samples in place, each channel has its own states
+    pub fn process(&mut self, samples: &mut [f32], channels: usize) {
+        if self.states.len() != channels {
+            self.states = vec![[0.0; 4]; channels];
+        }
+
+        for frame in samples.chunks_exact_mut(channels) {
+            for (sample, state) in frame.iter_mut().zip(self.states.iter_mut()) {
+                let [x1, x2, y1, y2] = *state;
+                let x0 = *sample;
+                let y0 = self.b0 * x0 + self.b1 * x1 + self.b2 * x2 - self.a1 * y1 - self.a2 * y2;
+
+                *state = [x0, x1, y0, y1];
+                *sample = y0;
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.states.clear();
+    }
+}
@@ -0,0 +1,183 @@
+use super::AudioFilter;
+
+/// Parameters of night mode
+const NIGHT_THRESHOLD_DB: f32 = -24.0;
+const NIGHT_RATIO: f32 = 4.0;
+const NIGHT_ATTACK_SECS: f32 = 0.005;
+const NIGHT_RELEASE_SECS: f32 = 0.2;
+const NIGHT_MAKEUP_DB: f32 = 6.0;
+
+/// Feed forward dynamic range compressor, the level of all channels is detected together
+/// so that the stereo image is not shifted
+pub struct Compressor {
+    enabled: bool,
+    threshold_db: f32,
+    ratio: f32,
+    makeup_db: f32,
+    attack_coef: f32,
+    release_coef: f32,
+    /// The detected level, linear
+    envelope: f32,
+}
+
+impl Compressor {
+    /// # Arguments
+    /// * `threshold_db` - Levels above it are compressed, unit: dBFS
+    /// * `ratio` - The input level change over the output level change above threshold
+    /// * `attack_secs` - How fast the compressor reacts to rising level
+    /// * `release_secs` - How fast the compressor recovers after level falls
+    /// * `makeup_db` - The gain applied after compression
+    pub fn new(
+        sample_rate: f32,
+        threshold_db: f32,
+        ratio: f32,
+        attack_secs: f32,
+        release_secs: f32,
+        makeup_db: f32,
+    ) -> Self {
+        Self {
+            enabled: true,
+            threshold_db,
+            ratio: ratio.max(1.0),
+            makeup_db,
+            attack_coef: Self::time_to_coef(attack_secs, sample_rate),
+            release_coef: Self::time_to_coef(release_secs, sample_rate),
+            envelope: 0.0,
+        }
+    }
+
+    /// The compressor used by night mode, it's disabled by default
+    pub fn night_mode(sample_rate: f32) -> Self {
+        let mut compressor = Self::new(
+            sample_rate,
+            NIGHT_THRESHOLD_DB,
+            NIGHT_RATIO,
+            NIGHT_ATTACK_SECS,
+            NIGHT_RELEASE_SECS,
+            NIGHT_MAKEUP_DB,
+        );
+        compressor.enabled = false;
+
+        compressor
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// The static gain for the specified level, both unit: dB
+    fn gain_db(&self, level_db: f32) -> f32 {
+        let reduction = if level_db > self.threshold_db {
+            let output_db = self.threshold_db + (level_db - self.threshold_db) / self.ratio;
+            output_db - level_db
+        } else {
+            0.0
+        };
+
+        reduction + self.makeup_db
+    }
+
+    /// One pole smoothing coefficient that reaches about 63% of the target in `secs`
+    fn time_to_coef(secs: f32, sample_rate: f32) -> f32 {
+        if secs <= 0.0 || sample_rate <= 0.0 {
+            return 0.0;
+        }
+
+        (-1.0 / (secs * sample_rate)).exp()
+    }
+}
+
+impl AudioFilter for Compressor {
+    fn process(&mut self, samples: &mut [f32], channels: usize) {
+        if !self.enabled {
+            return;
+        }
+
+        for frame in samples.chunks_exact_mut(channels) {
+            let level = frame
+                .iter()
+                .fold(0.0f32, |max, sample| max.max(sample.abs()));
+            let coef = if level > self.envelope {
+                self.attack_coef
+            } else {
+                self.release_coef
+            };
+            self.envelope = coef * self.envelope + (1.0 - coef) * level;
+
+            let level_db = 20.0 * self.envelope.max(f32::EPSILON).log10();
+            let gain = 10f32.powf(self.gain_db(level_db) / 20.0);
+            for sample in frame.iter_mut() {
+                *sample *= gain;
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.envelope = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sound::dsp::test_util::to_db;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    /// Constant signal, so the detected level is exactly its amplitude after settling
+    fn process_constant(compressor: &mut Compressor, amplitude: f32) -> f32 {
+        let mut samples = vec![amplitude; SAMPLE_RATE as usize * 2];
+        compressor.process(&mut samples, 2);
+
+        *samples.last().unwrap()
+    }
+
+    #[test]
+    fn test_above_threshold() {
+        // 0 dBFS with threshold -20 dB and ratio 4:1 results in -20 + 20 / 4 = -15 dBFS
+        let mut compressor = Compressor::new(SAMPLE_RATE, -20.0, 4.0, 0.005, 0.1, 0.0);
+        let output = process_constant(&mut compressor, 1.0);
+
+        assert!(
+            (to_db(output) + 15.0).abs() < 0.05,
+            "output: {}",
+            to_db(output)
+        );
+    }
+
+    #[test]
+    fn test_below_threshold() {
+        // -26 dBFS is below threshold, only makeup gain is applied
+        let mut compressor = Compressor::new(SAMPLE_RATE, -20.0, 4.0, 0.005, 0.1, 6.0);
+        let input = 10f32.powf(-26.0 / 20.0);
+        let output = process_constant(&mut compressor, input);
+
+        assert!(
+            (to_db(output) + 20.0).abs() < 0.05,
+            "output: {}",
+            to_db(output)
+        );
+    }
+
+    #[test]
+    fn test_disabled_is_identity() {
+        let mut compressor = Compressor::night_mode(SAMPLE_RATE);
+        let output = process_constant(&mut compressor, 1.0);
+
+        assert_eq!(output, 1.0);
+    }
+
+    #[test]
+    fn test_night_mode_reduces_dynamic_range() {
+        let mut compressor = Compressor::night_mode(SAMPLE_RATE);
+        compressor.set_enabled(true);
+        let loud = process_constant(&mut compressor, 1.0);
+        compressor.reset();
+        let quiet = process_constant(&mut compressor, 0.01);
+
+        // The input range is 40 dB
+        let range = to_db(loud) - to_db(quiet);
+        assert!(range < 30.0, "range: {}", range);
+        assert!(loud < 1.0);
+    }
+}
@@ -0,0 +1,205 @@
+use serde::{Deserialize, Serialize};
+
+use super::{AudioFilter, Biquad};
+
+/// Number of bands of the graphic equalizer
+pub const BAND_COUNT: usize = 10;
+/// The center frequency of each band, unit: Hz
+pub const BAND_FREQUENCIES: [f32; BAND_COUNT] = [
+    31.0, 62.0, 125.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0, 8000.0, 16000.0,
+];
+/// The range of band gain, unit: dB
+pub const MAX_BAND_GAIN: f32 = 12.0;
+/// The quality factor of each band, about one octave wide
+const BAND_Q: f32 = 1.41;
+/// Bands whose center frequency is too close to Nyquist frequency are skipped
+const NYQUIST_LIMIT: f32 = 0.45;
+
+/// Built-in equalizer presets
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EqPreset {
+    Flat,
+    Rock,
+    Pop,
+    Jazz,
+    Classical,
+    BassBoost,
+    TrebleBoost,
+    Vocal,
+    /// Gains adjusted by user
+    Custom,
+}
+
+impl EqPreset {
+    /// The gain of each band, unit: dB. `Custom` has no predefined gains, flat gains are returned
+    pub fn gains(&self) -> [f32; BAND_COUNT] {
+        match self {
+            EqPreset::Flat | EqPreset::Custom => [0.0; BAND_COUNT],
+            EqPreset::Rock => [5.0, 4.0, 3.0, 1.0, -1.0, -1.0, 1.0, 3.0, 4.0, 5.0],
+            EqPreset::Pop => [-1.0, 0.0, 2.0, 4.0, 5.0, 4.0, 2.0, 0.0, -1.0, -1.0],
+            EqPreset::Jazz => [3.0, 2.0, 1.0, 2.0, -1.0, -1.0, 0.0, 1.0, 2.0, 3.0],
+            EqPreset::Classical => [4.0, 3.0, 2.0, 1.0, -1.0, -1.0, 0.0, 2.0, 3.0, 4.0],
+            EqPreset::BassBoost => [7.0, 6.0, 5.0, 3.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+            EqPreset::TrebleBoost => [0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 3.0, 5.0, 6.0, 7.0],
+            EqPreset::Vocal => [-2.0, -2.0, -1.0, 1.0, 3.0, 4.0, 3.0, 1.0, 0.0, -1.0],
+        }
+    }
+
+    /// The next built-in preset, it's used to cycle through presets with keyboard
+    pub fn next(&self) -> Self {
+        match self {
+            EqPreset::Flat => EqPreset::Rock,
+            EqPreset::Rock => EqPreset::Pop,
+            EqPreset::Pop => EqPreset::Jazz,
+            EqPreset::Jazz => EqPreset::Classical,
+            EqPreset::Classical => EqPreset::BassBoost,
+            EqPreset::BassBoost => EqPreset::TrebleBoost,
+            EqPreset::TrebleBoost => EqPreset::Vocal,
+            EqPreset::Vocal | EqPreset::Custom => EqPreset::Flat,
+        }
+    }
+}
+
+/// Graphic equalizer with `BAND_COUNT` peaking bands
+pub struct Equalizer {
+    sample_rate: f32,
+    gains: [f32; BAND_COUNT],
+    /// Filters of bands, `None` means the band is skipped
+    bands: Vec<Option<Biquad>>,
+}
+
+impl Equalizer {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            sample_rate,
+            gains: [0.0; BAND_COUNT],
+            bands: (0..BAND_COUNT).map(|_| None).collect(),
+        }
+    }
+
+    /// Set the gain of each band, unit: dB, range: [-MAX_BAND_GAIN, MAX_BAND_GAIN]
+    pub fn set_gains(&mut self, gains: &[f32; BAND_COUNT]) {
+        for (index, gain) in gains.iter().enumerate() {
+            let gain = gain.clamp(-MAX_BAND_GAIN, MAX_BAND_GAIN);
+            let frequency = BAND_FREQUENCIES[index];
+            self.gains[index] = gain;
+
+            // Bands with zero gain are identity, skip them to save cpu
+            if gain == 0.0 || frequency >= self.sample_rate * NYQUIST_LIMIT {
+                self.bands[index] = None;
+                continue;
+            }
+
+            let new_band = Biquad::peaking(frequency, BAND_Q, gain, self.sample_rate);
+            match self.bands[index].as_mut() {
+                Some(band) => band.set_coefficients(&new_band),
+                None => self.bands[index] = Some(new_band),
+            }
+        }
+    }
+
+    pub fn gains(&self) -> &[f32; BAND_COUNT] {
+        &self.gains
+    }
+}
+
+impl AudioFilter for Equalizer {
+    fn process(&mut self, samples: &mut [f32], channels: usize) {
+        for band in self.bands.iter_mut().flatten() {
+            band.process(samples, channels);
+        }
+    }
+
+    fn reset(&mut self) {
+        for band in self.bands.iter_mut().flatten() {
+            band.reset();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sound::dsp::test_util::{peak, sine, to_db};
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    #[test]
+    fn test_flat_is_identity() {
+        let mut eq = Equalizer::new(SAMPLE_RATE);
+        eq.set_gains(&EqPreset::Flat.gains());
+
+        let input = sine(440.0, 0.5, SAMPLE_RATE, 4800, 2);
+        let mut output = input.clone();
+        eq.process(&mut output, 2);
+
+        assert_eq!(input, output);
+    }
+
+    #[test]
+    fn test_peaking_gain_at_center() {
+        // The response of a peaking filter at its center frequency equals to its gain
+        for gain in [-12.0, -6.0, 6.0, 12.0] {
+            let mut filter = Biquad::peaking(1000.0, BAND_Q, gain, SAMPLE_RATE);
+            let mut samples = sine(1000.0, 0.25, SAMPLE_RATE, 48000, 1);
+            filter.process(&mut samples, 1);
+
+            let measured = to_db(peak(&samples, 1, 0, 24000) / 0.25);
+            assert!(
+                (measured - gain).abs() < 0.1,
+                "gain: {}, measured: {}",
+                gain,
+                measured
+            );
+        }
+    }
+
+    #[test]
+    fn test_peaking_far_from_center() {
+        // Far away from the center frequency, the response is close to unity
+        let mut filter = Biquad::peaking(8000.0, BAND_Q, 12.0, SAMPLE_RATE);
+        let mut samples = sine(100.0, 0.25, SAMPLE_RATE, 48000, 1);
+        filter.process(&mut samples, 1);
+
+        let measured = to_db(peak(&samples, 1, 0, 24000) / 0.25);
+        assert!(measured.abs() < 0.1, "measured: {}", measured);
+    }
+
+    #[test]
+    fn test_channels_are_independent() {
+        let mut eq = Equalizer::new(SAMPLE_RATE);
+        let mut gains = [0.0; BAND_COUNT];
+        gains[5] = 6.0;
+        eq.set_gains(&gains);
+
+        // Left channel has signal, right channel is silent
+        let mut samples = sine(1000.0, 0.25, SAMPLE_RATE, 48000, 2);
+        for frame in samples.chunks_mut(2) {
+            frame[1] = 0.0;
+        }
+        eq.process(&mut samples, 2);
+
+        assert!((to_db(peak(&samples, 2, 0, 24000) / 0.25) - 6.0).abs() < 0.2);
+        assert_eq!(peak(&samples, 2, 1, 0), 0.0);
+    }
+
+    #[test]
+    fn test_bands_above_nyquist_are_skipped() {
+        let mut eq = Equalizer::new(22050.0);
+        eq.set_gains(&[6.0; BAND_COUNT]);
+
+        assert!(eq.bands[9].is_none());
+        assert!(eq.bands[8].is_some());
+    }
+
+    #[test]
+    fn test_preset_cycle() {
+        let mut preset = EqPreset::Flat;
+        for _ in 0..8 {
+            preset = preset.next();
+        }
+
+        assert_eq!(preset, EqPreset::Flat);
+        assert_eq!(EqPreset::Custom.next(), EqPreset::Flat);
+    }
+}
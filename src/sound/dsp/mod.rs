@@ -0,0 +1,147 @@
+mod biquad;
+mod compressor;
+mod equalizer;
+mod stereo;
+
+pub use biquad::Biquad;
+pub use compressor::Compressor;
+pub use equalizer::{EqPreset, Equalizer, BAND_COUNT, BAND_FREQUENCIES, MAX_BAND_GAIN};
+pub use stereo::{Balance, ChannelSwap};
+
+use serde::{Deserialize, Serialize};
+
+/// The common interface of audio filters in the dsp chain
+pub trait AudioFilter {
+    /// Process the interleaved samples in place
+    /// # Arguments
+    /// * `samples` - Interleaved samples, the length should be a multiple of `channels`
+    /// * `channels` - Number of channels
+    fn process(&mut self, samples: &mut [f32], channels: usize);
+    /// Clear the internal state, it should be called after seek
+    fn reset(&mut self);
+}
+
+/// Options of the dsp chain, which are persisted in settings
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct DspSettings {
+    /// The preset of equalizer
+    pub eq_preset: EqPreset,
+    /// Gains of each band while the preset is `Custom`, unit: dB
+    pub eq_gains: [f32; BAND_COUNT],
+    /// Compress the dynamic range, so that quiet parts can be heard without loud parts being too loud
+    pub night_mode: bool,
+    /// Stereo balance, range: [-1, 1], -1 means left only, 1 means right only
+    pub balance: f32,
+    /// Swap left and right channels
+    pub swap_channels: bool,
+}
+
+impl Default for DspSettings {
+    fn default() -> Self {
+        Self {
+            eq_preset: EqPreset::Flat,
+            eq_gains: [0.0; BAND_COUNT],
+            night_mode: false,
+            balance: 0.0,
+            swap_channels: false,
+        }
+    }
+}
+
+impl DspSettings {
+    /// Get the gains of equalizer bands with current preset
+    pub fn band_gains(&self) -> [f32; BAND_COUNT] {
+        match self.eq_preset {
+            EqPreset::Custom => self.eq_gains,
+            preset => preset.gains(),
+        }
+    }
+}
+
+/// The chain of audio filters which are applied before output.
+/// Processing order: channel swap, balance, equalizer, compressor
+pub struct DspChain {
+    channels: usize,
+    swap: ChannelSwap,
+    balance: Balance,
+    equalizer: Equalizer,
+    compressor: Compressor,
+}
+
+impl DspChain {
+    pub fn new(sample_rate: i32, channels: u8, settings: &DspSettings) -> Self {
+        let mut chain = Self {
+            channels: channels.max(1) as usize,
+            swap: ChannelSwap::new(),
+            balance: Balance::new(),
+            equalizer: Equalizer::new(sample_rate as f32),
+            compressor: Compressor::night_mode(sample_rate as f32),
+        };
+        chain.apply_settings(settings);
+
+        chain
+    }
+
+    /// Apply the new options, filter states are kept so that there's no click while adjusting
+    pub fn apply_settings(&mut self, settings: &DspSettings) {
+        self.swap.set_enabled(settings.swap_channels);
+        self.balance.set_balance(settings.balance);
+        self.equalizer.set_gains(&settings.band_gains());
+        self.compressor.set_enabled(settings.night_mode);
+    }
+
+    pub fn process(&mut self, samples: &mut [f32]) {
+        let channels = self.channels;
+
+        self.swap.process(samples, channels);
+        self.balance.process(samples, channels);
+        self.equalizer.process(samples, channels);
+        self.compressor.process(samples, channels);
+    }
+
+    pub fn reset(&mut self) {
+        self.swap.reset();
+        self.balance.reset();
+        self.equalizer.reset();
+        self.compressor.reset();
+    }
+}
+
+/// Helpers for the tests of filters
+#[cfg(test)]
+mod test_util {
+    use std::f32::consts::PI;
+
+    /// Generate interleaved sine wave, all channels have the same samples
+    pub fn sine(
+        frequency: f32,
+        amplitude: f32,
+        sample_rate: f32,
+        len: usize,
+        channels: usize,
+    ) -> Vec<f32> {
+        let mut samples = Vec::with_capacity(len * channels);
+        for n in 0..len {
+            let value = amplitude * (2.0 * PI * frequency * n as f32 / sample_rate).sin();
+            for _ in 0..channels {
+                samples.push(value);
+            }
+        }
+
+        samples
+    }
+
+    /// The peak value of the specified channel, skipping the first `skip` frames
+    pub fn peak(samples: &[f32], channels: usize, channel: usize, skip: usize) -> f32 {
+        samples
+            .chunks(channels)
+            .skip(skip)
+            .map(|frame| frame[channel].abs())
+            .fold(0.0, f32::max)
+    }
+
+    pub fn to_db(value: f32) -> f32 {
+        20.0 * value.log10()
+    }
+}
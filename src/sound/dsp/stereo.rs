@@ -0,0 +1,117 @@
+use super::AudioFilter;
+
+/// Adjust the relative level of left and right channels,
+/// only the first two channels are affected
+#[derive(Default)]
+pub struct Balance {
+    /// Range: [-1, 1], -1 means left only, 1 means right only
+    balance: f32,
+}
+
+impl Balance {
+    pub fn new() -> Self {
+        Self { balance: 0.0 }
+    }
+
+    pub fn set_balance(&mut self, balance: f32) {
+        self.balance = balance.clamp(-1.0, 1.0);
+    }
+
+    /// The gains of left and right channels, the channel on the side of balance is kept unchanged
+    fn gains(&self) -> (f32, f32) {
+        let left = (1.0 - self.balance).min(1.0);
+        let right = (1.0 + self.balance).min(1.0);
+
+        (left, right)
+    }
+}
+
+impl AudioFilter for Balance {
+    fn process(&mut self, samples: &mut [f32], channels: usize) {
+        if self.balance == 0.0 || channels < 2 {
+            return;
+        }
+
+        let (left, right) = self.gains();
+        for frame in samples.chunks_exact_mut(channels) {
+            frame[0] *= left;
+            frame[1] *= right;
+        }
+    }
+
+    fn reset(&mut self) {}
+}
+
+/// Swap left and right channels
+#[derive(Default)]
+pub struct ChannelSwap {
+    enabled: bool,
+}
+
+impl ChannelSwap {
+    pub fn new() -> Self {
+        Self { enabled: false }
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+}
+
+impl AudioFilter for ChannelSwap {
+    fn process(&mut self, samples: &mut [f32], channels: usize) {
+        if !self.enabled || channels < 2 {
+            return;
+        }
+
+        for frame in samples.chunks_exact_mut(channels) {
+            frame.swap(0, 1);
+        }
+    }
+
+    fn reset(&mut self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_balance() {
+        let mut balance = Balance::new();
+        let mut samples = vec![1.0, 1.0, 1.0, 1.0];
+        balance.process(&mut samples, 2);
+        assert_eq!(samples, vec![1.0, 1.0, 1.0, 1.0]);
+
+        balance.set_balance(0.5);
+        balance.process(&mut samples, 2);
+        assert_eq!(samples, vec![0.5, 1.0, 0.5, 1.0]);
+
+        let mut samples = vec![1.0, 1.0];
+        balance.set_balance(-1.0);
+        balance.process(&mut samples, 2);
+        assert_eq!(samples, vec![1.0, 0.0]);
+    }
+
+    #[test]
+    fn test_balance_mono_is_untouched() {
+        let mut balance = Balance::new();
+        balance.set_balance(1.0);
+        let mut samples = vec![1.0, 1.0];
+        balance.process(&mut samples, 1);
+
+        assert_eq!(samples, vec![1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_channel_swap() {
+        let mut swap = ChannelSwap::new();
+        let mut samples = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        swap.process(&mut samples, 3);
+        assert_eq!(samples, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+
+        swap.set_enabled(true);
+        swap.process(&mut samples, 3);
+        assert_eq!(samples, vec![2.0, 1.0, 3.0, 5.0, 4.0, 6.0]);
+    }
+}
@@ -0,0 +1,129 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::Write,
+    path::Path,
+    time::UNIX_EPOCH,
+};
+
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use super::LoudnessInfo;
+use crate::util::error::SuperError;
+
+const FILE_NAME: &str = "loudness.yml";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+struct CacheEntry {
+    info: LoudnessInfo,
+    /// Size and modified time of the file, the result is invalid if the file is changed
+    size: u64,
+    modified: u64,
+}
+
+/// Results of loudness analysis, so that files are not analyzed again
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LoudnessCache {
+    /// The key is the path of media
+    entries: BTreeMap<String, CacheEntry>,
+}
+
+impl LoudnessCache {
+    /// Get the result of the file, `None` is returned if the file is changed after analysis
+    pub fn get(&self, path: &Path) -> Option<LoudnessInfo> {
+        let entry = self.entries.get(&path.to_string_lossy().to_string())?;
+        let (size, modified) = Self::file_stamp(path)?;
+        if entry.size != size || entry.modified != modified {
+            return None;
+        }
+
+        Some(entry.info)
+    }
+
+    pub fn insert(&mut self, path: &Path, info: LoudnessInfo) {
+        if let Some((size, modified)) = Self::file_stamp(path) {
+            let entry = CacheEntry {
+                info,
+                size,
+                modified,
+            };
+            self.entries
+                .insert(path.to_string_lossy().to_string(), entry);
+        }
+    }
+
+    /// Size and modified time in seconds of the file
+    fn file_stamp(path: &Path) -> Option<(u64, u64)> {
+        let metadata = fs::metadata(path).ok()?;
+        let modified = metadata
+            .modified()
+            .ok()?
+            .duration_since(UNIX_EPOCH)
+            .ok()?
+            .as_secs();
+
+        Some((metadata.len(), modified))
+    }
+}
+
+/// Load the cache from disk
+pub fn load() -> Result<LoudnessCache, SuperError> {
+    // If the file does not exist, use an empty cache
+    if !Path::new(FILE_NAME).try_exists()? {
+        return Ok(LoudnessCache::default());
+    }
+
+    let f = File::open(FILE_NAME)?;
+    let cache = serde_yaml::from_reader(f)?;
+
+    Ok(cache)
+}
+
+/// Load the cache from disk, if any error occurred, log the error and use an empty cache
+pub fn load_or_default() -> LoudnessCache {
+    match load() {
+        Ok(cache) => cache,
+        Err(err) => {
+            error!("failed to load {}: {}", FILE_NAME, err);
+            LoudnessCache::default()
+        }
+    }
+}
+
+/// Save the cache to disk
+pub fn save(cache: &LoudnessCache) -> Result<(), SuperError> {
+    let mut f = File::create(FILE_NAME)?;
+    serde_yaml::to_writer(&mut f, cache)?;
+    f.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_cache_invalidation() {
+        let path = env::temp_dir().join("nt_player_loudness_cache_test.bin");
+        fs::write(&path, [0u8; 16]).unwrap();
+
+        let info = LoudnessInfo {
+            integrated_lufs: -20.0,
+            peak: 0.9,
+        };
+        let mut cache = LoudnessCache::default();
+        assert_eq!(None, cache.get(&path));
+
+        cache.insert(&path, info);
+        assert_eq!(Some(info), cache.get(&path));
+
+        // The size is changed, so the result is invalid
+        fs::write(&path, [0u8; 32]).unwrap();
+        assert_eq!(None, cache.get(&path));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
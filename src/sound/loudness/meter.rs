@@ -0,0 +1,265 @@
+use std::{collections::VecDeque, f64::consts::PI};
+
+use crate::sound::dsp::Biquad;
+
+/// Duration of a gating block is 400ms, it consists of 4 sub blocks with 75% overlap
+const SUB_BLOCKS_PER_BLOCK: usize = 4;
+/// Number of sub blocks per second
+const SUB_BLOCKS_PER_SECOND: i32 = 10;
+/// Blocks quieter than it are ignored, unit: LUFS
+const ABSOLUTE_GATE: f64 = -70.0;
+/// Blocks quieter than the ungated loudness minus it are ignored, unit: LU
+const RELATIVE_GATE: f64 = 10.0;
+
+/// Measure the integrated loudness with the algorithm described in ITU-R BS.1770-4 and EBU R128
+pub struct LoudnessMeter {
+    channels: usize,
+    /// The weight of each channel
+    weights: Vec<f64>,
+    /// The first stage of K-weighting, models the acoustic effect of the head
+    pre_filter: Biquad,
+    /// The second stage of K-weighting, the revised low frequency B-curve
+    rlb_filter: Biquad,
+    /// Number of frames in a sub block
+    sub_block_frames: usize,
+    /// Number of frames in current sub block
+    frame_count: usize,
+    /// The sum of squares of each channel in current sub block
+    sums: Vec<f64>,
+    /// Weighted mean squares of the latest sub blocks
+    sub_blocks: VecDeque<f64>,
+    /// Weighted mean squares of all gating blocks
+    blocks: Vec<f64>,
+    /// The maximum absolute sample value
+    peak: f32,
+}
+
+impl LoudnessMeter {
+    pub fn new(sample_rate: i32, channels: u8) -> Self {
+        let channels = channels.max(1) as usize;
+        let (pre_filter, rlb_filter) = Self::k_weighting(sample_rate as f64);
+
+        Self {
+            channels,
+            weights: (0..channels)
+                .map(|index| Self::channel_weight(index, channels))
+                .collect(),
+            pre_filter,
+            rlb_filter,
+            sub_block_frames: (sample_rate / SUB_BLOCKS_PER_SECOND).max(1) as usize,
+            frame_count: 0,
+            sums: vec![0.0; channels],
+            sub_blocks: VecDeque::with_capacity(SUB_BLOCKS_PER_BLOCK),
+            blocks: Vec::new(),
+            peak: 0.0,
+        }
+    }
+
+    /// Feed interleaved samples
+    pub fn push_samples(&mut self, samples: &[f32]) {
+        for sample in samples {
+            self.peak = self.peak.max(sample.abs());
+        }
+
+        let mut weighted = samples.to_vec();
+        self.pre_filter.process(&mut weighted, self.channels);
+        self.rlb_filter.process(&mut weighted, self.channels);
+
+        for frame in weighted.chunks_exact(self.channels) {
+            for (sum, sample) in self.sums.iter_mut().zip(frame) {
+                *sum += (*sample as f64) * (*sample as f64);
+            }
+
+            self.frame_count += 1;
+            if self.frame_count == self.sub_block_frames {
+                self.finish_sub_block();
+            }
+        }
+    }
+
+    /// The gated loudness of all samples pushed, unit: LUFS.
+    /// `None` is returned if there's no block louder than the absolute gate
+    pub fn integrated_loudness(&self) -> Option<f64> {
+        let absolute_gated: Vec<f64> = self
+            .blocks
+            .iter()
+            .copied()
+            .filter(|energy| Self::to_lufs(*energy) > ABSOLUTE_GATE)
+            .collect();
+        if absolute_gated.is_empty() {
+            return None;
+        }
+
+        let relative_gate = Self::to_lufs(Self::mean(&absolute_gated)) - RELATIVE_GATE;
+        let relative_gated: Vec<f64> = absolute_gated
+            .into_iter()
+            .filter(|energy| Self::to_lufs(*energy) > relative_gate)
+            .collect();
+
+        Some(Self::to_lufs(Self::mean(&relative_gated)))
+    }
+
+    /// The maximum absolute sample value
+    pub fn peak(&self) -> f32 {
+        self.peak
+    }
+
+    fn finish_sub_block(&mut self) {
+        let frames = self.frame_count as f64;
+        let energy = self
+            .sums
+            .iter()
+            .zip(self.weights.iter())
+            .map(|(sum, weight)| weight * sum / frames)
+            .sum();
+
+        self.sums.iter_mut().for_each(|sum| *sum = 0.0);
+        self.frame_count = 0;
+
+        if self.sub_blocks.len() == SUB_BLOCKS_PER_BLOCK {
+            self.sub_blocks.pop_front();
+        }
+        self.sub_blocks.push_back(energy);
+        if self.sub_blocks.len() == SUB_BLOCKS_PER_BLOCK {
+            let block = self.sub_blocks.iter().sum::<f64>() / SUB_BLOCKS_PER_BLOCK as f64;
+            self.blocks.push(block);
+        }
+    }
+
+    fn mean(values: &[f64]) -> f64 {
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+
+    fn to_lufs(energy: f64) -> f64 {
+        -0.691 + 10.0 * energy.max(f64::MIN_POSITIVE).log10()
+    }
+
+    /// Surround channels are louder for human, LFE channel is not counted.
+    /// The channel order is L, R, C, LFE, Ls, Rs for 5.1 and L, R, C, Ls, Rs for 5.0
+    fn channel_weight(index: usize, channels: usize) -> f64 {
+        match (channels, index) {
+            (6, 3) => 0.0,
+            (6, 4) | (6, 5) | (5, 3) | (5, 4) => 1.41,
+            _ => 1.0,
+        }
+    }
+
+    /// The coefficients of K-weighting filters for the specified sample rate,
+    /// they are derived from the analog prototypes of the 48kHz filters in BS.1770
+    fn k_weighting(sample_rate: f64) -> (Biquad, Biquad) {
+        // High shelf
+        let f0 = 1681.974450955533;
+        let gain = 3.999843853973347;
+        let q = 0.7071752369554196;
+        let k = (PI * f0 / sample_rate).tan();
+        let vh = 10f64.powf(gain / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let pre_filter = Biquad::new(
+            [
+                (vh + vb * k / q + k * k) as f32,
+                (2.0 * (k * k - vh)) as f32,
+                (vh - vb * k / q + k * k) as f32,
+            ],
+            [
+                (1.0 + k / q + k * k) as f32,
+                (2.0 * (k * k - 1.0)) as f32,
+                (1.0 - k / q + k * k) as f32,
+            ],
+        );
+
+        // High pass
+        let f0 = 38.13547087602444;
+        let q = 0.5003270373238773;
+        let k = (PI * f0 / sample_rate).tan();
+        let rlb_filter = Biquad::new(
+            [1.0, -2.0, 1.0],
+            [
+                (1.0 + k / q + k * k) as f32,
+                (2.0 * (k * k - 1.0)) as f32,
+                (1.0 - k / q + k * k) as f32,
+            ],
+        );
+
+        (pre_filter, rlb_filter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    const SAMPLE_RATE: i32 = 48000;
+
+    /// Stereo 1kHz sine wave with the specified level of each channel, unit: dBFS
+    fn sine(level_db: f32, secs: f32) -> Vec<f32> {
+        let amplitude = 10f32.powf(level_db / 20.0);
+        let frames = (SAMPLE_RATE as f32 * secs) as usize;
+        let mut samples = Vec::with_capacity(frames * 2);
+        for n in 0..frames {
+            let value = amplitude * (2.0 * PI * 1000.0 * n as f32 / SAMPLE_RATE as f32).sin();
+            samples.push(value);
+            samples.push(value);
+        }
+
+        samples
+    }
+
+    fn measure(parts: &[(f32, f32)]) -> Option<f64> {
+        let mut meter = LoudnessMeter::new(SAMPLE_RATE, 2);
+        for (level_db, secs) in parts {
+            meter.push_samples(&sine(*level_db, *secs));
+        }
+
+        meter.integrated_loudness()
+    }
+
+    // The reference signals below come from EBU Tech 3341
+
+    #[test]
+    fn test_reference_minus_23() {
+        let loudness = measure(&[(-23.0, 20.0)]).unwrap();
+        assert!((loudness + 23.0).abs() < 0.1, "loudness: {}", loudness);
+    }
+
+    #[test]
+    fn test_reference_minus_33() {
+        let loudness = measure(&[(-33.0, 20.0)]).unwrap();
+        assert!((loudness + 33.0).abs() < 0.1, "loudness: {}", loudness);
+    }
+
+    #[test]
+    fn test_relative_gate() {
+        let loudness = measure(&[(-36.0, 10.0), (-23.0, 60.0), (-36.0, 10.0)]).unwrap();
+        assert!((loudness + 23.0).abs() < 0.1, "loudness: {}", loudness);
+    }
+
+    #[test]
+    fn test_absolute_gate() {
+        let loudness = measure(&[
+            (-72.0, 10.0),
+            (-36.0, 10.0),
+            (-23.0, 60.0),
+            (-36.0, 10.0),
+            (-72.0, 10.0),
+        ])
+        .unwrap();
+        assert!((loudness + 23.0).abs() < 0.1, "loudness: {}", loudness);
+    }
+
+    #[test]
+    fn test_silence() {
+        let mut meter = LoudnessMeter::new(SAMPLE_RATE, 2);
+        meter.push_samples(&vec![0.0; SAMPLE_RATE as usize * 2]);
+
+        assert_eq!(None, meter.integrated_loudness());
+    }
+
+    #[test]
+    fn test_peak() {
+        let mut meter = LoudnessMeter::new(SAMPLE_RATE, 2);
+        meter.push_samples(&[0.1, -0.5, 0.25, 0.0]);
+
+        assert_eq!(0.5, meter.peak());
+    }
+}
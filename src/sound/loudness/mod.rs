@@ -0,0 +1,243 @@
+mod cache;
+mod meter;
+mod scanner;
+
+pub use cache::{load_or_default, save, LoudnessCache};
+pub use meter::LoudnessMeter;
+pub use scanner::LoudnessScanner;
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// The target loudness, the same as ReplayGain 2.0, unit: LUFS
+pub const REFERENCE_LUFS: f64 = -18.0;
+/// The reference loudness of R128 gain tags used by Opus, unit: LUFS
+const R128_REFERENCE_LUFS: f64 = -23.0;
+/// The range of normalization gain, unit: dB
+const MIN_GAIN_DB: f64 = -24.0;
+const MAX_GAIN_DB: f64 = 12.0;
+
+/// Which gain is used to normalize the loudness
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalizationMode {
+    Off,
+    /// Every track has the same loudness
+    Track,
+    /// Keep the loudness differences between tracks of the same album
+    Album,
+}
+
+impl NormalizationMode {
+    pub fn next(&self) -> Self {
+        match self {
+            NormalizationMode::Off => NormalizationMode::Track,
+            NormalizationMode::Track => NormalizationMode::Album,
+            NormalizationMode::Album => NormalizationMode::Off,
+        }
+    }
+}
+
+/// The result of loudness analysis
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct LoudnessInfo {
+    /// Unit: LUFS
+    pub integrated_lufs: f64,
+    /// The maximum absolute sample value
+    pub peak: f32,
+}
+
+/// Gains and peaks read from ReplayGain or R128 tags, gains are relative to `REFERENCE_LUFS`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ReplayGain {
+    pub track_gain: Option<f64>,
+    pub track_peak: Option<f64>,
+    pub album_gain: Option<f64>,
+    pub album_peak: Option<f64>,
+}
+
+impl ReplayGain {
+    /// Read gains from the metadata tags, `None` is returned if there's no gain tag.
+    /// Keys of tags are case insensitive, since different taggers write them differently
+    pub fn from_tags(tags: &BTreeMap<String, String>) -> Option<Self> {
+        let mut rg = ReplayGain::default();
+        for (key, value) in tags {
+            match key.to_ascii_uppercase().as_str() {
+                "REPLAYGAIN_TRACK_GAIN" => rg.track_gain = Self::parse_number(value),
+                "REPLAYGAIN_TRACK_PEAK" => rg.track_peak = Self::parse_number(value),
+                "REPLAYGAIN_ALBUM_GAIN" => rg.album_gain = Self::parse_number(value),
+                "REPLAYGAIN_ALBUM_PEAK" => rg.album_peak = Self::parse_number(value),
+                // ReplayGain tags take precedence over R128 tags
+                "R128_TRACK_GAIN" => {
+                    rg.track_gain = rg.track_gain.or_else(|| Self::parse_r128(value))
+                }
+                "R128_ALBUM_GAIN" => {
+                    rg.album_gain = rg.album_gain.or_else(|| Self::parse_r128(value))
+                }
+                _ => {}
+            }
+        }
+
+        if rg.track_gain.is_none() && rg.album_gain.is_none() {
+            return None;
+        }
+
+        Some(rg)
+    }
+
+    /// The gain and peak for the specified mode, the other kind is used if it's missing
+    pub fn gain(&self, mode: NormalizationMode) -> Option<(f64, Option<f64>)> {
+        let track = self.track_gain.map(|gain| (gain, self.track_peak));
+        let album = self.album_gain.map(|gain| (gain, self.album_peak));
+
+        match mode {
+            NormalizationMode::Off => None,
+            NormalizationMode::Track => track.or(album),
+            NormalizationMode::Album => album.or(track),
+        }
+    }
+
+    /// Parse value like "-6.54 dB" or "0.988553"
+    fn parse_number(value: &str) -> Option<f64> {
+        let value = value.trim();
+        let value = value
+            .strip_suffix("dB")
+            .or_else(|| value.strip_suffix("db"))
+            .unwrap_or(value);
+
+        value.trim().parse().ok()
+    }
+
+    /// R128 gain is a Q7.8 fixed point number relative to `R128_REFERENCE_LUFS`
+    fn parse_r128(value: &str) -> Option<f64> {
+        let gain = value.trim().parse::<i32>().ok()? as f64 / 256.0;
+
+        Some(gain + REFERENCE_LUFS - R128_REFERENCE_LUFS)
+    }
+}
+
+/// Compute the linear gain to normalize the loudness, tags are preferred to measured loudness.
+/// The gain is reduced if the peak would exceed full scale after normalization
+pub fn normalization_gain(
+    mode: NormalizationMode,
+    replay_gain: Option<&ReplayGain>,
+    measured: Option<&LoudnessInfo>,
+) -> f32 {
+    if mode == NormalizationMode::Off {
+        return 1.0;
+    }
+
+    let tagged = replay_gain.and_then(|rg| rg.gain(mode));
+    let (gain_db, peak) = match (tagged, measured) {
+        (Some(tagged), _) => tagged,
+        (None, Some(info)) => (
+            REFERENCE_LUFS - info.integrated_lufs,
+            Some(info.peak as f64),
+        ),
+        (None, None) => return 1.0,
+    };
+
+    let mut gain_db = gain_db.clamp(MIN_GAIN_DB, MAX_GAIN_DB);
+    if let Some(peak) = peak.filter(|p| *p > 0.0) {
+        gain_db = gain_db.min(-20.0 * peak.log10());
+    }
+
+    10f64.powf(gain_db / 20.0) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_from_tags() {
+        let rg = ReplayGain::from_tags(&tags(&[
+            ("replaygain_track_gain", "-6.50 dB"),
+            ("REPLAYGAIN_TRACK_PEAK", "0.988553"),
+            ("REPLAYGAIN_ALBUM_GAIN", "-7.00 dB"),
+            ("title", "Song"),
+        ]))
+        .unwrap();
+
+        assert_eq!(Some(-6.5), rg.track_gain);
+        assert_eq!(Some(0.988553), rg.track_peak);
+        assert_eq!(Some(-7.0), rg.album_gain);
+        assert_eq!(None, rg.album_peak);
+
+        assert_eq!(None, ReplayGain::from_tags(&tags(&[("title", "Song")])));
+    }
+
+    #[test]
+    fn test_r128_tags() {
+        // -512 / 256 = -2 dB relative to -23 LUFS, that's +3 dB relative to -18 LUFS
+        let rg = ReplayGain::from_tags(&tags(&[("R128_TRACK_GAIN", "-512")])).unwrap();
+        assert_eq!(Some(3.0), rg.track_gain);
+
+        // ReplayGain tag wins
+        let rg = ReplayGain::from_tags(&tags(&[
+            ("R128_TRACK_GAIN", "-512"),
+            ("REPLAYGAIN_TRACK_GAIN", "1.0 dB"),
+        ]))
+        .unwrap();
+        assert_eq!(Some(1.0), rg.track_gain);
+    }
+
+    #[test]
+    fn test_mode_fallback() {
+        let rg = ReplayGain {
+            track_gain: Some(-3.0),
+            ..Default::default()
+        };
+
+        assert_eq!(Some((-3.0, None)), rg.gain(NormalizationMode::Album));
+        assert_eq!(None, rg.gain(NormalizationMode::Off));
+    }
+
+    #[test]
+    fn test_normalization_gain() {
+        let rg = ReplayGain {
+            track_gain: Some(-6.0),
+            ..Default::default()
+        };
+        let gain = normalization_gain(NormalizationMode::Track, Some(&rg), None);
+        assert!((gain - 0.501).abs() < 0.001);
+
+        assert_eq!(
+            1.0,
+            normalization_gain(NormalizationMode::Off, Some(&rg), None)
+        );
+        assert_eq!(
+            1.0,
+            normalization_gain(NormalizationMode::Track, None, None)
+        );
+    }
+
+    #[test]
+    fn test_measured_loudness() {
+        // -28 LUFS needs +10 dB to reach -18 LUFS
+        let info = LoudnessInfo {
+            integrated_lufs: -28.0,
+            peak: 0.1,
+        };
+        let gain = normalization_gain(NormalizationMode::Track, None, Some(&info));
+        assert!((gain - 3.162).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_peak_limit() {
+        // +10 dB would make the peak exceed full scale, only +6 dB is allowed
+        let info = LoudnessInfo {
+            integrated_lufs: -28.0,
+            peak: 0.5,
+        };
+        let gain = normalization_gain(NormalizationMode::Track, None, Some(&info));
+        assert!((gain * 0.5 - 1.0).abs() < 0.001);
+    }
+}
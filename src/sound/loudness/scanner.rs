@@ -0,0 +1,166 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use crossbeam::channel::{unbounded, Sender};
+use log::{info, warn};
+use rsmpeg::{
+    avcodec::{AVCodec, AVCodecContext},
+    ffi::{
+        AVMediaType_AVMEDIA_TYPE_AUDIO as AVMEDIATYPE_AVMEDIA_TYPE_AUDIO,
+        AV_DISPOSITION_ATTACHED_PIC,
+    },
+};
+
+use super::{LoudnessCache, LoudnessInfo, LoudnessMeter};
+use crate::{
+    entity::EventMessage,
+    media::decoder::MediaDecoder,
+    util::{
+        error::{safe_send, SuperError},
+        sample_format,
+    },
+    EVENT_CHANNEL,
+};
+
+/// Analyze the loudness of files one by one in a background thread,
+/// `EventMessage::LoudnessAnalyzed` is sent once a file is finished
+pub struct LoudnessScanner {
+    sender: Sender<PathBuf>,
+    /// The files waiting for or under analysis, they are not queued again
+    pending: Arc<Mutex<HashSet<PathBuf>>>,
+    stop_flag: Arc<AtomicBool>,
+}
+
+impl LoudnessScanner {
+    pub fn new() -> Self {
+        let (sender, receiver) = unbounded::<PathBuf>();
+        let pending = Arc::new(Mutex::new(HashSet::new()));
+        let stop_flag = Arc::new(AtomicBool::new(false));
+
+        thread::spawn({
+            let pending = pending.clone();
+            let stop_flag = stop_flag.clone();
+            move || {
+                // The loop ends while the scanner is dropped
+                for path in receiver.iter() {
+                    match Self::analyze(&path, &stop_flag) {
+                        Ok(Some(info)) => {
+                            info!(
+                                "loudness of {:?}: {:.2} LUFS, peak {:.4}",
+                                path, info.integrated_lufs, info.peak
+                            );
+                            let msg = EventMessage::LoudnessAnalyzed(path.clone(), info);
+                            safe_send(EVENT_CHANNEL.0.send(msg));
+                        }
+                        Ok(None) => {}
+                        Err(err) => warn!("failed to analyze loudness of {:?}: {}", path, err),
+                    }
+                    pending.lock().unwrap().remove(&path);
+
+                    if stop_flag.load(Ordering::Acquire) {
+                        break;
+                    }
+                }
+            }
+        });
+
+        Self {
+            sender,
+            pending,
+            stop_flag,
+        }
+    }
+
+    /// Add the file to the queue of analysis,
+    /// it's skipped if it's already queued or its result is in the cache
+    pub fn scan(&self, path: PathBuf, cache: &LoudnessCache) {
+        if cache.get(&path).is_some() {
+            return;
+        }
+        if !self.pending.lock().unwrap().insert(path.clone()) {
+            return;
+        }
+
+        if let Err(err) = self.sender.send(path) {
+            warn!("loudness scanner is not running: {}", err);
+        }
+    }
+
+    /// Decode the whole audio stream and measure it.
+    /// `None` is returned if it's stopped or the file has no audible audio
+    fn analyze(path: &Path, stop_flag: &AtomicBool) -> Result<Option<LoudnessInfo>, SuperError> {
+        let path = path.to_str().ok_or("path is not valid unicode")?;
+        let mut ctx = MediaDecoder::get_media_context(path)?;
+
+        // Find the first audio stream, the cover is not counted
+        let mut audio = None;
+        for stream in ctx.streams() {
+            let codecpar = stream.codecpar();
+            if codecpar.codec_type != AVMEDIATYPE_AVMEDIA_TYPE_AUDIO
+                || stream.disposition & AV_DISPOSITION_ATTACHED_PIC as i32 != 0
+            {
+                continue;
+            }
+
+            let decoder = AVCodec::find_decoder(codecpar.codec_id).ok_or("no audio decoder")?;
+            let mut dctx = AVCodecContext::new(&decoder);
+            dctx.apply_codecpar(&codecpar)?;
+            dctx.open(None)?;
+
+            audio = Some((stream.index, dctx));
+            break;
+        }
+        let (index, mut dctx) = match audio {
+            Some(audio) => audio,
+            None => return Ok(None),
+        };
+
+        let mut meter: Option<LoudnessMeter> = None;
+        while let Some(packet) = ctx.read_packet()? {
+            if stop_flag.load(Ordering::Acquire) {
+                return Ok(None);
+            }
+            if packet.stream_index != index {
+                continue;
+            }
+            if let Err(err) = dctx.send_packet(Some(&packet)) {
+                warn!("send packet to context error: {}", err);
+                continue;
+            }
+
+            while let Ok(frame) = dctx.receive_frame() {
+                if let Some(samples) = sample_format::interleave_samples(&frame) {
+                    meter
+                        .get_or_insert_with(|| {
+                            LoudnessMeter::new(frame.sample_rate, frame.channels as u8)
+                        })
+                        .push_samples(&samples);
+                }
+            }
+        }
+
+        let info = meter.and_then(|meter| {
+            meter
+                .integrated_loudness()
+                .map(|integrated_lufs| LoudnessInfo {
+                    integrated_lufs,
+                    peak: meter.peak(),
+                })
+        });
+
+        Ok(info)
+    }
+}
+
+impl Drop for LoudnessScanner {
+    fn drop(&mut self) {
+        self.stop_flag.store(true, Ordering::Release);
+    }
+}
@@ -1,3 +1,7 @@
+pub mod dsp;
+pub mod loudness;
+pub mod output;
 mod sounder;
+pub mod volume;
 
 pub use sounder::Sounder;
\ No newline at end of file
@@ -0,0 +1,191 @@
+use std::collections::VecDeque;
+
+use sdl2::{
+    audio::{AudioQueue, AudioSpecDesired, AudioStatus},
+    AudioSubsystem,
+};
+
+use crate::util::error::SuperError;
+
+/// The destination of audio samples
+pub trait AudioOutput {
+    /// Append interleaved samples to the end of queue
+    fn queue(&mut self, samples: &[f32]) -> Result<(), SuperError>;
+    /// Start or continue playing
+    fn resume(&mut self);
+    /// Number of samples which are queued but not played yet
+    fn queued_samples(&self) -> usize;
+    /// Whether the device is disconnected and cannot play any more
+    fn is_lost(&self) -> bool;
+    /// The name of device, `None` means the default device
+    fn device_name(&self) -> Option<&str>;
+}
+
+/// Play samples with an SDL audio device
+pub struct SdlOutput {
+    queue: AudioQueue<f32>,
+    name: Option<String>,
+}
+
+impl SdlOutput {
+    /// Open the device with the specified name, or the default device if the name is `None`
+    pub fn open(
+        sys: &AudioSubsystem,
+        name: Option<&str>,
+        sample_rate: i32,
+        channels: u8,
+    ) -> Result<Self, SuperError> {
+        let spec = AudioSpecDesired {
+            freq: Some(sample_rate),
+            channels: Some(channels),
+            samples: None,
+        };
+        let queue = sys.open_queue::<f32, _>(name, &spec)?;
+
+        Ok(Self {
+            queue,
+            name: name.map(String::from),
+        })
+    }
+}
+
+impl AudioOutput for SdlOutput {
+    fn queue(&mut self, samples: &[f32]) -> Result<(), SuperError> {
+        self.queue.queue_audio(samples)?;
+
+        Ok(())
+    }
+
+    fn resume(&mut self) {
+        self.queue.resume();
+    }
+
+    fn queued_samples(&self) -> usize {
+        self.queue.size() as usize / std::mem::size_of::<f32>()
+    }
+
+    /// SDL reports a disconnected device as stopped, while a working device is
+    /// either playing or paused
+    fn is_lost(&self) -> bool {
+        self.queue.status() == AudioStatus::Stopped
+    }
+
+    fn device_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+}
+
+/// Discard all the samples, it's used while there's no audio device, e.g. headless testing
+#[derive(Default)]
+pub struct NullOutput {
+    /// Number of samples that have been discarded
+    discarded: usize,
+}
+
+impl NullOutput {
+    pub fn new() -> Self {
+        Self { discarded: 0 }
+    }
+
+    pub fn discarded(&self) -> usize {
+        self.discarded
+    }
+}
+
+impl AudioOutput for NullOutput {
+    fn queue(&mut self, samples: &[f32]) -> Result<(), SuperError> {
+        self.discarded += samples.len();
+
+        Ok(())
+    }
+
+    fn resume(&mut self) {}
+
+    fn queued_samples(&self) -> usize {
+        0
+    }
+
+    fn is_lost(&self) -> bool {
+        false
+    }
+
+    fn device_name(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// Keep a copy of the samples waiting in the device queue, so that they can be queued
+/// to a new device if the current one is disconnected, then no audio is lost
+#[derive(Default)]
+pub struct PrimeBuffer {
+    samples: VecDeque<f32>,
+}
+
+impl PrimeBuffer {
+    pub fn new() -> Self {
+        Self {
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Record the samples that have just been queued
+    /// # Arguments
+    /// * `samples` - The samples that have just been queued
+    /// * `queued` - Number of samples in the device queue after queuing
+    pub fn push(&mut self, samples: &[f32], queued: usize) {
+        self.samples.extend(samples);
+
+        // The samples at front have been played
+        let played = self.samples.len().saturating_sub(queued);
+        self.samples.drain(..played);
+    }
+
+    /// Take all the samples that have not been played yet
+    pub fn take(&mut self) -> Vec<f32> {
+        self.samples.drain(..).collect()
+    }
+}
+
+/// Names of all the audio output devices
+pub fn output_devices(sys: &AudioSubsystem) -> Vec<String> {
+    let count = sys.num_audio_playback_devices().unwrap_or(0);
+
+    (0..count)
+        .filter_map(|index| sys.audio_playback_device_name(index).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prime_buffer() {
+        let mut buffer = PrimeBuffer::new();
+        buffer.push(&[1.0, 2.0, 3.0, 4.0], 4);
+        // Two samples have been played before the next samples are queued
+        buffer.push(&[5.0, 6.0], 4);
+
+        assert_eq!(vec![3.0, 4.0, 5.0, 6.0], buffer.take());
+        assert_eq!(Vec::<f32>::new(), buffer.take());
+    }
+
+    #[test]
+    fn test_prime_buffer_all_played() {
+        let mut buffer = PrimeBuffer::new();
+        buffer.push(&[1.0, 2.0], 0);
+
+        assert_eq!(Vec::<f32>::new(), buffer.take());
+    }
+
+    #[test]
+    fn test_null_output() {
+        let mut output = NullOutput::new();
+        output.queue(&[0.0; 8]).unwrap();
+        output.resume();
+
+        assert_eq!(8, output.discarded());
+        assert_eq!(0, output.queued_samples());
+        assert_eq!(false, output.is_lost());
+    }
+}
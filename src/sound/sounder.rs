@@ -1,37 +1,202 @@
-use sdl2::{
-    audio::{AudioCallback, AudioQueue, AudioSpecDesired},
-    AudioSubsystem,
-};
+use std::sync::atomic::Ordering;
+
+use log::{error, warn};
+use sdl2::{audio::AudioCallback, AudioSubsystem};
 
+use super::{
+    dsp::{DspChain, DspSettings},
+    output::{AudioOutput, NullOutput, PrimeBuffer, SdlOutput},
+    volume,
+};
 use crate::{
     media::decoder::{AudioFrame, AudioSummary},
     util::error::SuperError,
+    MUTED, VOLUME,
 };
 
 pub struct Sounder {
-    device: AudioQueue<f32>,
+    /// It's `None` if only null output is allowed
+    sys: Option<AudioSubsystem>,
+    output: Box<dyn AudioOutput>,
+    /// The device chosen by user, `None` means the default device
+    preferred_device: Option<String>,
+    /// Whether the preferred device could not be opened and another output is used
+    degraded: bool,
+    /// Samples waiting in the queue of device, they are moved to the new device while reopening
+    prime_buffer: PrimeBuffer,
+    sample_rate: i32,
+    channels: u8,
+    /// Audio effects applied before volume
+    dsp: DspChain,
+    /// The linear gain to normalize the loudness of media
+    normalization_gain: f32,
 }
 
 impl Sounder {
-    pub fn new(sys: &AudioSubsystem, summary: &AudioSummary) -> Self {
-        let spec = AudioSpecDesired {
-            freq: Some(summary.sample_rate),
-            channels: Some(summary.channels),
-            samples: None,
+    /// Open the specified audio device, `None` means the default device.
+    /// If the device is not available, the default device is used,
+    /// and if there's no device at all, samples are discarded
+    pub fn new(
+        sys: &AudioSubsystem,
+        summary: &AudioSummary,
+        device: Option<String>,
+        dsp_settings: &DspSettings,
+    ) -> Self {
+        let mut sounder = Self::null(summary, dsp_settings);
+        sounder.sys = Some(sys.clone());
+        sounder.preferred_device = device;
+
+        let (output, degraded) = sounder.open_output();
+        sounder.output = output;
+        sounder.degraded = degraded;
+
+        sounder
+    }
+
+    /// Create sounder which discards all the samples, e.g. for headless testing
+    pub fn null(summary: &AudioSummary, dsp_settings: &DspSettings) -> Self {
+        let dsp = DspChain::new(summary.sample_rate, summary.channels, dsp_settings);
+
+        Self {
+            sys: None,
+            output: Box::new(NullOutput::new()),
+            preferred_device: None,
+            degraded: false,
+            prime_buffer: PrimeBuffer::new(),
+            sample_rate: summary.sample_rate,
+            channels: summary.channels,
+            dsp,
+            normalization_gain: 1.0,
+        }
+    }
+
+    /// The name of device which is playing, `None` means the default device or null output
+    pub fn device_name(&self) -> Option<&str> {
+        self.output.device_name()
+    }
+
+    /// Switch to another device, `None` means the default device.
+    /// Samples waiting in the queue are moved to the new device
+    pub fn set_device(&mut self, device: Option<String>) {
+        self.preferred_device = device;
+        self.reopen();
+    }
+
+    /// Check the device after devices are added or removed,
+    /// reopen it if the device is disconnected or the preferred device becomes available
+    pub fn check_device(&mut self) {
+        if self.output.is_lost() {
+            warn!(
+                "audio device {:?} is disconnected",
+                self.output.device_name()
+            );
+            self.reopen();
+        } else if self.degraded {
+            self.reopen();
+        }
+    }
+
+    fn reopen(&mut self) {
+        // Close the current device first, some drivers allow only one stream at a time
+        self.output = Box::new(NullOutput::new());
+
+        let (output, degraded) = self.open_output();
+        self.output = output;
+        self.degraded = degraded;
+
+        // Prime the new device with the samples which have not been played,
+        // so that the play position is not changed
+        let samples = self.prime_buffer.take();
+        if let Err(err) = self.output.queue(&samples) {
+            error!("failed to prime audio device: {}", err);
+        }
+        self.prime_buffer
+            .push(&samples, self.output.queued_samples());
+        self.output.resume();
+    }
+
+    /// Open the preferred device, fall back to the default device and then null output.
+    /// The returned flag indicates whether it's fallen back
+    fn open_output(&self) -> (Box<dyn AudioOutput>, bool) {
+        let sys = match self.sys.as_ref() {
+            Some(sys) => sys,
+            None => return (Box::new(NullOutput::new()), false),
         };
 
-        let device = sys.open_queue::<f32, _>(None, &spec).unwrap();
-        Self { device }
+        open_with_fallback(self.preferred_device.as_deref(), |name| {
+            let output = SdlOutput::open(sys, name, self.sample_rate, self.channels)?;
+            Ok(Box::new(output))
+        })
+    }
+
+    /// Apply the new options of audio effects, it takes effect from the next frame
+    pub fn apply_dsp_settings(&mut self, settings: &DspSettings) {
+        self.dsp.apply_settings(settings);
+    }
+
+    /// Set the linear gain to normalize the loudness, it's applied together with volume
+    pub fn set_normalization_gain(&mut self, gain: f32) {
+        self.normalization_gain = gain;
     }
 
-    pub fn play_sound(&self, frame: AudioFrame) -> Result<(), SuperError> {
-        self.device.queue_audio(&frame.data)?;
-        self.device.resume();
+    /// Clear the states of audio effects, so that samples before seeking
+    /// do not affect the samples after seeking
+    pub fn reset_dsp(&mut self) {
+        self.dsp.reset();
+    }
+
+    /// Queue the frame to the device, the current volume is applied right before output,
+    /// so that volume changes take effect immediately
+    pub fn play_sound(&mut self, frame: AudioFrame) -> Result<(), SuperError> {
+        let mut data = frame.data;
+        self.dsp.process(&mut data);
+
+        let gain = if MUTED.load(Ordering::Acquire) {
+            0.0
+        } else {
+            volume::volume_to_gain(VOLUME.load(Ordering::Acquire)) * self.normalization_gain
+        };
+        volume::apply_gain(&mut data, gain);
+
+        if self.output.is_lost() {
+            self.check_device();
+        }
+        self.output.queue(&data)?;
+        self.prime_buffer.push(&data, self.output.queued_samples());
+        self.output.resume();
 
         Ok(())
     }
 }
 
+/// Open the preferred device with `open`, fall back to the default device and then null output
+/// # Arguments
+/// * `open` - Open the device with the name, or the default device if it's `None`
+/// # Returns
+/// The output, and whether it's fallen back
+fn open_with_fallback<F>(preferred: Option<&str>, mut open: F) -> (Box<dyn AudioOutput>, bool)
+where
+    F: FnMut(Option<&str>) -> Result<Box<dyn AudioOutput>, SuperError>,
+{
+    if let Some(name) = preferred {
+        match open(Some(name)) {
+            Ok(output) => return (output, false),
+            Err(err) => warn!("failed to open audio device {}: {}", name, err),
+        }
+    }
+
+    match open(None) {
+        Ok(output) => (output, preferred.is_some()),
+        Err(err) => {
+            error!(
+                "failed to open default audio device: {}, audio is muted",
+                err
+            );
+            (Box::new(NullOutput::new()), true)
+        }
+    }
+}
+
 struct S16CallBack {
     data: Vec<i16>,
     pos: usize,
@@ -60,3 +225,110 @@ impl AudioCallback for S16CallBack {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An output standing for a device, it keeps the name it's opened with
+    struct FakeOutput {
+        name: Option<String>,
+    }
+
+    impl AudioOutput for FakeOutput {
+        fn queue(&mut self, _samples: &[f32]) -> Result<(), SuperError> {
+            Ok(())
+        }
+
+        fn resume(&mut self) {}
+
+        fn queued_samples(&self) -> usize {
+            0
+        }
+
+        fn is_lost(&self) -> bool {
+            false
+        }
+
+        fn device_name(&self) -> Option<&str> {
+            self.name.as_deref()
+        }
+    }
+
+    /// Open the devices whose names are listed, `None` stands for the default device
+    fn open_from<'a>(
+        available: &'a [Option<&'a str>],
+    ) -> impl FnMut(Option<&str>) -> Result<Box<dyn AudioOutput>, SuperError> + 'a {
+        move |name| {
+            if !available.iter().any(|a| *a == name) {
+                return Err(format!("no such device: {:?}", name).into());
+            }
+
+            Ok(Box::new(FakeOutput {
+                name: name.map(String::from),
+            }))
+        }
+    }
+
+    fn summary() -> AudioSummary {
+        AudioSummary {
+            decoder_name: String::from("pcm_f32le"),
+            duration: 0,
+            duration_millis: 0,
+            frames: 0,
+            timebase_num: 1,
+            timebase_den: 48000,
+            timebase_inverse: 48000,
+            play_interval: 20,
+            channels: 2,
+            channel_layout: 3,
+            sample_rate: 48000,
+            frame_size: 960,
+        }
+    }
+
+    #[test]
+    fn test_open_with_fallback() {
+        let (output, degraded) = open_with_fallback(Some("usb"), open_from(&[Some("usb"), None]));
+        assert_eq!((Some("usb"), false), (output.device_name(), degraded));
+
+        // The preferred device is unplugged
+        let (output, degraded) = open_with_fallback(Some("usb"), open_from(&[None]));
+        assert_eq!((None, true), (output.device_name(), degraded));
+
+        // The default device is used as preferred
+        let (output, degraded) = open_with_fallback(None, open_from(&[None]));
+        assert_eq!((None, false), (output.device_name(), degraded));
+
+        // There's no device at all, the samples are discarded
+        let (mut output, degraded) = open_with_fallback(Some("usb"), open_from(&[]));
+        assert_eq!((None, true), (output.device_name(), degraded));
+        output.queue(&[0.5; 4]).unwrap();
+        assert_eq!(0, output.queued_samples());
+    }
+
+    #[test]
+    fn test_null_sounder() {
+        let summary = summary();
+        let mut sounder = Sounder::null(&summary, &DspSettings::default());
+        assert_eq!(None, sounder.device_name());
+        // There's no device to fall back from, so it's not reopened
+        sounder.check_device();
+        assert_eq!(false, sounder.degraded);
+
+        for pts_millis in [0, 20] {
+            let frame = AudioFrame {
+                format: 0,
+                data: vec![0.25; 960 * 2],
+                pts: pts_millis * 48,
+                pts_millis,
+                sample_rate: summary.sample_rate,
+                channels: summary.channels,
+                channel_layout: 3,
+            };
+            sounder.play_sound(frame).unwrap();
+        }
+        // The samples are discarded at once, nothing is kept to prime another device
+        assert_eq!(Vec::<f32>::new(), sounder.prime_buffer.take());
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{MAX_VOLUME, UNITY_VOLUME};
+
+/// The attenuation at the lowest non-zero volume, unit: dB
+const ATTENUATION_RANGE_DB: f32 = 40.0;
+/// The boost at the maximum volume, unit: dB
+const BOOST_RANGE_DB: f32 = 6.0;
+/// Samples louder than this value are compressed softly to avoid clipping
+const LIMITER_THRESHOLD: f32 = 0.8;
+
+/// Convert the volume to the linear gain of samples.
+/// The volume is mapped to decibels, so that each step sounds the same loud to human ears.
+/// * `0` - silence
+/// * `UNITY_VOLUME` - the original loudness
+/// * `MAX_VOLUME` - the original loudness boosted by `BOOST_RANGE_DB`
+pub fn volume_to_gain(volume: i16) -> f32 {
+    if volume <= 0 {
+        return 0.0;
+    }
+
+    let db = if volume <= UNITY_VOLUME {
+        (volume as f32 / UNITY_VOLUME as f32 - 1.0) * ATTENUATION_RANGE_DB
+    } else {
+        let over = (volume - UNITY_VOLUME) as f32 / (MAX_VOLUME - UNITY_VOLUME) as f32;
+        over.min(1.0) * BOOST_RANGE_DB
+    };
+
+    10f32.powf(db / 20.0)
+}
+
+/// Apply the gain to samples, if the gain is larger than 1,
+/// the samples are passed through the limiter to avoid clipping
+pub fn apply_gain(samples: &mut [f32], gain: f32) {
+    if gain == 1.0 {
+        return;
+    }
+
+    for sample in samples.iter_mut() {
+        *sample *= gain;
+        if gain > 1.0 {
+            *sample = soft_limit(*sample);
+        }
+    }
+}
+
+/// Compress the sample above `LIMITER_THRESHOLD` smoothly,
+/// the output never exceeds 1.0
+fn soft_limit(sample: f32) -> f32 {
+    let magnitude = sample.abs();
+    if magnitude <= LIMITER_THRESHOLD {
+        return sample;
+    }
+
+    let headroom = 1.0 - LIMITER_THRESHOLD;
+    let limited = LIMITER_THRESHOLD + headroom * ((magnitude - LIMITER_THRESHOLD) / headroom).tanh();
+
+    limited.copysign(sample)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_volume_to_gain() {
+        assert_eq!(0.0, volume_to_gain(0));
+        assert_eq!(0.0, volume_to_gain(-10));
+        assert_eq!(1.0, volume_to_gain(UNITY_VOLUME));
+
+        // Half volume is -20dB
+        let half = volume_to_gain(UNITY_VOLUME / 2);
+        assert!((half - 0.1).abs() < 1e-4);
+
+        // Maximum volume is +6dB
+        let max = volume_to_gain(MAX_VOLUME);
+        assert!((max - 1.9953).abs() < 1e-3);
+
+        let mut pre = 0.0;
+        for volume in 0..=MAX_VOLUME {
+            let gain = volume_to_gain(volume);
+            assert!(gain >= pre);
+            pre = gain;
+        }
+    }
+
+    #[test]
+    fn test_apply_gain() {
+        let mut samples = vec![0.5, -0.5, 0.1];
+        apply_gain(&mut samples, 0.5);
+        assert_eq!(vec![0.25, -0.25, 0.05], samples);
+
+        // Boosted samples never clip
+        let mut samples = vec![0.9, -0.9, 0.2, 1.0];
+        apply_gain(&mut samples, 2.0);
+        assert_eq!(0.4, samples[2]);
+        for sample in samples {
+            assert!(sample.abs() <= 1.0);
+        }
+    }
+
+    #[test]
+    fn test_soft_limit() {
+        assert_eq!(0.5, soft_limit(0.5));
+        assert_eq!(-LIMITER_THRESHOLD, soft_limit(-LIMITER_THRESHOLD));
+        assert!(soft_limit(0.9) > LIMITER_THRESHOLD && soft_limit(0.9) < 0.9);
+        assert!(soft_limit(10.0) <= 1.0);
+        assert!(soft_limit(-10.0) >= -1.0);
+        assert!(soft_limit(1.5) > soft_limit(1.2));
+    }
+}
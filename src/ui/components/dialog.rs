@@ -7,3 +7,9 @@ pub fn show_error(message: &str) {
         error!("{}", err);
     }
 }
+
+pub fn show_info(title: &str, message: &str) {
+    if let Err(err) = show_simple_message_box(MessageBoxFlag::INFORMATION, title, message, None) {
+        error!("{}", err);
+    }
+}
@@ -0,0 +1,654 @@
+use sdl2::keyboard::{Keycode, Mod};
+
+use super::{
+    KeyDownParam, MouseDownParam, MouseMotionParam, MouseUpParam, MouseWheelParam, TControl,
+    TextEditingParam, TextInputParam,
+};
+use crate::util::error::SuperError;
+
+/// Route the events of a window to its controls through the tree of `TControl::children_mut`,
+/// so that the containers don't have to pass the events to their controls one by one.
+/// Since the controls can't be referenced between events,
+/// they are addressed by the indices along the tree, e.g. `[2, 0]` is the first child
+/// of the third top-level control
+/// * Mouse button and wheel events are delivered to the topmost control under the cursor,
+///   and bubbled up to its ancestors until one of them handles it
+/// * The control which handles the mouse button down captures the cursor,
+///   it receives the motion and button up events until the button is released
+/// * Motion events are delivered to all controls while the cursor is not captured,
+///   so that they can update the hover state
+/// * Key and text events are delivered to the focused control and bubbled up to its ancestors,
+///   Tab and Shift+Tab move the focus between the focusable controls
+#[derive(Default)]
+pub struct EventDispatcher {
+    /// The control which captures the cursor while the mouse button is pressed
+    captured: Option<Vec<usize>>,
+    /// The control which has the keyboard focus
+    focused: Option<Vec<usize>>,
+}
+
+impl EventDispatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # Arguments
+    /// * `controls` - The top-level controls of the window, in the order of drawing
+    pub fn on_mouse_down(
+        &mut self,
+        controls: &mut [&mut dyn TControl],
+        params: &MouseDownParam,
+    ) -> Result<bool, SuperError> {
+        let path = hit_test(controls, params.x, params.y);
+
+        // Clicking moves the focus to the control, or clears it if the control is not focusable
+        let focus = (1..=path.len())
+            .rev()
+            .map(|len| &path[..len])
+            .find(|p| control_at(controls, p).map_or(false, |c| c.is_focusable()));
+        self.set_focus(controls, focus.map(|p| p.to_vec()));
+
+        self.captured = bubble(controls, &path, |c| c.on_mouse_down(params))?;
+
+        Ok(self.captured.is_some())
+    }
+
+    pub fn on_mouse_up(
+        &mut self,
+        controls: &mut [&mut dyn TControl],
+        params: &MouseUpParam,
+    ) -> Result<bool, SuperError> {
+        // The button may be released out of the control which captures the cursor
+        if let Some(path) = self.captured.take() {
+            if let Some(control) = control_at(controls, &path) {
+                return control.on_mouse_up(params);
+            }
+        }
+
+        let path = hit_test(controls, params.x, params.y);
+        let handler = bubble(controls, &path, |c| c.on_mouse_up(params))?;
+
+        Ok(handler.is_some())
+    }
+
+    pub fn on_mouse_motion(
+        &mut self,
+        controls: &mut [&mut dyn TControl],
+        params: &MouseMotionParam,
+    ) -> Result<bool, SuperError> {
+        if let Some(path) = self.captured.as_ref() {
+            if let Some(control) = control_at(controls, path) {
+                return control.on_mouse_motion(params);
+            }
+        }
+
+        let mut handled = false;
+        for control in controls.iter_mut() {
+            handled |= broadcast(&mut **control, &mut |c| c.on_mouse_motion(params))?;
+        }
+
+        Ok(handled)
+    }
+
+    pub fn on_mouse_wheel(
+        &mut self,
+        controls: &mut [&mut dyn TControl],
+        params: &MouseWheelParam,
+    ) -> Result<bool, SuperError> {
+        let path = hit_test(controls, params.mouse_x, params.mouse_y);
+        let handler = bubble(controls, &path, |c| c.on_mouse_wheel(params))?;
+
+        Ok(handler.is_some())
+    }
+
+    pub fn on_key_down(
+        &mut self,
+        controls: &mut [&mut dyn TControl],
+        params: &KeyDownParam,
+    ) -> Result<bool, SuperError> {
+        let modified = params
+            .keymod
+            .intersects(Mod::LCTRLMOD | Mod::RCTRLMOD | Mod::LALTMOD | Mod::RALTMOD);
+        if params.keycode == Some(Keycode::Tab) && !modified {
+            let backward = params.keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+            // Tab goes on to the shortcuts if no control takes the focus
+            return Ok(self.move_focus(controls, backward));
+        }
+
+        let path = match self.focused.as_ref() {
+            Some(path) => path.clone(),
+            None => return Ok(false),
+        };
+        let handler = bubble(controls, &path, |c| c.on_key_down(params))?;
+
+        Ok(handler.is_some())
+    }
+
+    pub fn on_text_input(
+        &mut self,
+        controls: &mut [&mut dyn TControl],
+        params: &TextInputParam,
+    ) -> Result<bool, SuperError> {
+        let path = match self.focused.as_ref() {
+            Some(path) => path.clone(),
+            None => return Ok(false),
+        };
+        let handler = bubble(controls, &path, |c| c.on_text_input(params))?;
+
+        Ok(handler.is_some())
+    }
+
+    pub fn on_text_editing(
+        &mut self,
+        controls: &mut [&mut dyn TControl],
+        params: &TextEditingParam,
+    ) -> Result<bool, SuperError> {
+        let path = match self.focused.as_ref() {
+            Some(path) => path.clone(),
+            None => return Ok(false),
+        };
+        let handler = bubble(controls, &path, |c| c.on_text_editing(params))?;
+
+        Ok(handler.is_some())
+    }
+
+    /// Whether any control has the keyboard focus
+    pub fn has_focus(&self) -> bool {
+        self.focused.is_some()
+    }
+
+    /// Move the focus to the next focusable control, or the previous one if `backward` is true,
+    /// it wraps around at the ends
+    /// # Returns
+    /// Whether there's a focusable control to move the focus to
+    fn move_focus(&mut self, controls: &mut [&mut dyn TControl], backward: bool) -> bool {
+        let mut focusables = vec![];
+        for (i, control) in controls.iter_mut().enumerate() {
+            collect_focusables(&mut **control, &mut vec![i], &mut focusables);
+        }
+        if focusables.is_empty() {
+            return false;
+        }
+
+        let count = focusables.len();
+        let current = self
+            .focused
+            .as_ref()
+            .and_then(|f| focusables.iter().position(|p| p == f));
+        let next = match (current, backward) {
+            (Some(i), false) => (i + 1) % count,
+            (Some(i), true) => (i + count - 1) % count,
+            (None, false) => 0,
+            (None, true) => count - 1,
+        };
+        self.set_focus(controls, Some(focusables.swap_remove(next)));
+
+        true
+    }
+
+    fn set_focus(&mut self, controls: &mut [&mut dyn TControl], focus: Option<Vec<usize>>) {
+        if self.focused == focus {
+            return;
+        }
+
+        if let Some(path) = self.focused.take() {
+            if let Some(control) = control_at(controls, &path) {
+                control.set_focused(false);
+            }
+        }
+        if let Some(path) = focus.as_ref() {
+            if let Some(control) = control_at(controls, path) {
+                control.set_focused(true);
+            }
+        }
+        self.focused = focus;
+    }
+}
+
+/// Find the control by its indices along the tree
+fn control_at<'a>(
+    controls: &'a mut [&mut dyn TControl],
+    path: &[usize],
+) -> Option<&'a mut dyn TControl> {
+    let (first, rest) = path.split_first()?;
+    let mut control: &'a mut dyn TControl = &mut **controls.get_mut(*first)?;
+    for i in rest {
+        control = control.children_mut().into_iter().nth(*i)?;
+    }
+
+    Some(control)
+}
+
+/// Find the topmost control under the cursor, the later controls are on top of the former ones
+/// # Returns
+/// The indices along the tree, it's empty if there's no control under the cursor
+fn hit_test<'a>(controls: &'a mut [&mut dyn TControl], x: i32, y: i32) -> Vec<usize> {
+    let mut path = vec![];
+    let mut candidates: Vec<&'a mut dyn TControl> = controls
+        .iter_mut()
+        .map(|c| -> &'a mut dyn TControl { &mut **c })
+        .collect();
+    loop {
+        let hit = candidates
+            .into_iter()
+            .enumerate()
+            .rev()
+            .find_map(|(i, c)| c.is_cursor_in(x, y).then_some((i, c)));
+        match hit {
+            Some((i, control)) => {
+                path.push(i);
+                candidates = control.children_mut();
+            }
+            None => return path,
+        }
+    }
+}
+
+/// Deliver the event to the control, then to its ancestors until it's handled
+/// # Returns
+/// The indices of the control which handles the event
+fn bubble<F>(
+    controls: &mut [&mut dyn TControl],
+    path: &[usize],
+    mut handle: F,
+) -> Result<Option<Vec<usize>>, SuperError>
+where
+    F: FnMut(&mut dyn TControl) -> Result<bool, SuperError>,
+{
+    for len in (1..=path.len()).rev() {
+        if let Some(control) = control_at(controls, &path[..len]) {
+            if handle(control)? {
+                return Ok(Some(path[..len].to_vec()));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Deliver the event to the control and all the controls within
+/// # Returns
+/// `true` if any of them handles the event
+fn broadcast(
+    control: &mut dyn TControl,
+    handle: &mut dyn FnMut(&mut dyn TControl) -> Result<bool, SuperError>,
+) -> Result<bool, SuperError> {
+    let mut handled = handle(control)?;
+    for child in control.children_mut() {
+        handled |= broadcast(child, handle)?;
+    }
+
+    Ok(handled)
+}
+
+fn collect_focusables(
+    control: &mut dyn TControl,
+    path: &mut Vec<usize>,
+    focusables: &mut Vec<Vec<usize>>,
+) {
+    if control.is_focusable() {
+        focusables.push(path.clone());
+    }
+    for (i, child) in control.children_mut().into_iter().enumerate() {
+        path.push(i);
+        collect_focusables(child, path, focusables);
+        path.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::{Ref, RefCell, RefMut},
+        rc::Rc,
+    };
+
+    use sdl2::{
+        mouse::{MouseButton, MouseState},
+        render::Canvas,
+        video::Window,
+    };
+
+    use super::*;
+    use crate::ui::components::{ControlDistance, DistanceDirection};
+
+    type Log = Rc<RefCell<Vec<String>>>;
+
+    /// A control without canvas, which records the events it receives
+    struct MockControl {
+        name: &'static str,
+        area: (i32, i32, u32, u32),
+        /// Whether it handles the mouse button events
+        clickable: bool,
+        focusable: bool,
+        focused: bool,
+        /// Whether it's hidden and lets the cursor through
+        hidden: bool,
+        children: Vec<MockControl>,
+        log: Log,
+    }
+
+    impl MockControl {
+        fn new(name: &'static str, area: (i32, i32, u32, u32), log: &Log) -> Self {
+            Self {
+                name,
+                area,
+                clickable: true,
+                focusable: false,
+                focused: false,
+                hidden: false,
+                children: vec![],
+                log: log.clone(),
+            }
+        }
+
+        fn passive(mut self) -> Self {
+            self.clickable = false;
+            self
+        }
+
+        fn focusable(mut self) -> Self {
+            self.focusable = true;
+            self
+        }
+
+        fn child(mut self, child: MockControl) -> Self {
+            self.children.push(child);
+            self
+        }
+
+        fn record(&self, event: &str) {
+            self.log
+                .borrow_mut()
+                .push(format!("{} {}", event, self.name));
+        }
+    }
+
+    impl TControl for MockControl {
+        fn on_mouse_down(&mut self, params: &MouseDownParam) -> Result<bool, SuperError> {
+            if !self.is_cursor_in(params.x, params.y) {
+                return Ok(false);
+            }
+            self.record("down");
+
+            Ok(self.clickable)
+        }
+
+        fn on_mouse_up(&mut self, _params: &MouseUpParam) -> Result<bool, SuperError> {
+            self.record("up");
+
+            Ok(self.clickable)
+        }
+
+        fn on_mouse_motion(&mut self, _params: &MouseMotionParam) -> Result<bool, SuperError> {
+            self.record("motion");
+
+            Ok(true)
+        }
+
+        fn on_key_down(&mut self, _params: &KeyDownParam) -> Result<bool, SuperError> {
+            self.record("key");
+
+            Ok(self.focusable)
+        }
+
+        fn on_text_input(&mut self, params: &TextInputParam) -> Result<bool, SuperError> {
+            self.record(&format!("text {:?}", params.text));
+
+            Ok(self.focusable)
+        }
+
+        fn render(&mut self) -> Result<(), SuperError> {
+            Ok(())
+        }
+
+        fn is_cursor_in(&mut self, x: i32, y: i32) -> bool {
+            let (left, top, width, height) = self.area;
+            !self.hidden
+                && x >= left
+                && x < left + width as i32
+                && y >= top
+                && y < top + height as i32
+        }
+
+        fn distance(&self, _x: i32, _y: i32) -> ControlDistance {
+            ControlDistance::Rectangle(0, DistanceDirection::Inside)
+        }
+
+        fn canvas(&self) -> Ref<Canvas<Window>> {
+            unreachable!("mock control has no canvas")
+        }
+
+        fn canvas_mut(&self) -> RefMut<Canvas<Window>> {
+            unreachable!("mock control has no canvas")
+        }
+
+        fn children_mut(&mut self) -> Vec<&mut dyn TControl> {
+            self.children
+                .iter_mut()
+                .map(|c| c as &mut dyn TControl)
+                .collect()
+        }
+
+        fn is_focusable(&self) -> bool {
+            self.focusable
+        }
+
+        fn set_focused(&mut self, focused: bool) {
+            self.focused = focused;
+        }
+    }
+
+    fn mouse_down(x: i32, y: i32) -> MouseDownParam {
+        MouseDownParam {
+            timestamp: 0,
+            window_id: 0,
+            which: 0,
+            mouse_btn: MouseButton::Left,
+            clicks: 1,
+            x,
+            y,
+        }
+    }
+
+    fn mouse_up(x: i32, y: i32) -> MouseUpParam {
+        MouseUpParam {
+            timestamp: 0,
+            window_id: 0,
+            which: 0,
+            mouse_btn: MouseButton::Left,
+            clicks: 1,
+            x,
+            y,
+        }
+    }
+
+    fn mouse_motion(x: i32, y: i32) -> MouseMotionParam {
+        MouseMotionParam {
+            timestamp: 0,
+            window_id: 0,
+            which: 0,
+            mousestate: MouseState::from_sdl_state(0),
+            x,
+            y,
+            xrel: 0,
+            yrel: 0,
+        }
+    }
+
+    fn key_down(keycode: Keycode, keymod: Mod) -> KeyDownParam {
+        KeyDownParam {
+            timestamp: 0,
+            window_id: 0,
+            keycode: Some(keycode),
+            keymod,
+            repeat: false,
+        }
+    }
+
+    /// A video below a bar, the bar has a passive label and two buttons
+    fn window(log: &Log) -> (MockControl, MockControl) {
+        let video = MockControl::new("video", (0, 0, 100, 100), log);
+        let bar = MockControl::new("bar", (0, 80, 100, 20), log)
+            .child(MockControl::new("label", (0, 80, 20, 20), log).passive())
+            .child(MockControl::new("play", (40, 80, 20, 20), log).focusable())
+            .child(MockControl::new("next", (60, 80, 20, 20), log).focusable());
+
+        (video, bar)
+    }
+
+    fn take(log: &Log) -> Vec<String> {
+        log.borrow_mut().drain(..).collect()
+    }
+
+    #[test]
+    fn test_hit_test_and_bubble() {
+        let log = Log::default();
+        let (mut video, mut bar) = window(&log);
+        let mut dispatcher = EventDispatcher::new();
+
+        // The bar is on top of the video
+        let controls: &mut [&mut dyn TControl] = &mut [&mut video, &mut bar];
+        assert_eq!(vec![1, 1], hit_test(controls, 45, 85));
+        assert_eq!(vec![0], hit_test(controls, 45, 10));
+        assert_eq!(Vec::<usize>::new(), hit_test(controls, 200, 10));
+
+        let handled = dispatcher.on_mouse_down(controls, &mouse_down(45, 85));
+        assert_eq!(true, handled.unwrap());
+        assert_eq!(vec!["down play"], take(&log));
+
+        // The label doesn't handle clicking, so the bar takes it
+        dispatcher.on_mouse_up(controls, &mouse_up(45, 85)).unwrap();
+        take(&log);
+        let handled = dispatcher.on_mouse_down(controls, &mouse_down(5, 85));
+        assert_eq!(true, handled.unwrap());
+        assert_eq!(vec!["down label", "down bar"], take(&log));
+    }
+
+    #[test]
+    fn test_hidden_control() {
+        let log = Log::default();
+        let (mut video, mut bar) = window(&log);
+        bar.hidden = true;
+        let mut dispatcher = EventDispatcher::new();
+        let controls: &mut [&mut dyn TControl] = &mut [&mut video, &mut bar];
+
+        // The clicks go through the hidden bar and its buttons to the video beneath
+        assert_eq!(vec![0], hit_test(controls, 45, 85));
+        let double_click = MouseDownParam {
+            clicks: 2,
+            ..mouse_down(45, 85)
+        };
+        let handled = dispatcher.on_mouse_down(controls, &double_click);
+        assert_eq!(true, handled.unwrap());
+        dispatcher.on_mouse_up(controls, &mouse_up(45, 85)).unwrap();
+        assert_eq!(vec!["down video", "up video"], take(&log));
+        assert_eq!(false, dispatcher.has_focus());
+    }
+
+    #[test]
+    fn test_capture() {
+        let log = Log::default();
+        let (mut video, mut bar) = window(&log);
+        let mut dispatcher = EventDispatcher::new();
+        let controls: &mut [&mut dyn TControl] = &mut [&mut video, &mut bar];
+
+        // Not captured, all the controls are notified
+        dispatcher
+            .on_mouse_motion(controls, &mouse_motion(10, 10))
+            .unwrap();
+        assert_eq!(
+            vec![
+                "motion video",
+                "motion bar",
+                "motion label",
+                "motion play",
+                "motion next"
+            ],
+            take(&log)
+        );
+
+        // Dragging from the video to the bar, only the video receives the events
+        dispatcher
+            .on_mouse_down(controls, &mouse_down(10, 10))
+            .unwrap();
+        dispatcher
+            .on_mouse_motion(controls, &mouse_motion(45, 85))
+            .unwrap();
+        dispatcher.on_mouse_up(controls, &mouse_up(45, 85)).unwrap();
+        assert_eq!(vec!["down video", "motion video", "up video"], take(&log));
+
+        // The capture is released with the button
+        dispatcher.on_mouse_up(controls, &mouse_up(45, 85)).unwrap();
+        assert_eq!(vec!["up play"], take(&log));
+    }
+
+    #[test]
+    fn test_focus() {
+        let log = Log::default();
+        let (mut video, mut bar) = window(&log);
+        let mut dispatcher = EventDispatcher::new();
+        {
+            let controls: &mut [&mut dyn TControl] = &mut [&mut video, &mut bar];
+            assert_focus_moves(&mut dispatcher, controls, &log);
+        }
+        assert_eq!(false, bar.children[1].focused || bar.children[2].focused);
+    }
+
+    #[test]
+    fn test_tab_without_focusables() {
+        let log = Log::default();
+        let mut video = MockControl::new("video", (0, 0, 100, 100), &log);
+        let mut dispatcher = EventDispatcher::new();
+        let controls: &mut [&mut dyn TControl] = &mut [&mut video];
+
+        // Tab is left to the shortcuts if nothing can be focused
+        let tab = key_down(Keycode::Tab, Mod::NOMOD);
+        assert_eq!(false, dispatcher.on_key_down(controls, &tab).unwrap());
+        assert_eq!(false, dispatcher.has_focus());
+    }
+
+    fn assert_focus_moves(
+        dispatcher: &mut EventDispatcher,
+        controls: &mut [&mut dyn TControl],
+        log: &Log,
+    ) {
+        // Keys are ignored without focus
+        let enter = key_down(Keycode::Return, Mod::NOMOD);
+        assert_eq!(false, dispatcher.on_key_down(controls, &enter).unwrap());
+
+        let tab = key_down(Keycode::Tab, Mod::NOMOD);
+        let shift_tab = key_down(Keycode::Tab, Mod::LSHIFTMOD);
+        assert_eq!(true, dispatcher.on_key_down(controls, &tab).unwrap());
+        assert_eq!(Some(vec![1, 1]), dispatcher.focused);
+        dispatcher.on_key_down(controls, &tab).unwrap();
+        assert_eq!(Some(vec![1, 2]), dispatcher.focused);
+        // Wrap around at the ends
+        dispatcher.on_key_down(controls, &tab).unwrap();
+        assert_eq!(Some(vec![1, 1]), dispatcher.focused);
+        dispatcher.on_key_down(controls, &shift_tab).unwrap();
+        assert_eq!(Some(vec![1, 2]), dispatcher.focused);
+
+        assert_eq!(true, dispatcher.on_key_down(controls, &enter).unwrap());
+        assert_eq!(vec!["key next"], take(log));
+
+        // Text goes to the focused control as well
+        let text = TextInputParam {
+            timestamp: 0,
+            window_id: 0,
+            text: String::from("é"),
+        };
+        assert_eq!(true, dispatcher.on_text_input(controls, &text).unwrap());
+        assert_eq!(vec!["text \"é\" next"], take(log));
+
+        // Clicking moves the focus, clicking out of focusable controls clears it
+        dispatcher
+            .on_mouse_down(controls, &mouse_down(45, 85))
+            .unwrap();
+        assert_eq!(Some(vec![1, 1]), dispatcher.focused);
+        dispatcher
+            .on_mouse_down(controls, &mouse_down(10, 10))
+            .unwrap();
+        assert_eq!(false, dispatcher.has_focus());
+    }
+}
@@ -0,0 +1,357 @@
+use std::collections::BTreeMap;
+
+use super::rectangle::{Align, Rectangle};
+
+/// The area which a control occupies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounds {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Bounds {
+    pub fn new(x: i32, y: i32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Shrink the area by the spaces on each side
+    /// # Arguments
+    /// * `spaces` - (left, right, top, bottom), the same as padding and margin of `Rectangle`
+    fn shrink(&self, spaces: (u32, u32, u32, u32)) -> Self {
+        let (left, right, top, bottom) = spaces;
+
+        Self {
+            x: self.x + left as i32,
+            y: self.y + top as i32,
+            width: self.width.saturating_sub(left + right),
+            height: self.height.saturating_sub(top + bottom),
+        }
+    }
+}
+
+/// How a control is sized along an axis
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Length {
+    /// The exact size in pixels
+    Fixed(u32),
+    /// Share the space left by the fixed controls in a stack by weight,
+    /// or fill the space in other cases
+    Flex(u32),
+}
+
+/// How the children of a layout are arranged
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arrangement {
+    /// From left to right
+    Horizontal,
+    /// From top to bottom
+    Vertical,
+    /// Children are stacked over each other and anchored by their alignment,
+    /// e.g. a bar anchored to the bottom of window
+    Overlay,
+}
+
+/// A node of the layout tree, which describes how a control is placed in its parent.
+/// The layout is computed without a canvas, then the bounds are applied to controls by name
+pub struct Layout {
+    /// The name to find the bounds of the control, the ones of unnamed nodes are not kept
+    name: Option<&'static str>,
+    width: Length,
+    height: Length,
+    /// (left, right, top, bottom)
+    padding: (u32, u32, u32, u32),
+    /// (left, right, top, bottom)
+    margin: (u32, u32, u32, u32),
+    /// Where the control is put if its fixed size is smaller than the space for it
+    align: Align,
+    arrangement: Arrangement,
+    /// The space between children of a stack
+    spacing: u32,
+    children: Vec<Layout>,
+}
+
+impl Layout {
+    pub fn new<N: Into<Option<&'static str>>>(name: N, width: Length, height: Length) -> Self {
+        Self {
+            name: name.into(),
+            width,
+            height,
+            padding: (0, 0, 0, 0),
+            margin: (0, 0, 0, 0),
+            align: Align::Center,
+            arrangement: Arrangement::Overlay,
+            spacing: 0,
+            children: vec![],
+        }
+    }
+
+    /// The node of control, its padding, margin and alignment are taken from the control
+    pub fn from_rect(name: &'static str, rect: &Rectangle, width: Length, height: Length) -> Self {
+        Self::new(name, width, height)
+            .with_padding(rect.padding())
+            .with_margin(rect.margin())
+            .with_align(*rect.align())
+    }
+
+    /// A flexible space in stacks, e.g. a pair of them center the controls between
+    pub fn spacer() -> Self {
+        Self::new(None, Length::Flex(1), Length::Flex(1))
+    }
+
+    /// Arrange the children from left to right
+    pub fn horizontal(mut self, spacing: u32) -> Self {
+        self.arrangement = Arrangement::Horizontal;
+        self.spacing = spacing;
+        self
+    }
+
+    /// Arrange the children from top to bottom
+    pub fn vertical(mut self, spacing: u32) -> Self {
+        self.arrangement = Arrangement::Vertical;
+        self.spacing = spacing;
+        self
+    }
+
+    /// # Arguments
+    /// * `padding` - (left, right, top, bottom)
+    pub fn with_padding(mut self, padding: (u32, u32, u32, u32)) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// # Arguments
+    /// * `margin` - (left, right, top, bottom)
+    pub fn with_margin(mut self, margin: (u32, u32, u32, u32)) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    pub fn with_align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn child(mut self, child: Layout) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// Compute the bounds of all the named nodes in the area
+    pub fn compute(&self, area: Bounds) -> BTreeMap<&'static str, Bounds> {
+        let mut result = BTreeMap::new();
+        self.place(area, &mut result);
+
+        result
+    }
+
+    /// Place the node in the space given by its parent, the space includes the margin
+    fn place(&self, space: Bounds, result: &mut BTreeMap<&'static str, Bounds>) {
+        let space = space.shrink(self.margin);
+        let (width, x) = match self.width {
+            Length::Fixed(width) => {
+                let offset = Self::offset(space.width, width, self.horizontal_anchor());
+                (width, space.x + offset)
+            }
+            Length::Flex(_) => (space.width, space.x),
+        };
+        let (height, y) = match self.height {
+            Length::Fixed(height) => {
+                let offset = Self::offset(space.height, height, self.vertical_anchor());
+                (height, space.y + offset)
+            }
+            Length::Flex(_) => (space.height, space.y),
+        };
+
+        let bounds = Bounds::new(x, y, width, height);
+        if let Some(name) = self.name {
+            result.insert(name, bounds);
+        }
+
+        let content = bounds.shrink(self.padding);
+        match self.arrangement {
+            Arrangement::Horizontal | Arrangement::Vertical => self.place_stack(content, result),
+            Arrangement::Overlay => {
+                for child in self.children.iter() {
+                    child.place(content, result);
+                }
+            }
+        }
+    }
+
+    /// Place the children one after another, the flexible ones share the space left
+    fn place_stack(&self, content: Bounds, result: &mut BTreeMap<&'static str, Bounds>) {
+        let is_horizontal = self.arrangement == Arrangement::Horizontal;
+        let main_size = if is_horizontal {
+            content.width
+        } else {
+            content.height
+        };
+
+        // The size of each child along the main axis including margin, 0 for flexible ones
+        let mut sizes: Vec<u32> = self
+            .children
+            .iter()
+            .map(|c| match c.main_length(is_horizontal) {
+                Length::Fixed(size) => size + c.main_margin(is_horizontal),
+                Length::Flex(_) => c.main_margin(is_horizontal),
+            })
+            .collect();
+        let spacing = self.spacing * (self.children.len() as u32).saturating_sub(1);
+        let mut remaining = main_size.saturating_sub(sizes.iter().sum::<u32>() + spacing);
+
+        let mut total_weight: u32 = self
+            .children
+            .iter()
+            .filter_map(|c| match c.main_length(is_horizontal) {
+                Length::Flex(weight) => Some(weight),
+                Length::Fixed(_) => None,
+            })
+            .sum();
+        for (size, child) in sizes.iter_mut().zip(self.children.iter()) {
+            if let Length::Flex(weight) = child.main_length(is_horizontal) {
+                // The last flexible child takes the rest, so that there's no gap for rounding
+                let share = match total_weight {
+                    0 => 0,
+                    _ if weight == total_weight => remaining,
+                    _ => remaining * weight / total_weight,
+                };
+                *size += share;
+                remaining -= share;
+                total_weight -= weight;
+            }
+        }
+
+        let mut offset = 0;
+        for (size, child) in sizes.into_iter().zip(self.children.iter()) {
+            let space = if is_horizontal {
+                Bounds::new(content.x + offset, content.y, size, content.height)
+            } else {
+                Bounds::new(content.x, content.y + offset, content.width, size)
+            };
+            child.place(space, result);
+            offset += (size + self.spacing) as i32;
+        }
+    }
+
+    fn main_length(&self, is_horizontal: bool) -> Length {
+        if is_horizontal {
+            self.width
+        } else {
+            self.height
+        }
+    }
+
+    fn main_margin(&self, is_horizontal: bool) -> u32 {
+        let (left, right, top, bottom) = self.margin;
+        if is_horizontal {
+            left + right
+        } else {
+            top + bottom
+        }
+    }
+
+    /// The anchor along the x axis, 0 for left, 1 for center and 2 for right
+    fn horizontal_anchor(&self) -> u32 {
+        match self.align {
+            Align::Left => 0,
+            Align::Right => 2,
+            _ => 1,
+        }
+    }
+
+    /// The anchor along the y axis, 0 for top, 1 for center and 2 for bottom
+    fn vertical_anchor(&self) -> u32 {
+        match self.align {
+            Align::Top => 0,
+            Align::Bottom => 2,
+            _ => 1,
+        }
+    }
+
+    /// The offset of control in the space by the anchor
+    fn offset(space: u32, size: u32, anchor: u32) -> i32 {
+        (space as i32 - size as i32) * anchor as i32 / 2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_anchors() {
+        let layout = Layout::new("window", Length::Flex(1), Length::Flex(1))
+            .child(Layout::new("video", Length::Flex(1), Length::Flex(1)))
+            .child(Layout::new("title", Length::Flex(1), Length::Fixed(40)).with_align(Align::Top))
+            .child(
+                Layout::new("control", Length::Flex(1), Length::Fixed(70))
+                    .with_align(Align::Bottom)
+                    .with_margin((0, 0, 0, 10)),
+            )
+            .child(
+                Layout::new("logo", Length::Fixed(100), Length::Fixed(50))
+                    .with_align(Align::Center),
+            );
+
+        let bounds = layout.compute(Bounds::new(0, 0, 1024, 768));
+        assert_eq!(Bounds::new(0, 0, 1024, 768), bounds["video"]);
+        assert_eq!(Bounds::new(0, 0, 1024, 40), bounds["title"]);
+        assert_eq!(Bounds::new(0, 688, 1024, 70), bounds["control"]);
+        assert_eq!(Bounds::new(462, 359, 100, 50), bounds["logo"]);
+
+        // Everything follows the size of window
+        let bounds = layout.compute(Bounds::new(0, 0, 640, 480));
+        assert_eq!(Bounds::new(0, 400, 640, 70), bounds["control"]);
+    }
+
+    #[test]
+    fn test_stacks() {
+        // A column of a fixed row and a flexible row, which centers the buttons
+        let layout = Layout::new("bar", Length::Flex(1), Length::Flex(1))
+            .vertical(5)
+            .with_padding((10, 10, 4, 4))
+            .child(Layout::new("progress", Length::Flex(1), Length::Fixed(20)))
+            .child(
+                Layout::new("buttons", Length::Flex(1), Length::Flex(1))
+                    .horizontal(10)
+                    .child(Layout::spacer())
+                    .child(Layout::new("play", Length::Fixed(30), Length::Fixed(30)))
+                    .child(Layout::new("next", Length::Fixed(30), Length::Fixed(20)))
+                    .child(Layout::spacer()),
+            );
+
+        let bounds = layout.compute(Bounds::new(0, 100, 300, 80));
+        assert_eq!(Bounds::new(10, 104, 280, 20), bounds["progress"]);
+        assert_eq!(Bounds::new(10, 129, 280, 47), bounds["buttons"]);
+        // The space left is 280 - 60 - 30 = 190, shared by the spacers
+        assert_eq!(Bounds::new(115, 137, 30, 30), bounds["play"]);
+        assert_eq!(Bounds::new(155, 142, 30, 20), bounds["next"]);
+    }
+
+    #[test]
+    fn test_flex_weights() {
+        let layout = Layout::new(None, Length::Flex(1), Length::Fixed(10))
+            .horizontal(0)
+            .child(Layout::new("a", Length::Flex(1), Length::Flex(1)))
+            .child(Layout::new("b", Length::Flex(2), Length::Flex(1)).with_margin((5, 5, 0, 0)))
+            .child(Layout::new("c", Length::Fixed(20), Length::Flex(1)));
+
+        let bounds = layout.compute(Bounds::new(0, 0, 111, 10));
+        // 111 - 20 - 10 = 81 is shared by a and b with 1:2, the rest goes to the last one
+        assert_eq!(Bounds::new(0, 0, 27, 10), bounds["a"]);
+        assert_eq!(Bounds::new(32, 0, 54, 10), bounds["b"]);
+        assert_eq!(Bounds::new(91, 0, 20, 10), bounds["c"]);
+
+        // There's not enough space, the flexible ones are collapsed
+        let bounds = layout.compute(Bounds::new(0, 0, 25, 10));
+        assert_eq!(0, bounds["a"].width);
+        assert_eq!(0, bounds["b"].width);
+    }
+}
@@ -1,17 +1,20 @@
 pub mod circular;
 pub mod dialog;
+pub mod dispatch;
+pub mod layout;
 pub mod rectangle;
 pub mod triangle;
 
 use std::{cell::{Ref, RefMut}};
 
 use sdl2::{
+    keyboard::{Keycode, Mod},
     mouse::{MouseButton, MouseState, MouseWheelDirection},
     render::Canvas,
     video::Window,
 };
 
-use crate::util::error::SuperError;
+use crate::{ui::theme::Theme, util::error::SuperError};
 
 const DOUBLE_CLICK_INTERVAL: u128 = 300;
 
@@ -62,7 +65,7 @@ pub trait TControl {
     /// or other reasons.
     /// * `SuperError` - the error information
     fn on_mouse_wheel(&mut self, params: &MouseWheelParam) -> Result<bool, SuperError> {
-        if !self.is_cursor_in(params.x, params.y) {
+        if !self.is_cursor_in(params.mouse_x, params.mouse_y) {
             return Ok(false);
         }
 
@@ -151,7 +154,44 @@ pub trait TControl {
     fn distance(&self, x: i32, y: i32) -> ControlDistance;
     fn canvas(&self) -> Ref<Canvas<Window>>;
     fn canvas_mut(&self) -> RefMut<Canvas<Window>>;
-
+    /// The controls within current control, which are dispatched events by `EventDispatcher`
+    /// # Returns
+    /// The controls in the order of drawing, so the later ones are on top of the former ones
+    fn children_mut(&mut self) -> Vec<&mut dyn TControl> {
+        vec![]
+    }
+    /// Handle key down event, it's delivered to the focused control
+    /// and bubbled up to its ancestors until it's handled
+    /// # Returns
+    /// * `bool` - true if the event is handled, false if the event is rejected
+    /// * `SuperError` - the error information
+    fn on_key_down(&mut self, _params: &KeyDownParam) -> Result<bool, SuperError> {
+        Ok(false)
+    }
+    /// Handle the text typed by user or committed by IME, it's delivered like key down event
+    /// # Returns
+    /// * `bool` - true if the event is handled, false if the event is rejected
+    /// * `SuperError` - the error information
+    fn on_text_input(&mut self, _params: &TextInputParam) -> Result<bool, SuperError> {
+        Ok(false)
+    }
+    /// Handle the text being composed in IME, it's delivered like key down event
+    /// # Returns
+    /// * `bool` - true if the event is handled, false if the event is rejected
+    /// * `SuperError` - the error information
+    fn on_text_editing(&mut self, _params: &TextEditingParam) -> Result<bool, SuperError> {
+        Ok(false)
+    }
+    /// Whether current control can take the keyboard focus
+    fn is_focusable(&self) -> bool {
+        false
+    }
+    /// Current control gains or loses the keyboard focus
+    fn set_focused(&mut self, _focused: bool) {}
+    /// Take the colors, fonts and sizes from the theme, the children are updated by `theme::apply`
+    fn apply_theme(&mut self, _theme: &Theme) -> Result<(), SuperError> {
+        Ok(())
+    }
 }
 
 /// Distance type of control
@@ -199,11 +239,54 @@ pub struct MouseMotionParam {
     pub yrel: i32,
 }
 
+pub struct KeyDownParam {
+    pub timestamp: u32,
+    pub window_id: u32,
+    pub keycode: Option<Keycode>,
+    pub keymod: Mod,
+    pub repeat: bool,
+}
+
+impl KeyDownParam {
+    /// Whether the key activates the focused control, like clicking it
+    pub fn is_activation(&self) -> bool {
+        matches!(
+            self.keycode,
+            Some(Keycode::Return | Keycode::KpEnter | Keycode::Space)
+        )
+    }
+}
+
+pub struct TextInputParam {
+    pub timestamp: u32,
+    pub window_id: u32,
+    pub text: String,
+}
+
+pub struct TextEditingParam {
+    pub timestamp: u32,
+    pub window_id: u32,
+    /// The text being composed, it's empty if the composition is finished or canceled
+    pub text: String,
+    /// The position of cursor in the composition, unit: characters
+    pub start: i32,
+    /// The number of characters selected in the composition
+    pub length: i32,
+}
+
 pub struct MouseWheelParam {
     pub timestamp: u32,
     pub window_id: u32,
     pub which: u32,
+    /// The amount scrolled horizontally
     pub x: i32,
+    /// The amount scrolled vertically, positive value means scrolling away from the user
     pub y: i32,
     pub direction: MouseWheelDirection,
+    /// The x coordinate of the cursor
+    pub mouse_x: i32,
+    /// The y coordinate of the cursor
+    pub mouse_y: i32,
+    /// The modifier keys pressed while scrolling
+    pub keymod: Mod,
 }
@@ -4,9 +4,9 @@ use std::{
     rc::Rc,
 };
 
-use sdl2::{render::Canvas, video::Window, pixels::Color, rect::Rect};
+use sdl2::{render::Canvas, video::Window, rect::Rect};
 
-use crate::{util::error::SuperError, ui::{components::TControl, TTF_CONTEXT, DEFAULT_FONT_PATH}};
+use crate::{util::error::SuperError, ui::{components::TControl, theme::Theme, THEME}};
 
 use super::Rectangle;
 
@@ -25,18 +25,25 @@ impl Label {
         text: &'static str,
         canvas: Rc<RefCell<Canvas<Window>>>,
     ) -> Result<Self, SuperError> {
-        let mut inner = Rectangle::new(x, y, width, height, canvas)?;
-        // Set the default font color as black, which is usually adopted by user
-        inner.foreground_color = Color::BLACK;
+        let inner = Rectangle::new(x, y, width, height, canvas)?;
+        let font_size = THEME.read()?.fonts.small;
 
-        Ok(Self { inner, text, font_size: 10 })
+        Ok(Self { inner, text, font_size })
+    }
+
+    /// Take the colors and font size from the theme
+    pub fn apply_theme(&mut self, theme: &Theme) -> Result<(), SuperError> {
+        self.inner.apply_theme(theme)?;
+        self.font_size = theme.fonts.small;
+
+        Ok(())
     }
 
     pub fn render(&mut self) -> Result<bool, SuperError> {
         self.inner.render()?;
 
         let mut canvas = self.canvas_mut();
-        let ttf = TTF_CONTEXT.load_font(DEFAULT_FONT_PATH, self.font_size)?;
+        let ttf = THEME.read()?.load_font(self.font_size)?;
         let sfs = ttf.render(self.text).blended(self.foreground_color)?;
         let tc = canvas.texture_creator();
         let texture = tc.create_texture_from_surface(sfs)?;
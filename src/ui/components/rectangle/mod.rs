@@ -2,14 +2,18 @@ pub mod button;
 pub mod label;
 pub mod panel;
 pub mod progressbar;
+pub mod textinput;
 pub mod tree;
 
 use std::{cell::RefCell, rc::Rc, time::Instant};
 
 use sdl2::{gfx::primitives::DrawRenderer, pixels::Color, render::Canvas, video::Window};
 
-use super::{ControlDistance, DistanceDirection, TControl};
-use crate::util::error::SuperError;
+use super::{layout::Bounds, ControlDistance, DistanceDirection, TControl};
+use crate::{
+    ui::{theme::Theme, THEME},
+    util::error::SuperError,
+};
 
 /// The basic struct of Rectangle control,
 /// All the controls with rectangle shape should extend this struct.
@@ -31,7 +35,7 @@ pub struct Rectangle {
     padding: (u32, u32, u32, u32),
     /// The outside margin of control
     /// # Format
-    /// (top, bottom, left, right)
+    /// (left, right, top, bottom)
     margin: (u32, u32, u32, u32),
     /// The radius of control if the control is rounded cornor
     radius: u32,
@@ -51,6 +55,8 @@ pub struct Rectangle {
     align: Align,
     /// Indicate if the cursor is currently in this control
     is_cursorin: bool,
+    /// Indicate if this control has the keyboard focus
+    is_focused: bool,
     /// The time of pre-click
     preclick: Instant,
 }
@@ -64,21 +70,25 @@ impl Rectangle {
         height: u32,
         canvas: Rc<RefCell<Canvas<Window>>>,
     ) -> Result<Self, SuperError> {
+        let theme = THEME.read()?;
+        let palette = &theme.palette;
+
         Ok(Self {
             position: (x, y),
             size: (width, height),
             canvas,
             padding: (5, 5, 5, 5),
             margin: (5, 5, 5, 5),
-            radius: 0,
-            background_color: Color::BLACK,
-            foreground_color: Color::WHITE,
+            radius: theme.radii.control,
+            background_color: palette.background,
+            foreground_color: palette.text,
             align: Align::Left,
             border_color: None,
-            cursorin_background_color: Color::BLACK,
-            cursorin_foreground_color: Color::WHITE,
+            cursorin_background_color: palette.background,
+            cursorin_foreground_color: palette.text,
             cursorin_border_color: None,
             is_cursorin: false,
+            is_focused: false,
             preclick: Instant::now(),
         })
     }
@@ -143,6 +153,11 @@ impl Rectangle {
             self.position.1 = y;
         }
     }
+    /// Set the position and size of current control with the bounds computed by layout
+    pub fn set_bounds(&mut self, bounds: &Bounds) {
+        self.position = (bounds.x, bounds.y);
+        self.size = (bounds.width, bounds.height);
+    }
     /// Get the center position of current control
     /// # Returns
     /// (center_x, center_y)
@@ -352,6 +367,11 @@ impl Rectangle {
     pub fn is_cursorin(&self) -> bool {
         self.is_cursorin
     }
+    /// Check if current control has the keyboard focus,
+    /// the focus is given by `EventDispatcher` through `set_focused`
+    pub fn is_focused(&self) -> bool {
+        self.is_focused
+    }
 }
 
 impl TControl for Rectangle {
@@ -437,9 +457,25 @@ impl TControl for Rectangle {
 
         Ok(true)
     }
+
+    fn set_focused(&mut self, focused: bool) {
+        self.is_focused = focused;
+    }
+
+    fn apply_theme(&mut self, theme: &Theme) -> Result<(), SuperError> {
+        let palette = &theme.palette;
+        self.radius = theme.radii.control;
+        self.background_color = palette.background;
+        self.foreground_color = palette.text;
+        self.cursorin_background_color = palette.background;
+        self.cursorin_foreground_color = palette.text;
+
+        Ok(())
+    }
 }
 
 /// Align type of control
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Align {
     /// Indicates that the control is centered with the specified coordinates(both x and y)
     Center,
@@ -2,7 +2,7 @@ use std::{ops::{Deref, DerefMut}, rc::Rc, cell::RefCell};
 
 use sdl2::{render::Canvas, video::Window};
 
-use crate::util::error::SuperError;
+use crate::{ui::{components::TControl, theme::Theme, THEME}, util::error::SuperError};
 
 use super::Rectangle;
 
@@ -19,10 +19,22 @@ impl Panel{
         canvas: Rc<RefCell<Canvas<Window>>>,
     ) -> Result<Self, SuperError> {
         let inner = Rectangle::new(x, y, width, height, canvas)?;
-
-        Ok(Self {
+        let mut panel = Self {
             inner,
-        })
+        };
+        panel.apply_theme(&THEME.read()?)?;
+
+        Ok(panel)
+    }
+
+    /// Panels float over other controls, so they take the surface color and radius of panels
+    pub fn apply_theme(&mut self, theme: &Theme) -> Result<(), SuperError> {
+        self.inner.apply_theme(theme)?;
+        self.inner.set_radius(theme.radii.panel);
+        self.inner.set_background_color(theme.palette.surface);
+        self.inner.set_cursorin_background_color(theme.palette.surface);
+
+        Ok(())
     }
 }
 
@@ -1,22 +1,26 @@
 use std::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
     ops::{Deref, DerefMut},
     rc::Rc,
 };
 
 use log::{error, warn};
 use sdl2::{
+    mouse::MouseButton,
     pixels::Color,
     rect::{Point, Rect},
-    render::Canvas,
+    render::{BlendMode, Canvas},
     ttf::Font,
     video::Window,
 };
 
 use crate::{
     ui::{
-        components::{rectangle::Rectangle, MouseDownParam, MouseMotionParam, TControl},
-        DEFAULT_FONT_PATH, TTF_CONTEXT,
+        components::{
+            rectangle::Rectangle, MouseDownParam, MouseMotionParam, MouseUpParam, TControl,
+        },
+        theme::Theme,
+        THEME,
     },
     util::error::SuperError,
 };
@@ -24,26 +28,6 @@ use crate::{
 /// The left and right padding for the bar body，
 /// it's located between the prefix/suffix and bar body
 const BAR_LR_PADDING: u32 = 5;
-/// The position of bar body
-/// # Notice
-/// * This variable is put here because its value cannot be set in render function,
-/// due to the borrow mechanism and deref mechanism.
-/// * If better solution is found later,
-/// it could be put into the `ProgressBar` struct as part of the struct
-/// # Warn
-/// * This variable is is designed to be used in local thread,
-/// it's forbidden to be used in multiple threads unless it's changed to multiple threads version.
-static mut BAR_POSITION: (i32, i32) = (-1, -1);
-/// The size of bar body
-/// # Notices
-/// * This variable is put here because its value cannot be set in render function,
-/// due to the borrow mechanism and deref mechanism.
-/// * If better solution is found later,
-/// it could be put into the `ProgressBar` struct as part of the struct
-/// # Warn
-/// * This variable is is designed to be used in local thread,
-/// it's forbidden to be used in multiple threads unless it's changed to multiple threads version.
-static mut BAR_SIZE: (u32, u32) = (0, 0);
 
 pub struct ProgressBar {
     inner: Rectangle,
@@ -67,6 +51,27 @@ pub struct ProgressBar {
     font_color: Color,
     /// The position of cursor if the cursor is currently in the progress bar
     cursor_position: (i32, i32),
+    /// The value at the position of cursor if the cursor is on the bar body
+    hover_value: Option<u64>,
+    /// Whether the cursor button is dragged, it starts by pressing the left button on the bar
+    dragging: bool,
+    /// The highlighted region of the bar, either end can be absent
+    /// # Format
+    /// (start, end)
+    region: (Option<u64>, Option<u64>),
+    /// The color of highlighted region
+    region_color: Color,
+    /// The values marked with ticks on the bar and the colors of ticks,
+    /// such as the start of chapters
+    marks: Vec<(u64, Color)>,
+    /// The position of bar body, it's computed while rendering
+    /// # Format
+    /// (x, y)
+    bar_position: Cell<(i32, i32)>,
+    /// The size of bar body, it's computed while rendering
+    /// # Format
+    /// (width, height)
+    bar_size: Cell<(u32, u32)>,
 }
 
 #[allow(unused)]
@@ -79,7 +84,9 @@ impl ProgressBar {
         canvas: Rc<RefCell<Canvas<Window>>>,
     ) -> Result<Self, SuperError> {
         let inner = Rectangle::new(x, y, width, height, canvas)?;
-        let font = TTF_CONTEXT.load_font(DEFAULT_FONT_PATH, 10)?;
+        let theme = THEME.read()?;
+        let palette = &theme.palette;
+        let font = theme.load_font(theme.fonts.small)?;
 
         Ok(Self {
             inner,
@@ -87,13 +94,20 @@ impl ProgressBar {
             suffix: None,
             max: 100,
             value: 0,
-            progressed_color: Color::RGB(65, 105, 225),
-            unprogressed_color: Color::GREY,
+            progressed_color: palette.accent,
+            unprogressed_color: palette.track,
             font,
-            font_color: Color::WHITE,
+            font_color: palette.text,
             cursor_position: (-1, -1),
-            cursorbutton_color: Color::WHITE,
+            hover_value: None,
+            dragging: false,
+            cursorbutton_color: palette.text,
             cursorbutton_rad: 5,
+            region: (None, None),
+            region_color: palette.region,
+            marks: vec![],
+            bar_position: Cell::new((-1, -1)),
+            bar_size: Cell::new((0, 0)),
         })
     }
 
@@ -147,17 +161,15 @@ impl ProgressBar {
             let bar_x = content_x + prefix_width as i32 + BAR_LR_PADDING as i32;
             let bar_width = content_width - prefix_width - suffix_width - BAR_LR_PADDING * 2;
 
-            unsafe {
-                BAR_POSITION = (bar_x, content_y);
-                BAR_SIZE = (bar_width, content_height);
-            }
+            self.bar_position.set((bar_x, content_y));
+            self.bar_size.set((bar_width, content_height));
 
             if bar_width <= 0 {
                 warn!("progress bar width is too small: {}", bar_width);
                 return Ok(true);
             }
 
-            let progressed_width = (bar_width as u64 * self.value / self.max) as u32;
+            let progressed_width = Self::value_to_offset(self.value, self.max, bar_width) as u32;
 
             if bar_width < progressed_width {
                 error!(
@@ -178,6 +190,33 @@ impl ProgressBar {
             let remain_rect = Rect::new(remain_start, content_y, remain_width, content_height);
             canvas.set_draw_color(self.unprogressed_color);
             canvas.draw_rect(remain_rect)?;
+
+            // Draw the highlighted region, each end is marked with a vertical line
+            let (start, end) = self.region;
+            let start_x = start.map(|v| bar_x + Self::value_to_offset(v, self.max, bar_width));
+            let end_x = end.map(|v| bar_x + Self::value_to_offset(v, self.max, bar_width));
+            canvas.set_draw_color(self.region_color);
+            for x in [start_x, end_x].into_iter().flatten() {
+                canvas.fill_rect(Rect::new(x - 1, content_y - 2, 2, content_height + 4))?;
+            }
+            if let (Some(start_x), Some(end_x)) = (start_x, end_x) {
+                if end_x > start_x {
+                    let Color { r, g, b, .. } = self.region_color;
+                    let region_rect =
+                        Rect::new(start_x, content_y, (end_x - start_x) as u32, content_height);
+                    canvas.set_blend_mode(BlendMode::Blend);
+                    canvas.set_draw_color(Color::RGBA(r, g, b, 100));
+                    canvas.fill_rect(region_rect)?;
+                    canvas.set_blend_mode(BlendMode::None);
+                }
+            }
+
+            // Draw the ticks, the one at the start is hidden by the bar border
+            for &(mark, color) in self.marks.iter().filter(|(m, _)| *m > 0 && *m < self.max) {
+                let x = bar_x + Self::value_to_offset(mark, self.max, bar_width);
+                canvas.set_draw_color(color);
+                canvas.fill_rect(Rect::new(x - 1, content_y, 2, content_height))?;
+            }
         }
 
         // Draw the slide button(filled circle) at the position of cursor
@@ -215,6 +254,47 @@ impl ProgressBar {
         self.value = value;
     }
 
+    /// The value at the position of cursor, `None` if the cursor is not on the bar body
+    pub fn hover_value(&self) -> Option<u64> {
+        self.hover_value
+    }
+
+    /// Whether the cursor button is being dragged
+    pub fn is_dragging(&self) -> bool {
+        self.dragging
+    }
+
+    /// The position of cursor, `(-1, -1)` if the cursor is out of the progress bar
+    pub fn cursor_position(&self) -> (i32, i32) {
+        self.cursor_position
+    }
+
+    /// The position of bar body, it's known after the first rendering
+    pub fn bar_position(&self) -> (i32, i32) {
+        self.bar_position.get()
+    }
+
+    /// Set the highlighted region of the progress bar,
+    /// the start and end are values in the same unit as the progress value
+    pub fn set_region<S, E>(&mut self, start: S, end: E)
+    where
+        S: Into<Option<u64>>,
+        E: Into<Option<u64>>,
+    {
+        self.region = (start.into(), end.into());
+    }
+
+    /// Set the color of highlighted region
+    pub fn set_region_color(&mut self, color: Color) {
+        self.region_color = color;
+    }
+
+    /// Set the values marked with ticks and the colors of ticks,
+    /// the values are in the same unit as the progress value
+    pub fn set_marks(&mut self, marks: Vec<(u64, Color)>) {
+        self.marks = marks;
+    }
+
     /// Set the prefix that will be displayed before the progress bar
     pub fn set_prefix<T: Into<Option<String>>>(&mut self, prefix: T) {
         self.prefix = prefix.into();
@@ -237,7 +317,22 @@ impl ProgressBar {
 
     /// Set the font size of prefix and suffix
     pub fn set_font_size(&mut self, size: u16) -> Result<(), SuperError> {
-        self.font = TTF_CONTEXT.load_font(DEFAULT_FONT_PATH, size)?;
+        self.font = THEME.read()?.load_font(size)?;
+
+        Ok(())
+    }
+
+    /// Take the colors and font from the theme
+    pub fn apply_theme(&mut self, theme: &Theme) -> Result<(), SuperError> {
+        self.inner.apply_theme(theme)?;
+
+        let palette = &theme.palette;
+        self.progressed_color = palette.accent;
+        self.unprogressed_color = palette.track;
+        self.font_color = palette.text;
+        self.cursorbutton_color = palette.text;
+        self.region_color = palette.region;
+        self.font = theme.load_font(theme.fonts.small)?;
 
         Ok(())
     }
@@ -265,34 +360,82 @@ impl ProgressBar {
             return Ok(false);
         }
 
-        // Compute the current value by clicked position
-        let x = params.x;
-        let bar_x = unsafe { BAR_POSITION.0 };
-        let bar_width = unsafe { BAR_SIZE.0 };
-
         // Only clicking on the bar is valid, do nothing out range of the bar
-        if x < bar_x || x > (bar_x + bar_width as i32) {
-            return Ok(false);
+        let (bar_x, _) = self.bar_position.get();
+        let (bar_width, _) = self.bar_size.get();
+        match Self::offset_to_value(params.x, bar_x, bar_width, self.max) {
+            Some(value) => {
+                // update the current value with the position user clicked at
+                self.value = value;
+                self.dragging = params.mouse_btn == MouseButton::Left;
+                Ok(true)
+            }
+            None => Ok(false),
         }
+    }
 
-        // update the current value with the position user clicked at
-        let progressed_width = (x - bar_x) as u64;
-        self.value = self.max * progressed_width / bar_width as u64;
+    /// Stop dragging wherever the button is released
+    /// # Returns
+    /// `true` if a dragging is finished
+    pub fn on_mouse_up(&mut self, _params: &MouseUpParam) -> Result<bool, SuperError> {
+        let dragging = self.dragging;
+        self.dragging = false;
 
-        Ok(true)
+        Ok(dragging)
     }
 
+    /// Track the cursor, the value under it is kept if it's on the bar body.
+    /// While dragging, the value follows the cursor even if it leaves the bar
     pub fn on_mouse_motion(&mut self, params: &MouseMotionParam) -> Result<bool, SuperError> {
+        let (bar_x, _) = self.bar_position.get();
+        let (bar_width, _) = self.bar_size.get();
+        if self.dragging {
+            let x = Self::clamp_offset(params.x, bar_x, bar_width);
+            if let Some(value) = Self::offset_to_value(x, bar_x, bar_width, self.max) {
+                self.value = value;
+                self.hover_value = Some(value);
+            }
+            self.cursor_position = (x, params.y);
+
+            return Ok(true);
+        }
+
         if !self.inner.on_mouse_motion(params)? {
             self.cursor_position = (-1, -1);
+            self.hover_value = None;
 
             return Ok(false);
         }
 
         self.cursor_position = (params.x, params.y);
+        self.hover_value = Self::offset_to_value(params.x, bar_x, bar_width, self.max);
 
         Ok(true)
     }
+
+    /// Compute the value at the horizontal position,
+    /// `None` is returned if the position is out of the bar body
+    fn offset_to_value(x: i32, bar_x: i32, bar_width: u32, max: u64) -> Option<u64> {
+        if bar_width == 0 || x < bar_x || x > bar_x + bar_width as i32 {
+            return None;
+        }
+
+        Some(max * (x - bar_x) as u64 / bar_width as u64)
+    }
+
+    /// Keep the horizontal position in the bar body
+    fn clamp_offset(x: i32, bar_x: i32, bar_width: u32) -> i32 {
+        x.min(bar_x + bar_width as i32).max(bar_x)
+    }
+
+    /// Compute the horizontal offset of the specified value on the bar body
+    fn value_to_offset(value: u64, max: u64, bar_width: u32) -> i32 {
+        if max == 0 {
+            return 0;
+        }
+
+        (bar_width as u64 * value.min(max) / max) as i32
+    }
 }
 
 impl Deref for ProgressBar {
@@ -308,3 +451,48 @@ impl DerefMut for ProgressBar {
         &mut self.inner
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offset_to_value() {
+        // A bar of 200 pixels starting at 50, for a media of 10 seconds
+        assert_eq!(Some(0), ProgressBar::offset_to_value(50, 50, 200, 10_000));
+        assert_eq!(
+            Some(5_000),
+            ProgressBar::offset_to_value(150, 50, 200, 10_000)
+        );
+        assert_eq!(
+            Some(10_000),
+            ProgressBar::offset_to_value(250, 50, 200, 10_000)
+        );
+        assert_eq!(Some(50), ProgressBar::offset_to_value(51, 50, 200, 10_000));
+    }
+
+    #[test]
+    fn test_offset_out_of_bar() {
+        assert_eq!(None, ProgressBar::offset_to_value(49, 50, 200, 10_000));
+        assert_eq!(None, ProgressBar::offset_to_value(251, 50, 200, 10_000));
+        // The bar is not rendered yet
+        assert_eq!(None, ProgressBar::offset_to_value(0, -1, 0, 10_000));
+    }
+
+    #[test]
+    fn test_clamp_offset() {
+        // Dragging out of the bar stops at its ends
+        assert_eq!(50, ProgressBar::clamp_offset(-20, 50, 200));
+        assert_eq!(120, ProgressBar::clamp_offset(120, 50, 200));
+        assert_eq!(250, ProgressBar::clamp_offset(900, 50, 200));
+    }
+
+    #[test]
+    fn test_offset_round_trip() {
+        // The value of a position is drawn at the same position
+        for x in 50..=250 {
+            let value = ProgressBar::offset_to_value(x, 50, 200, 3_600_000).unwrap();
+            assert_eq!(x - 50, ProgressBar::value_to_offset(value, 3_600_000, 200));
+        }
+    }
+}
@@ -0,0 +1,842 @@
+use std::{
+    borrow::Cow,
+    cell::{Ref, RefCell, RefMut},
+    ops::{Deref, DerefMut},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use sdl2::{
+    clipboard::ClipboardUtil,
+    gfx::primitives::DrawRenderer,
+    keyboard::{Keycode, Mod},
+    mouse::MouseButton,
+    rect::{Point, Rect},
+    render::Canvas,
+    ttf::Font,
+    video::Window,
+};
+
+use crate::{
+    ui::{
+        components::{
+            ControlDistance, KeyDownParam, MouseDownParam, MouseMotionParam, MouseUpParam,
+            TControl, TextEditingParam, TextInputParam,
+        },
+        theme::{Palette, Theme},
+        THEME,
+    },
+    util::error::SuperError,
+};
+
+use super::Rectangle;
+
+/// The caret is shown and hidden alternately in this period
+const BLINK_PERIOD: Duration = Duration::from_millis(500);
+
+/// The text being composed by IME, it's shown at the cursor until it's committed
+#[derive(Debug, Clone, PartialEq)]
+struct Composition {
+    text: String,
+    /// The position of the caret in the composition, unit: bytes
+    cursor: usize,
+}
+
+/// The editing state of single line text, it's independent of rendering.
+/// All the positions are byte offsets at the boundaries of characters
+#[derive(Debug, Default, Clone)]
+pub struct TextBuffer {
+    text: String,
+    cursor: usize,
+    /// The other end of selection, the selected text is between it and the cursor
+    anchor: Option<usize>,
+    composition: Option<Composition>,
+}
+
+impl TextBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replace the whole text, the cursor is moved to the end
+    pub fn set_text(&mut self, text: &str) {
+        self.text = sanitize(text);
+        self.cursor = self.text.len();
+        self.anchor = None;
+        self.composition = None;
+    }
+
+    #[allow(dead_code)]
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// The selected range in order
+    /// # Returns
+    /// (start, end), `None` if nothing is selected
+    pub fn selection(&self) -> Option<(usize, usize)> {
+        let anchor = self.anchor?;
+
+        match anchor.cmp(&self.cursor) {
+            std::cmp::Ordering::Less => Some((anchor, self.cursor)),
+            std::cmp::Ordering::Greater => Some((self.cursor, anchor)),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+
+    pub fn selected_text(&self) -> Option<&str> {
+        self.selection().map(|(start, end)| &self.text[start..end])
+    }
+
+    pub fn is_composing(&self) -> bool {
+        self.composition.is_some()
+    }
+
+    /// Insert the text at the cursor, the selection is replaced by it.
+    /// The control characters, e.g. line breaks in pasted text, are dropped
+    pub fn insert(&mut self, text: &str) {
+        self.composition = None;
+        self.delete_selection();
+
+        let text = sanitize(text);
+        self.text.insert_str(self.cursor, &text);
+        self.cursor += text.len();
+    }
+
+    /// Remove the selected text
+    /// # Returns
+    /// `true` if there was selected text
+    pub fn delete_selection(&mut self) -> bool {
+        let selection = self.selection();
+        self.anchor = None;
+
+        match selection {
+            Some((start, end)) => {
+                self.text.replace_range(start..end, "");
+                self.cursor = start;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove the character or the word before the cursor, or the selected text if any
+    pub fn backspace(&mut self, word: bool) {
+        if self.delete_selection() {
+            return;
+        }
+
+        let start = if word {
+            self.prev_word(self.cursor)
+        } else {
+            self.prev_boundary(self.cursor)
+        };
+        self.text.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    /// Remove the character or the word after the cursor, or the selected text if any
+    pub fn delete(&mut self, word: bool) {
+        if self.delete_selection() {
+            return;
+        }
+
+        let end = if word {
+            self.next_word(self.cursor)
+        } else {
+            self.next_boundary(self.cursor)
+        };
+        self.text.replace_range(self.cursor..end, "");
+    }
+
+    /// Move the cursor by a character or a word
+    /// # Arguments
+    /// * `word` - Move to the start of the previous word
+    /// * `select` - Extend the selection instead of clearing it
+    pub fn move_left(&mut self, word: bool, select: bool) {
+        // The cursor collapses to the start of selection, like other editors
+        if let (false, Some((start, _))) = (select, self.selection()) {
+            self.move_to(start, false);
+            return;
+        }
+
+        let target = if word {
+            self.prev_word(self.cursor)
+        } else {
+            self.prev_boundary(self.cursor)
+        };
+        self.move_to(target, select);
+    }
+
+    /// Move the cursor by a character or a word
+    /// # Arguments
+    /// * `word` - Move to the end of the next word
+    /// * `select` - Extend the selection instead of clearing it
+    pub fn move_right(&mut self, word: bool, select: bool) {
+        if let (false, Some((_, end))) = (select, self.selection()) {
+            self.move_to(end, false);
+            return;
+        }
+
+        let target = if word {
+            self.next_word(self.cursor)
+        } else {
+            self.next_boundary(self.cursor)
+        };
+        self.move_to(target, select);
+    }
+
+    pub fn move_home(&mut self, select: bool) {
+        self.move_to(0, select);
+    }
+
+    pub fn move_end(&mut self, select: bool) {
+        self.move_to(self.text.len(), select);
+    }
+
+    /// Move the cursor to the position, it's adjusted to the boundary of character
+    /// # Arguments
+    /// * `select` - Select the text between the previous cursor and the position
+    pub fn move_to(&mut self, index: usize, select: bool) {
+        let mut index = index.min(self.text.len());
+        while !self.text.is_char_boundary(index) {
+            index -= 1;
+        }
+
+        if !select {
+            self.anchor = None;
+        } else if self.anchor.is_none() {
+            self.anchor = Some(self.cursor);
+        }
+        self.cursor = index;
+    }
+
+    pub fn select_all(&mut self) {
+        self.anchor = Some(0);
+        self.cursor = self.text.len();
+    }
+
+    /// Select the word at the position, e.g. by double clicking
+    pub fn select_word(&mut self, index: usize) {
+        self.move_to(index, false);
+
+        let start = self.text[..self.cursor]
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| c.is_alphanumeric())
+            .last()
+            .map_or(self.cursor, |(i, _)| i);
+        let end = self.text[self.cursor..]
+            .char_indices()
+            .take_while(|(_, c)| c.is_alphanumeric())
+            .last()
+            .map_or(self.cursor, |(i, c)| self.cursor + i + c.len_utf8());
+
+        self.anchor = Some(start);
+        self.cursor = end;
+    }
+
+    /// Update the text being composed by IME, the selected text is replaced once composing starts
+    /// # Arguments
+    /// * `text` - The composition, empty if it's canceled
+    /// * `cursor` - The position of the caret in the composition, unit: characters
+    pub fn set_composition(&mut self, text: &str, cursor: usize) {
+        if text.is_empty() {
+            self.composition = None;
+            return;
+        }
+        if self.composition.is_none() {
+            self.delete_selection();
+        }
+
+        let cursor = text
+            .char_indices()
+            .nth(cursor)
+            .map_or(text.len(), |(i, _)| i);
+        self.composition = Some(Composition {
+            text: text.to_string(),
+            cursor,
+        });
+    }
+
+    /// The text to show, with the composition at the cursor
+    /// # Returns
+    /// (text, position of the caret, range of the composition)
+    pub fn display(&self) -> (Cow<'_, str>, usize, Option<(usize, usize)>) {
+        match &self.composition {
+            None => (Cow::Borrowed(&self.text), self.cursor, None),
+            Some(composition) => {
+                let mut text = self.text.clone();
+                text.insert_str(self.cursor, &composition.text);
+                let end = self.cursor + composition.text.len();
+
+                (
+                    Cow::Owned(text),
+                    self.cursor + composition.cursor,
+                    Some((self.cursor, end)),
+                )
+            }
+        }
+    }
+
+    /// Find the boundary of character nearest to the horizontal offset
+    /// # Arguments
+    /// * `x` - The offset from the start of text
+    /// * `measure` - Gives the width of the text
+    pub fn boundary_at<F: Fn(&str) -> u32>(&self, x: i32, measure: F) -> usize {
+        let mut left = 0;
+        for (i, c) in self.text.char_indices() {
+            let right = measure(&self.text[..i + c.len_utf8()]) as i32;
+            if x < (left + right) / 2 {
+                return i;
+            }
+            left = right;
+        }
+
+        self.text.len()
+    }
+
+    fn prev_boundary(&self, index: usize) -> usize {
+        self.text[..index]
+            .char_indices()
+            .next_back()
+            .map_or(0, |(i, _)| i)
+    }
+
+    fn next_boundary(&self, index: usize) -> usize {
+        self.text[index..]
+            .chars()
+            .next()
+            .map_or(index, |c| index + c.len_utf8())
+    }
+
+    /// The start of the word before the position, the separators in between are skipped
+    fn prev_word(&self, index: usize) -> usize {
+        let mut start = index;
+        let mut in_word = false;
+        for (i, c) in self.text[..index].char_indices().rev() {
+            if c.is_alphanumeric() {
+                in_word = true;
+            } else if in_word {
+                break;
+            }
+            start = i;
+        }
+
+        start
+    }
+
+    /// The end of the word after the position, the separators in between are skipped
+    fn next_word(&self, index: usize) -> usize {
+        let mut in_word = false;
+        for (i, c) in self.text[index..].char_indices() {
+            if c.is_alphanumeric() {
+                in_word = true;
+            } else if in_word {
+                return index + i;
+            }
+        }
+
+        self.text.len()
+    }
+}
+
+/// Drop the characters which can't be shown in single line
+fn sanitize(text: &str) -> String {
+    text.chars().filter(|c| !c.is_control()).collect()
+}
+
+/// Single line text box, the text is typed through the text input of SDL,
+/// so the characters composed by IME are supported
+pub struct TextInput {
+    inner: Rectangle,
+    buffer: TextBuffer,
+    font: Font<'static, 'static>,
+    palette: Palette,
+    /// The hint shown while the text is empty
+    placeholder: Option<String>,
+    /// The horizontal offset of text, it keeps the caret visible in long text
+    scroll: i32,
+    /// The caret blinks from the time of last editing
+    caret_time: Instant,
+    /// Whether the selection is being made by dragging
+    selecting: bool,
+    /// The area of caret given to IME, its candidate window is shown nearby
+    ime_rect: Option<Rect>,
+    /// Called with the text when Enter is pressed
+    on_submit: Option<Box<dyn FnMut(&str)>>,
+}
+
+impl TextInput {
+    #[allow(dead_code)]
+    pub fn new(
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        canvas: Rc<RefCell<Canvas<Window>>>,
+    ) -> Result<Self, SuperError> {
+        let inner = Rectangle::new(x, y, width, height, canvas)?;
+        let theme = THEME.read()?;
+        let mut input = Self {
+            inner,
+            buffer: TextBuffer::new(),
+            font: theme.load_font(theme.fonts.normal)?,
+            palette: theme.palette.clone(),
+            placeholder: None,
+            scroll: 0,
+            caret_time: Instant::now(),
+            selecting: false,
+            ime_rect: None,
+            on_submit: None,
+        };
+        input.set_border_color(theme.palette.text_dim);
+        input.set_cursorin_border_color(theme.palette.text);
+
+        Ok(input)
+    }
+
+    #[allow(dead_code)]
+    pub fn with_placeholder<T: Into<String>>(mut self, placeholder: T) -> Self {
+        self.placeholder = Some(placeholder.into());
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_on_submit<F: FnMut(&str) + 'static>(mut self, on_submit: F) -> Self {
+        self.on_submit = Some(Box::new(on_submit));
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn text(&self) -> &str {
+        self.buffer.text()
+    }
+
+    #[allow(dead_code)]
+    pub fn set_text(&mut self, text: &str) {
+        self.buffer.set_text(text);
+    }
+
+    /// Convert the x coordinate of window to the position in text
+    fn boundary_at(&self, x: i32) -> usize {
+        let offset = x - self.content_position().0 + self.scroll;
+
+        self.buffer
+            .boundary_at(offset, |s| self.font.size_of(s).map_or(0, |(w, _)| w))
+    }
+
+    fn copy(&self) -> Result<(), SuperError> {
+        if let Some(text) = self.buffer.selected_text() {
+            self.clipboard().set_clipboard_text(text)?;
+        }
+
+        Ok(())
+    }
+
+    fn paste(&mut self) -> Result<(), SuperError> {
+        let clipboard = self.clipboard();
+        if clipboard.has_clipboard_text() {
+            let text = clipboard.clipboard_text()?;
+            self.buffer.insert(&text);
+        }
+
+        Ok(())
+    }
+
+    fn clipboard(&self) -> ClipboardUtil {
+        self.canvas().window().subsystem().clipboard()
+    }
+
+    /// Draw the text with the selection and the composition,
+    /// the positions are relative to the start of text
+    fn render_text(
+        &self,
+        canvas: &mut Canvas<Window>,
+        text: &str,
+        composition: Option<(usize, usize)>,
+        text_y: i32,
+    ) -> Result<(), SuperError> {
+        let x = self.content_position().0 - self.scroll;
+        let (_, height) = self.content_size();
+        let line_height = self.font.height() as u32;
+        let width_of = |end: usize| -> Result<i32, SuperError> {
+            Ok(self.font.size_of(&text[..end])?.0 as i32)
+        };
+
+        if let Some((start, end)) = self.buffer.selection() {
+            let (start_x, end_x) = (width_of(start)?, width_of(end)?);
+            canvas.set_draw_color(self.palette.selection);
+            canvas.fill_rect(Rect::new(
+                x + start_x,
+                text_y,
+                (end_x - start_x) as u32,
+                line_height.min(height),
+            ))?;
+        }
+
+        let tc = canvas.texture_creator();
+        let sfs = self.font.render(text).blended(self.palette.text)?;
+        let texture = tc.create_texture_from_surface(sfs)?;
+        let query = texture.query();
+        canvas.copy(
+            &texture,
+            None,
+            Rect::new(x, text_y, query.width, query.height),
+        )?;
+
+        // Underline the composition, it's not committed to the text yet
+        if let Some((start, end)) = composition {
+            let underline_y = text_y + line_height as i32 - 1;
+            canvas.set_draw_color(self.palette.text);
+            canvas.draw_line(
+                Point::new(x + width_of(start)?, underline_y),
+                Point::new(x + width_of(end)?, underline_y),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl TControl for TextInput {
+    fn render(&mut self) -> Result<(), SuperError> {
+        self.inner.render()?;
+
+        let (content_x, content_y) = self.content_position();
+        let (width, height) = self.content_size();
+        let (text, caret, composition) = self.buffer.display();
+        let text_width = self.font.size_of(&text)?.0 as i32;
+        let caret_x = self.font.size_of(&text[..caret])?.0 as i32;
+
+        // Scroll the text to keep the caret visible, and not to leave blank at the end
+        let width = width as i32;
+        self.scroll = self.scroll.min((text_width - width).max(0));
+        if caret_x - self.scroll >= width {
+            self.scroll = caret_x - width + 1;
+        } else if caret_x < self.scroll {
+            self.scroll = caret_x;
+        }
+
+        let line_height = self.font.height();
+        let text_y = content_y + (height as i32 - line_height) / 2;
+        let caret_rect = Rect::new(
+            content_x + caret_x - self.scroll,
+            text_y,
+            1,
+            line_height as u32,
+        );
+        // Keep the candidate window of IME at the caret
+        if self.is_focused() && self.ime_rect != Some(caret_rect) {
+            self.ime_rect = Some(caret_rect);
+            self.canvas()
+                .window()
+                .subsystem()
+                .text_input()
+                .set_rect(caret_rect);
+        }
+
+        let mut canvas = self.canvas_mut();
+        canvas.set_clip_rect(Rect::new(content_x, content_y, width as u32, height));
+        if !text.is_empty() {
+            self.render_text(&mut canvas, &text, composition, text_y)?;
+        } else if let Some(placeholder) = self.placeholder.as_ref().filter(|p| !p.is_empty()) {
+            let tc = canvas.texture_creator();
+            let sfs = self
+                .font
+                .render(placeholder)
+                .blended(self.palette.text_dim)?;
+            let texture = tc.create_texture_from_surface(sfs)?;
+            let query = texture.query();
+            let dst = Rect::new(content_x, text_y, query.width, query.height);
+            canvas.copy(&texture, None, dst)?;
+        }
+
+        let blink = self.caret_time.elapsed().as_millis() / BLINK_PERIOD.as_millis();
+        if self.is_focused() && blink % 2 == 0 {
+            canvas.set_draw_color(self.palette.text);
+            canvas.fill_rect(caret_rect)?;
+        }
+        canvas.set_clip_rect(None);
+
+        if self.is_focused() {
+            let (x, y) = self.position16();
+            let (w, h) = self.size16();
+            let (end_x, end_y) = (x + w as i16, y + h as i16);
+            canvas.rounded_rectangle(
+                x,
+                y,
+                end_x,
+                end_y,
+                self.radius16() as i16,
+                self.palette.accent,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn on_mouse_down(&mut self, params: &MouseDownParam) -> Result<bool, SuperError> {
+        if !self.inner.on_mouse_down(params)? {
+            return Ok(false);
+        }
+        // The cursor is kept while composing, otherwise the composition goes elsewhere
+        if params.mouse_btn != MouseButton::Left || self.buffer.is_composing() {
+            return Ok(true);
+        }
+
+        let index = self.boundary_at(params.x);
+        match params.clicks {
+            1 => {
+                self.buffer.move_to(index, false);
+                self.selecting = true;
+            }
+            2 => self.buffer.select_word(index),
+            _ => self.buffer.select_all(),
+        }
+        self.caret_time = Instant::now();
+
+        Ok(true)
+    }
+
+    fn on_mouse_motion(&mut self, params: &MouseMotionParam) -> Result<bool, SuperError> {
+        if !self.selecting {
+            return self.inner.on_mouse_motion(params);
+        }
+
+        let index = self.boundary_at(params.x);
+        self.buffer.move_to(index, true);
+        self.caret_time = Instant::now();
+
+        Ok(true)
+    }
+
+    fn on_mouse_up(&mut self, params: &MouseUpParam) -> Result<bool, SuperError> {
+        self.selecting = false;
+
+        self.inner.on_mouse_up(params)
+    }
+
+    fn on_key_down(&mut self, params: &KeyDownParam) -> Result<bool, SuperError> {
+        let keycode = match params.keycode {
+            Some(keycode) => keycode,
+            None => return Ok(false),
+        };
+        // The keys are taken by IME while composing
+        if self.buffer.is_composing() {
+            return Ok(true);
+        }
+
+        let ctrl = params
+            .keymod
+            .intersects(Mod::LCTRLMOD | Mod::RCTRLMOD | Mod::LGUIMOD | Mod::RGUIMOD);
+        let shift = params.keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+        let alt = params.keymod.intersects(Mod::LALTMOD | Mod::RALTMOD);
+        match keycode {
+            Keycode::Left => self.buffer.move_left(ctrl, shift),
+            Keycode::Right => self.buffer.move_right(ctrl, shift),
+            Keycode::Home => self.buffer.move_home(shift),
+            Keycode::End => self.buffer.move_end(shift),
+            Keycode::Backspace => self.buffer.backspace(ctrl),
+            Keycode::Delete => self.buffer.delete(ctrl),
+            Keycode::A if ctrl => self.buffer.select_all(),
+            Keycode::C if ctrl => self.copy()?,
+            Keycode::X if ctrl => {
+                self.copy()?;
+                self.buffer.delete_selection();
+            }
+            Keycode::V if ctrl => self.paste()?,
+            Keycode::Return | Keycode::KpEnter => match self.on_submit.as_mut() {
+                Some(on_submit) => on_submit(self.buffer.text()),
+                None => return Ok(false),
+            },
+            // The characters come in text input events, the keys are taken here
+            // so that they don't trigger the shortcuts
+            _ => return Ok(!ctrl && !alt && (0x20..0x7f).contains(&(keycode as i32))),
+        }
+        self.caret_time = Instant::now();
+
+        Ok(true)
+    }
+
+    fn on_text_input(&mut self, params: &TextInputParam) -> Result<bool, SuperError> {
+        self.buffer.insert(&params.text);
+        self.caret_time = Instant::now();
+
+        Ok(true)
+    }
+
+    fn on_text_editing(&mut self, params: &TextEditingParam) -> Result<bool, SuperError> {
+        self.buffer
+            .set_composition(&params.text, params.start.max(0) as usize);
+        self.caret_time = Instant::now();
+
+        Ok(true)
+    }
+
+    fn is_focusable(&self) -> bool {
+        true
+    }
+
+    fn set_focused(&mut self, focused: bool) {
+        self.inner.set_focused(focused);
+        self.caret_time = Instant::now();
+        self.ime_rect = None;
+
+        // The text input of SDL is only started while typing is expected,
+        // so that IME doesn't take the keys of shortcuts
+        let text_input = self.canvas().window().subsystem().text_input();
+        if focused {
+            text_input.start();
+        } else {
+            text_input.stop();
+            self.buffer.set_composition("", 0);
+            self.selecting = false;
+        }
+    }
+
+    fn is_cursor_in(&mut self, x: i32, y: i32) -> bool {
+        self.inner.is_cursor_in(x, y)
+    }
+
+    fn distance(&self, x: i32, y: i32) -> ControlDistance {
+        self.inner.distance(x, y)
+    }
+
+    fn canvas(&self) -> Ref<Canvas<Window>> {
+        self.inner.canvas()
+    }
+
+    fn canvas_mut(&self) -> RefMut<Canvas<Window>> {
+        self.inner.canvas_mut()
+    }
+
+    fn apply_theme(&mut self, theme: &Theme) -> Result<(), SuperError> {
+        self.inner.apply_theme(theme)?;
+        self.font = theme.load_font(theme.fonts.normal)?;
+        self.palette = theme.palette.clone();
+        self.set_border_color(theme.palette.text_dim);
+        self.set_cursorin_border_color(theme.palette.text);
+
+        Ok(())
+    }
+}
+
+impl Deref for TextInput {
+    type Target = Rectangle;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl DerefMut for TextInput {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buffer(text: &str) -> TextBuffer {
+        let mut buffer = TextBuffer::new();
+        buffer.set_text(text);
+        buffer
+    }
+
+    #[test]
+    fn test_utf8_editing() {
+        let mut buffer = buffer("añ中");
+        buffer.move_left(false, false);
+        assert_eq!(3, buffer.cursor());
+        buffer.backspace(false);
+        assert_eq!("a中", buffer.text());
+        assert_eq!(1, buffer.cursor());
+
+        buffer.insert("é\n");
+        assert_eq!("aé中", buffer.text());
+        buffer.delete(false);
+        assert_eq!("aé", buffer.text());
+
+        // Positions inside a character are moved back to its start
+        buffer.move_to(2, false);
+        assert_eq!(1, buffer.cursor());
+    }
+
+    #[test]
+    fn test_word_movement() {
+        let mut buffer = buffer("foo, bar baz");
+        buffer.move_left(true, false);
+        assert_eq!(9, buffer.cursor());
+        buffer.backspace(true);
+        assert_eq!("foo, baz", buffer.text());
+
+        buffer.move_home(false);
+        buffer.move_right(true, false);
+        assert_eq!(3, buffer.cursor());
+        buffer.delete(true);
+        assert_eq!("foo", buffer.text());
+    }
+
+    #[test]
+    fn test_selection() {
+        let mut buffer = buffer("hello world");
+        buffer.move_left(true, true);
+        assert_eq!(Some("world"), buffer.selected_text());
+
+        // Moving without shift collapses the selection to its start
+        buffer.move_left(false, false);
+        assert_eq!(None, buffer.selection());
+        assert_eq!(6, buffer.cursor());
+
+        buffer.select_word(2);
+        assert_eq!(Some("hello"), buffer.selected_text());
+        buffer.insert("bye");
+        assert_eq!("bye world", buffer.text());
+
+        buffer.select_all();
+        buffer.backspace(false);
+        assert_eq!("", buffer.text());
+    }
+
+    #[test]
+    fn test_composition() {
+        let mut buffer = buffer("ab");
+        buffer.move_left(false, true);
+        buffer.set_composition("にほ", 1);
+        // The selection is replaced once composing starts
+        assert_eq!("a", buffer.text());
+        assert!(buffer.is_composing());
+
+        let (text, caret, composition) = buffer.display();
+        assert_eq!("aにほ", text);
+        assert_eq!(4, caret);
+        assert_eq!(Some((1, 7)), composition);
+
+        buffer.insert("日本");
+        assert!(!buffer.is_composing());
+        assert_eq!("a日本", buffer.text());
+
+        buffer.set_composition("x", 1);
+        buffer.set_composition("", 0);
+        assert_eq!(("a日本".into(), 7, None), buffer.display());
+    }
+
+    #[test]
+    fn test_boundary_at() {
+        let buffer = buffer("a中b");
+        let measure = |s: &str| s.chars().count() as u32 * 10;
+
+        assert_eq!(0, buffer.boundary_at(-5, measure));
+        assert_eq!(0, buffer.boundary_at(4, measure));
+        assert_eq!(1, buffer.boundary_at(6, measure));
+        assert_eq!(4, buffer.boundary_at(16, measure));
+        assert_eq!(5, buffer.boundary_at(100, measure));
+    }
+}
@@ -9,7 +9,8 @@ use sdl2::{pixels::Color, render::Canvas, ttf::Font, video::Window, rect::Rect};
 use crate::{
     ui::{
         components::{MouseUpParam, TControl, DOUBLE_CLICK_INTERVAL},
-        DEFAULT_FONT_PATH, TTF_CONTEXT,
+        theme::Theme,
+        THEME,
     },
     util::error::SuperError,
 };
@@ -36,7 +37,7 @@ impl<V: Clone + PartialEq, E: Clone> TreeView<V, E> {
         height: u32,
         canvas: Rc<RefCell<Canvas<Window>>>,
     ) -> Result<Self, SuperError> {
-        Ok(Self {
+        let mut tree = Self {
             inner: Rectangle::new(x, y, width, height, canvas.clone())?,
             root: Rc::new(RefCell::new(Node::new(
                 value,
@@ -48,7 +49,10 @@ impl<V: Clone + PartialEq, E: Clone> TreeView<V, E> {
                 canvas.clone(),
             )?)),
             show_root: false,
-        })
+        };
+        tree.apply_theme(&THEME.read()?)?;
+
+        Ok(tree)
     }
 
     pub fn selected_font_color(&self) -> Color {
@@ -85,6 +89,19 @@ impl<V: Clone + PartialEq, E: Clone> TreeView<V, E> {
 
         Ok(true)
     }
+
+    /// Take the colors and font size from the theme, the fonts of all nodes are reloaded
+    pub fn apply_theme(&mut self, theme: &Theme) -> Result<(), SuperError> {
+        self.inner.apply_theme(theme)?;
+        unsafe {
+            SELECTED_FONT_COLOR = theme.palette.selection;
+            DEFAULT_FONT_COLOR = theme.palette.text;
+            FONT_SIZE = theme.fonts.small;
+        }
+        self.root.borrow_mut().apply_theme(theme)?;
+
+        Ok(())
+    }
 }
 
 impl<V: Clone + PartialEq, E: Clone> Deref for TreeView<V, E> {
@@ -136,7 +153,7 @@ impl<V: Clone + PartialEq, E: Clone> Node<V, E> {
             extra: None,
             parent: None,
             children: vec![],
-            font: TTF_CONTEXT.load_font(DEFAULT_FONT_PATH, unsafe { FONT_SIZE })?,
+            font: THEME.read()?.load_font(unsafe { FONT_SIZE })?,
             selected: false,
         })
     }
@@ -207,6 +224,17 @@ impl<V: Clone + PartialEq, E: Clone> Node<V, E> {
         }
     }
 
+    /// Take the colors and font from the theme, so as the children
+    pub fn apply_theme(&mut self, theme: &Theme) -> Result<(), SuperError> {
+        self.inner.apply_theme(theme)?;
+        self.font = theme.load_font(unsafe { FONT_SIZE })?;
+        for child in &mut self.children {
+            child.apply_theme(theme)?;
+        }
+
+        Ok(())
+    }
+
     pub fn render<F>(&mut self) -> Result<(), SuperError> {
         let mut canvas = self.canvas.borrow_mut();
         let (x, y) = self.inner.content_position();
@@ -2,15 +2,23 @@ pub mod components;
 pub mod start_window;
 pub mod video_window;
 pub mod play_list_window;
+pub mod theme;
+
+use std::sync::RwLock;
 
 use sdl2::ttf::Sdl2TtfContext;
 use static_init::dynamic;
 
-static DEFAULT_FONT_PATH: &'static str = "./assets/fonts/Roboto-Regular.ttf";
+use self::theme::Theme;
 
 #[dynamic]
 static TTF_CONTEXT: Sdl2TtfContext = sdl2::ttf::init().unwrap();
 
+/// The theme of all the controls, controls take it when they are created
+/// and are updated by `theme::apply` when it's switched
+#[dynamic]
+pub static THEME: RwLock<Theme> = RwLock::new(Theme::default());
+
 pub trait NTWindow{
     /// Get the window id, this id is the id of sdl window
     fn id(&self)->u32;
@@ -1,6 +1,8 @@
 use std::{
     cell::RefCell,
+    fs,
     ops::{Deref, DerefMut},
+    path::{Path, PathBuf},
     rc::Rc,
 };
 
@@ -64,6 +66,16 @@ impl PlayButton {
                     safe_send(EVENT_CHANNEL.0.send(EventMessage::FileOpened(path)));
                 }
             }
+            MouseButton::Right => {
+                // Open all the files in a directory as the play list
+                let dir = rfd::FileDialog::new().pick_folder();
+                if let Some(dir) = dir {
+                    let paths = list_files(&dir)?;
+                    if !paths.is_empty() {
+                        safe_send(EVENT_CHANNEL.0.send(EventMessage::DirOpened(paths)));
+                    }
+                }
+            }
             _ => {}
         }
 
@@ -98,3 +110,18 @@ impl DerefMut for PlayButton {
         &mut self.inner
     }
 }
+
+/// The files in the directory sorted by name, the hidden files and sub-directories are skipped
+fn list_files(dir: &Path) -> Result<Vec<PathBuf>, SuperError> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if !hidden && entry.file_type()?.is_file() {
+            paths.push(entry.path());
+        }
+    }
+    paths.sort();
+
+    Ok(paths)
+}
@@ -0,0 +1,446 @@
+use std::{fs::File, path::Path};
+
+use log::error;
+use sdl2::{pixels::Color, ttf::Font};
+use serde::{Deserialize, Serialize};
+
+use super::{components::TControl, TTF_CONTEXT};
+use crate::util::error::SuperError;
+
+/// The theme defined by user, it's switched to after the built-in themes
+const CUSTOM_FILE_NAME: &str = "theme.yml";
+
+/// The themes which can be switched to at runtime
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemePreset {
+    Dark,
+    Light,
+    HighContrast,
+    /// The theme loaded from `theme.yml`
+    Custom,
+}
+
+impl ThemePreset {
+    pub fn next(&self) -> Self {
+        match self {
+            ThemePreset::Dark => ThemePreset::Light,
+            ThemePreset::Light => ThemePreset::HighContrast,
+            ThemePreset::HighContrast => ThemePreset::Custom,
+            ThemePreset::Custom => ThemePreset::Dark,
+        }
+    }
+}
+
+/// Colors, fonts and sizes of the controls.
+/// The fields absent from a theme file are taken from the dark theme
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Theme {
+    /// The name shown while switching to the theme
+    #[serde(default = "Theme::custom_name")]
+    pub name: String,
+    pub palette: Palette,
+    pub fonts: Fonts,
+    pub radii: Radii,
+    pub heights: Heights,
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            name: String::from("Dark"),
+            palette: Palette::default(),
+            fonts: Fonts::default(),
+            radii: Radii::default(),
+            heights: Heights::default(),
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            name: String::from("Light"),
+            palette: Palette::light(),
+            radii: Radii {
+                control: 4,
+                panel: 6,
+            },
+            ..Self::dark()
+        }
+    }
+
+    /// Pure colors on black, with larger texts and bars
+    pub fn high_contrast() -> Self {
+        Self {
+            name: String::from("High contrast"),
+            palette: Palette::high_contrast(),
+            fonts: Fonts {
+                small: 12,
+                normal: 14,
+                large: 16,
+                xlarge: 22,
+                ..Fonts::default()
+            },
+            heights: Heights {
+                titlebar: 48,
+                title_button: 36,
+                controlbar: 84,
+                statebar: 24,
+                volumebar: 24,
+            },
+            ..Self::dark()
+        }
+    }
+
+    fn custom_name() -> String {
+        String::from("Custom")
+    }
+
+    /// Load the font of the theme with the specified size
+    pub fn load_font(&self, size: u16) -> Result<Font<'static, 'static>, SuperError> {
+        let font = TTF_CONTEXT.load_font(&self.fonts.path, size)?;
+
+        Ok(font)
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+/// The colors of controls, they are written as `#RRGGBB` or `#RRGGBBAA` in theme files
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Palette {
+    /// The background of controls
+    #[serde(with = "hex_color")]
+    pub background: Color,
+    /// The background of floating panels, such as the preview of progress
+    #[serde(with = "hex_color")]
+    pub surface: Color,
+    /// The background of the texts over the video, such as on-screen messages
+    #[serde(with = "hex_color")]
+    pub overlay: Color,
+    /// The background of title bar and control bar, it's transparent to show the video under them
+    #[serde(with = "hex_color")]
+    pub bar: Color,
+    /// Texts, and the icons which are hovered or focused
+    #[serde(with = "hex_color")]
+    pub text: Color,
+    /// The icons which are not hovered, and the muted volume
+    #[serde(with = "hex_color")]
+    pub text_dim: Color,
+    /// Texts and icons over the button colors
+    #[serde(with = "hex_color")]
+    pub on_accent: Color,
+    /// Progress, levels and spectrum
+    #[serde(with = "hex_color")]
+    pub accent: Color,
+    /// The high levels of spectrum
+    #[serde(with = "hex_color")]
+    pub accent_light: Color,
+    /// The part of progress bar which is not reached
+    #[serde(with = "hex_color")]
+    pub track: Color,
+    /// The A-B loop section
+    #[serde(with = "hex_color")]
+    pub region: Color,
+    /// The ticks of chapters
+    #[serde(with = "hex_color")]
+    pub chapter: Color,
+    /// The ticks of bookmarks
+    #[serde(with = "hex_color")]
+    pub bookmark: Color,
+    /// Error messages
+    #[serde(with = "hex_color")]
+    pub error: Color,
+    /// The hovered buttons of window, such as minimizing
+    #[serde(with = "hex_color")]
+    pub button: Color,
+    /// The hovered buttons which are destructive, such as closing
+    #[serde(with = "hex_color")]
+    pub danger: Color,
+    /// Selected items, such as the nodes of tree view
+    #[serde(with = "hex_color")]
+    pub selection: Color,
+}
+
+impl Palette {
+    pub fn light() -> Self {
+        Self {
+            background: Color::RGB(240, 240, 240),
+            surface: Color::RGBA(250, 250, 250, 235),
+            overlay: Color::RGBA(255, 255, 255, 200),
+            bar: Color::RGBA(240, 240, 240, 220),
+            text: Color::RGB(32, 32, 32),
+            text_dim: Color::RGB(140, 140, 140),
+            on_accent: Color::WHITE,
+            accent: Color::RGB(47, 95, 208),
+            accent_light: Color::RGB(127, 168, 240),
+            track: Color::RGB(180, 180, 180),
+            region: Color::RGB(230, 126, 0),
+            chapter: Color::RGB(80, 80, 80),
+            bookmark: Color::RGB(200, 150, 0),
+            error: Color::RGB(200, 40, 30),
+            button: Color::RGB(74, 116, 232),
+            danger: Color::RGB(220, 20, 60),
+            selection: Color::RGB(60, 120, 220),
+        }
+    }
+
+    pub fn high_contrast() -> Self {
+        Self {
+            background: Color::BLACK,
+            surface: Color::BLACK,
+            overlay: Color::BLACK,
+            bar: Color::RGBA(0, 0, 0, 230),
+            text: Color::YELLOW,
+            text_dim: Color::WHITE,
+            on_accent: Color::BLACK,
+            accent: Color::CYAN,
+            accent_light: Color::WHITE,
+            track: Color::WHITE,
+            region: Color::MAGENTA,
+            chapter: Color::WHITE,
+            bookmark: Color::YELLOW,
+            error: Color::RGB(255, 64, 64),
+            button: Color::YELLOW,
+            danger: Color::RED,
+            selection: Color::CYAN,
+        }
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            background: Color::BLACK,
+            surface: Color::RGBA(20, 20, 20, 230),
+            overlay: Color::RGBA(0, 0, 0, 160),
+            bar: Color::RGBA(0, 0, 0, 0),
+            text: Color::WHITE,
+            text_dim: Color::GRAY,
+            on_accent: Color::WHITE,
+            accent: Color::RGB(65, 105, 225),
+            accent_light: Color::RGB(135, 206, 250),
+            track: Color::GRAY,
+            region: Color::RGB(255, 165, 0),
+            chapter: Color::RGB(230, 230, 230),
+            bookmark: Color::RGB(255, 215, 0),
+            error: Color::RGB(255, 99, 71),
+            button: Color::RGB(51, 51, 255),
+            danger: Color::RGB(220, 20, 60),
+            selection: Color::BLUE,
+        }
+    }
+}
+
+/// The font and its sizes for different kinds of texts
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Fonts {
+    /// The path of font file
+    pub path: String,
+    /// The texts in small controls, such as the values of progress bar
+    pub small: u16,
+    /// The texts in popups, such as the preview of progress
+    pub normal: u16,
+    /// The title of media and statistics
+    pub large: u16,
+    /// On-screen messages
+    pub xlarge: u16,
+}
+
+impl Default for Fonts {
+    fn default() -> Self {
+        Self {
+            path: String::from("./assets/fonts/Roboto-Regular.ttf"),
+            small: 10,
+            normal: 12,
+            large: 14,
+            xlarge: 18,
+        }
+    }
+}
+
+/// The radii of rounded corners
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct Radii {
+    /// Controls, such as buttons
+    pub control: u32,
+    /// Floating panels, such as on-screen messages
+    pub panel: u32,
+}
+
+/// The heights of bars and their controls
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct Heights {
+    pub titlebar: u32,
+    /// The buttons of window on the title bar
+    pub title_button: u32,
+    /// The control bar, including the state bar and play bar
+    pub controlbar: u32,
+    pub statebar: u32,
+    pub volumebar: u32,
+}
+
+impl Default for Heights {
+    fn default() -> Self {
+        Self {
+            titlebar: 40,
+            title_button: 30,
+            controlbar: 70,
+            statebar: 20,
+            volumebar: 20,
+        }
+    }
+}
+
+/// Load the theme of the preset, the custom one is read from disk
+pub fn load(preset: ThemePreset) -> Result<Theme, SuperError> {
+    let theme = match preset {
+        ThemePreset::Dark => Theme::dark(),
+        ThemePreset::Light => Theme::light(),
+        ThemePreset::HighContrast => Theme::high_contrast(),
+        ThemePreset::Custom => {
+            let f = File::open(CUSTOM_FILE_NAME)?;
+            serde_yaml::from_reader(f)?
+        }
+    };
+
+    Ok(theme)
+}
+
+/// Load the theme of the preset, if any error occurred, log the error and use the dark theme
+pub fn load_or_default(preset: ThemePreset) -> Theme {
+    match load(preset) {
+        Ok(theme) => theme,
+        Err(err) => {
+            error!("failed to load theme {:?}: {}", preset, err);
+            Theme::default()
+        }
+    }
+}
+
+/// The preset after the specified one, the custom theme is skipped if it's not defined by user
+pub fn next_preset(preset: ThemePreset) -> ThemePreset {
+    let next = preset.next();
+    if next == ThemePreset::Custom && !Path::new(CUSTOM_FILE_NAME).exists() {
+        return next.next();
+    }
+
+    next
+}
+
+/// Apply the theme to the control and all the controls within
+pub fn apply(control: &mut dyn TControl, theme: &Theme) -> Result<(), SuperError> {
+    control.apply_theme(theme)?;
+    for child in control.children_mut() {
+        apply(child, theme)?;
+    }
+
+    Ok(())
+}
+
+/// Serialize the colors as `#RRGGBB`, or `#RRGGBBAA` if they are translucent
+mod hex_color {
+    use sdl2::pixels::Color;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        let Color { r, g, b, a } = *color;
+        let hex = if a == 255 {
+            format!("#{:02X}{:02X}{:02X}", r, g, b)
+        } else {
+            format!("#{:02X}{:02X}{:02X}{:02X}", r, g, b, a)
+        };
+
+        serializer.serialize_str(&hex)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        parse(&hex).ok_or_else(|| de::Error::custom(format!("invalid color: {}", hex)))
+    }
+
+    pub fn parse(hex: &str) -> Option<Color> {
+        let digits = hex.strip_prefix('#')?;
+        if !digits.is_ascii() || (digits.len() != 6 && digits.len() != 8) {
+            return None;
+        }
+
+        let channel = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).ok();
+        let alpha = match digits.len() {
+            8 => channel(6)?,
+            _ => 255,
+        };
+
+        Some(Color::RGBA(channel(0)?, channel(2)?, channel(4)?, alpha))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(Some(Color::RGB(65, 105, 225)), hex_color::parse("#4169e1"));
+        assert_eq!(Some(Color::RGBA(0, 0, 0, 160)), hex_color::parse("#000000A0"));
+        assert_eq!(None, hex_color::parse("4169E1"));
+        assert_eq!(None, hex_color::parse("#4169E"));
+        assert_eq!(None, hex_color::parse("#GG69E1"));
+        assert_eq!(None, hex_color::parse("#ÿÿÿ"));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for theme in [Theme::dark(), Theme::light(), Theme::high_contrast()] {
+            let yaml = serde_yaml::to_string(&theme).unwrap();
+            let parsed: Theme = serde_yaml::from_str(&yaml).unwrap();
+            assert_eq!(theme, parsed);
+        }
+
+        let yaml = serde_yaml::to_string(&Theme::dark()).unwrap();
+        assert!(yaml.contains("overlay: '#000000A0'"));
+        assert!(yaml.contains("text: '#FFFFFF'"));
+    }
+
+    #[test]
+    fn test_partial_theme() {
+        let yaml = "
+palette:
+  accent: '#FF0000'
+fonts:
+  xlarge: 24
+heights:
+  controlbar: 90
+";
+        let theme: Theme = serde_yaml::from_str(yaml).unwrap();
+        // The absent fields are the same as the dark theme
+        let dark = Theme::dark();
+        assert_eq!("Custom", theme.name);
+        assert_eq!(Color::RED, theme.palette.accent);
+        assert_eq!(dark.palette.text, theme.palette.text);
+        assert_eq!(24, theme.fonts.xlarge);
+        assert_eq!(dark.fonts.path, theme.fonts.path);
+        assert_eq!(90, theme.heights.controlbar);
+        assert_eq!(dark.heights.statebar, theme.heights.statebar);
+        assert_eq!(dark.radii, theme.radii);
+
+        let invalid = "palette:\n  accent: red\n";
+        assert!(serde_yaml::from_str::<Theme>(invalid).is_err());
+    }
+
+    #[test]
+    fn test_next_preset() {
+        let mut preset = ThemePreset::Dark;
+        for _ in 0..4 {
+            preset = preset.next();
+        }
+        assert_eq!(ThemePreset::Dark, preset);
+    }
+}
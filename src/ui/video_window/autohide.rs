@@ -0,0 +1,81 @@
+use std::time::{Duration, Instant};
+
+/// The time of fading out after the idle period
+const FADE_DURATION: Duration = Duration::from_millis(300);
+
+/// Track the time since the last activity, so that the controls can be hidden while idle
+pub struct IdleTimer {
+    /// The idle period before hiding, zero means never hiding
+    delay: Duration,
+    last_active: Instant,
+}
+
+impl IdleTimer {
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            last_active: Instant::now(),
+        }
+    }
+
+    /// Restart the idle period
+    pub fn touch(&mut self, now: Instant) {
+        self.last_active = now;
+    }
+
+    /// Whether the idle period has passed
+    pub fn is_idle(&self, now: Instant) -> bool {
+        !self.delay.is_zero() && now.duration_since(self.last_active) >= self.delay
+    }
+
+    /// The opacity of controls, they fade out after the idle period
+    pub fn opacity(&self, now: Instant) -> u8 {
+        if !self.is_idle(now) {
+            return 255;
+        }
+
+        let fading = now.duration_since(self.last_active) - self.delay;
+        match FADE_DURATION.checked_sub(fading) {
+            Some(remaining) if !remaining.is_zero() => {
+                (255.0 * remaining.as_secs_f32() / FADE_DURATION.as_secs_f32()) as u8
+            }
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opacity() {
+        let now = Instant::now();
+        let delay = Duration::from_millis(2000);
+        let mut timer = IdleTimer::new(delay);
+        timer.touch(now);
+
+        assert_eq!(false, timer.is_idle(now + delay / 2));
+        assert_eq!(255, timer.opacity(now + delay / 2));
+
+        let alpha = timer.opacity(now + delay + FADE_DURATION / 2);
+        assert!((126..=128).contains(&alpha), "alpha: {}", alpha);
+        assert_eq!(true, timer.is_idle(now + delay));
+        assert_eq!(0, timer.opacity(now + delay + FADE_DURATION));
+
+        // Activity shows the controls again
+        timer.touch(now + delay * 2);
+        assert_eq!(255, timer.opacity(now + delay * 2));
+    }
+
+    #[test]
+    fn test_never_hide() {
+        let now = Instant::now();
+        let mut timer = IdleTimer::new(Duration::ZERO);
+        timer.touch(now);
+
+        let later = now + Duration::from_secs(3600);
+        assert_eq!(false, timer.is_idle(later));
+        assert_eq!(255, timer.opacity(later));
+    }
+}
@@ -1,96 +1,112 @@
 use std::{
-    cell::RefCell,
+    cell::{Ref, RefCell, RefMut},
     ops::{Deref, DerefMut},
     rc::Rc,
 };
 
 use sdl2::{render::Canvas, video::Window};
 
-use self::{playbar::PlayBar, statebar::StateBar};
+use self::{playbar::PlayBar, statebar::StateBar, volumebar::VolumeBar};
 use crate::{
-    ui::components::{
-        rectangle::Rectangle, MouseDownParam, MouseMotionParam, MouseUpParam, TControl,
+    media::decoder::VideoFrame,
+    ui::{
+        components::{
+            layout::{Bounds, Layout, Length},
+            rectangle::{Align, Rectangle},
+            ControlDistance, TControl,
+        },
+        theme::{Heights, Theme},
+        THEME,
     },
     util::error::SuperError,
 };
 
 mod playbar;
 mod statebar;
+mod volumebar;
 
-const HEIGHT: u32 = 70;
-const STATEBAR_HEIGHT: u32 = 20;
 const BAR_MARGIN: u32 = 10;
-const PLAYBAR_HEIGHT: u32 = HEIGHT - STATEBAR_HEIGHT - BAR_MARGIN;
 const MARGIN_BOTTOM: u32 = 10;
+const VOLUMEBAR_WIDTH: u32 = 150;
 
 pub struct ControlBar {
     inner: Rectangle,
     playbar: PlayBar,
     statebar: StateBar,
+    volumebar: VolumeBar,
+    /// The heights of the bars, they are taken from the theme
+    heights: Heights,
+    /// Whether the bar is shown, it's hidden while the cursor is idle
+    revealed: bool,
 }
 
 impl ControlBar {
     pub fn new(canvas: Rc<RefCell<Canvas<Window>>>) -> Result<Self, SuperError> {
         let (window_width, window_height) = canvas.borrow_mut().window_mut().drawable_size();
         let x = 0;
-        let y = window_height as i32 - HEIGHT as i32;
-
-        let mut inner = Rectangle::new(x, y, window_width, HEIGHT, canvas.clone())?;
+        let (heights, bar_color) = {
+            let theme = THEME.read()?;
+            (theme.heights, theme.palette.bar)
+        };
+        let height = heights.controlbar;
+        let y = window_height as i32 - height as i32;
+
+        let mut inner = Rectangle::new(x, y, window_width, height, canvas.clone())?;
         inner.set_margin(2, 2, 2, MARGIN_BOTTOM);
+        inner.set_background_color(bar_color);
+        inner.set_cursorin_background_color(bar_color);
 
-        let mut statebar = StateBar::new(x, y, window_width, STATEBAR_HEIGHT, canvas.clone())?;
+        // The bars within are placed by the layout later
+        let mut statebar = StateBar::new(x, y, window_width, heights.statebar, canvas.clone())?;
         statebar.set_padding(None, None, 8, 8);
-
-        let playbar_y = y + STATEBAR_HEIGHT as i32 + inner.margin().3 as i32;
-        let playbar = PlayBar::new(x, playbar_y, window_width, PLAYBAR_HEIGHT, canvas.clone())?;
-
-        Ok(Self {
+        let playbar = PlayBar::new(
+            x,
+            y,
+            window_width,
+            Self::playbar_height(&heights),
+            canvas.clone(),
+        )?;
+        let mut volumebar =
+            VolumeBar::new(x, y, VOLUMEBAR_WIDTH, heights.volumebar, canvas.clone())?;
+        volumebar.set_padding(None, None, 7, 7);
+        // The volume bar is located at the right side of play bar
+        volumebar.set_margin(None, BAR_MARGIN, None, None);
+        volumebar.set_align(Align::Right);
+
+        let mut controlbar = Self {
             inner,
             playbar,
             statebar,
-        })
-    }
-
-    pub fn on_mouse_up(&mut self, params: &MouseUpParam) -> Result<bool, SuperError> {
-        if !self.inner.on_mouse_up(params)? {
-            return Ok(false);
-        }
+            volumebar,
+            heights,
+            revealed: true,
+        };
+        controlbar.relayout();
 
-        self.statebar.on_mouse_up(params)?;
-        self.playbar.on_mouse_up(params)?;
-
-        Ok(true)
+        Ok(controlbar)
     }
 
-    pub fn on_mouse_motion(&mut self, params: &MouseMotionParam) -> Result<bool, SuperError> {
-        if !self.inner.on_mouse_motion(params)? {
-            return Ok(false);
-        }
-
-        self.statebar.on_mouse_motion(params)?;
-        self.playbar.on_mouse_motion(params)?;
-
-        Ok(true)
+    /// Keep the thumbnail for the preview of progress
+    pub fn update_thumbnail(&mut self, millis: i64, thumbnail: VideoFrame) {
+        self.statebar.update_thumbnail(millis, thumbnail);
     }
 
-    pub fn on_mouse_down(&mut self, params: &MouseDownParam) -> Result<bool, SuperError> {
-        if !self.inner.on_mouse_down(params)? {
-            return Ok(false);
-        }
-
-        self.statebar.on_mouse_down(params)?;
-        self.playbar.on_mouse_down(params)?;
+    /// Show the positions of bookmarks on the state bar, unit: milliseconds
+    pub fn set_bookmarks(&mut self, bookmarks: Vec<u64>) {
+        self.statebar.set_bookmarks(bookmarks);
+    }
 
-        Ok(true)
+    pub fn clear_thumbnails(&mut self) {
+        self.statebar.clear_thumbnails();
     }
 
-    pub fn render(&mut self) -> Result<bool, SuperError> {
-        if self.is_cursorin() {
-            self.statebar.render()?;
-            self.playbar.render()?;
-        }
+    pub fn set_revealed(&mut self, revealed: bool) {
+        self.revealed = revealed;
+    }
 
-        Ok(true)
+    /// Whether user is operating on the control bar
+    pub fn is_active(&self) -> bool {
+        self.is_cursorin() || self.statebar.is_dragging()
     }
 
     pub fn set_size<W, H>(&mut self, width: W, height: H)
@@ -98,15 +114,8 @@ impl ControlBar {
         W: Into<Option<u32>>,
         H: Into<Option<u32>>,
     {
-        let width: Option<u32> = width.into();
-        let height: Option<u32> = height.into();
-
-        // Set size of current control
         self.inner.set_size(width, height);
-
-        // Adjust width of controls within
-        self.statebar.set_size(width, None);
-        self.playbar.set_size(width, None);
+        self.relayout();
     }
 
     pub fn set_position<X, Y>(&mut self, x: X, y: Y)
@@ -114,17 +123,96 @@ impl ControlBar {
         X: Into<Option<i32>>,
         Y: Into<Option<i32>>,
     {
-        let x: Option<i32> = x.into();
-        let y: Option<i32> = y.into();
-
-        // Set control position
         self.inner.set_position(x, y);
+        self.relayout();
+    }
+
+    /// The play bar takes the height left by the state bar
+    fn playbar_height(heights: &Heights) -> u32 {
+        heights
+            .controlbar
+            .saturating_sub(heights.statebar + BAR_MARGIN)
+    }
+
+    /// Place the bars within, the state bar is on the top,
+    /// and the volume bar is at the right side of play bar
+    fn relayout(&mut self) {
+        let (x, y) = self.position();
+        let (width, height) = self.size();
+
+        let volumebar = Layout::from_rect(
+            "volumebar",
+            &self.volumebar,
+            Length::Fixed(VOLUMEBAR_WIDTH),
+            Length::Fixed(self.heights.volumebar),
+        );
+        let layout = Layout::new(None, Length::Flex(1), Length::Flex(1))
+            .vertical(0)
+            .child(Layout::new(
+                "statebar",
+                Length::Flex(1),
+                Length::Fixed(self.heights.statebar),
+            ))
+            .child(
+                Layout::new(
+                    None,
+                    Length::Flex(1),
+                    Length::Fixed(Self::playbar_height(&self.heights)),
+                )
+                .child(Layout::new("playbar", Length::Flex(1), Length::Flex(1)))
+                .child(volumebar),
+            );
+        let bounds = layout.compute(Bounds::new(x, y, width, height));
+
+        self.statebar.set_bounds(&bounds["statebar"]);
+        self.volumebar.set_bounds(&bounds["volumebar"]);
+        // The buttons of play bar are placed while it's moved
+        let playbar = bounds["playbar"];
+        self.playbar.set_size(playbar.width, playbar.height);
+        self.playbar.set_position(playbar.x, playbar.y);
+    }
+}
+
+impl TControl for ControlBar {
+    fn render(&mut self) -> Result<(), SuperError> {
+        self.inner.render()?;
+        self.statebar.render()?;
+        self.playbar.render()?;
+        self.volumebar.render()?;
+
+        Ok(())
+    }
+
+    /// The hidden bar lets the clicks through to the video beneath
+    fn is_cursor_in(&mut self, x: i32, y: i32) -> bool {
+        self.revealed && self.inner.is_cursor_in(x, y)
+    }
+
+    fn distance(&self, x: i32, y: i32) -> ControlDistance {
+        self.inner.distance(x, y)
+    }
+
+    fn canvas(&self) -> Ref<Canvas<Window>> {
+        self.inner.canvas()
+    }
+
+    fn canvas_mut(&self) -> RefMut<Canvas<Window>> {
+        self.inner.canvas_mut()
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn TControl> {
+        vec![&mut self.statebar, &mut self.playbar, &mut self.volumebar]
+    }
+
+    /// The bars within are resized by the heights of theme, which are applied after this
+    fn apply_theme(&mut self, theme: &Theme) -> Result<(), SuperError> {
+        self.inner.apply_theme(theme)?;
+        self.inner.set_background_color(theme.palette.bar);
+        self.inner.set_cursorin_background_color(theme.palette.bar);
+        self.heights = theme.heights;
+        self.set_size(None, theme.heights.controlbar);
 
-        // Set position of sub controls within
-        self.statebar.set_position(x, y);
-        if let Some(y) = y {
-            self.playbar.set_position(x, y + STATEBAR_HEIGHT as i32);
-        }
+        Ok(())
     }
 }
 
@@ -5,7 +5,7 @@ mod prebutton;
 mod rewindbutton;
 
 use std::{
-    cell::RefCell,
+    cell::{Ref, RefCell, RefMut},
     ops::{Deref, DerefMut},
     rc::Rc,
 };
@@ -13,7 +13,11 @@ use std::{
 use sdl2::{render::Canvas, video::Window};
 
 use crate::{
-    ui::components::{rectangle::Rectangle, MouseMotionParam, MouseUpParam, TControl},
+    ui::components::{
+        layout::{Bounds, Layout, Length},
+        rectangle::{Align, Rectangle},
+        ControlDistance, TControl,
+    },
     util::error::SuperError,
 };
 
@@ -63,96 +67,80 @@ impl PlayBar {
         })
     }
 
-    pub fn on_mouse_motion(&mut self, params: &MouseMotionParam) -> Result<bool, SuperError> {
-        if !self.inner.on_mouse_motion(params)? {
-            return Ok(false);
-        }
-        self.render()?;
+    pub fn set_position<X, Y>(&mut self, x: X, y: Y)
+    where
+        X: Into<Option<i32>>,
+        Y: Into<Option<i32>>,
+    {
+        let x: Option<i32> = x.into();
+        let y: Option<i32> = y.into();
 
-        self.playbutton.on_mouse_motion(params)?;
-        self.forwardbutton.on_mouse_motion(params)?;
-        self.rewindbutton.on_mouse_motion(params)?;
-        self.prebutton.on_mouse_motion(params)?;
-        self.nextbutton.on_mouse_motion(params)?;
+        // Set position of current control
+        self.inner.set_position(x, y);
+
+        // Keep the buttons in a row at the center of bar, with the play button in the middle
+        let (x, y) = self.inner.position();
+        let (width, height) = self.inner.size();
+        let layout = Layout::new(None, Length::Flex(1), Length::Flex(1))
+            .horizontal(BUTTON_MARGIN)
+            .child(Layout::spacer())
+            .child(Self::button_layout("pre", height))
+            .child(Self::button_layout("rewind", height))
+            .child(Self::button_layout("play", height))
+            .child(Self::button_layout("forward", height))
+            .child(Self::button_layout("next", height))
+            .child(Layout::spacer());
+        let bounds = layout.compute(Bounds::new(x, y, width, height));
+
+        self.prebutton.set_bounds(&bounds["pre"]);
+        self.rewindbutton.set_bounds(&bounds["rewind"]);
+        self.playbutton.set_bounds(&bounds["play"]);
+        self.forwardbutton.set_bounds(&bounds["forward"]);
+        self.nextbutton.set_bounds(&bounds["next"]);
+    }
 
-        Ok(true)
+    /// The button is a square as high as the bar, so it follows the height of theme
+    fn button_layout(name: &'static str, size: u32) -> Layout {
+        Layout::new(name, Length::Fixed(size), Length::Fixed(size))
+            .with_align(Align::VerticalCenter)
     }
+}
 
-    pub fn render(&mut self) -> Result<bool, SuperError> {
+impl TControl for PlayBar {
+    fn render(&mut self) -> Result<(), SuperError> {
         self.playbutton.render()?;
         self.rewindbutton.render()?;
         self.forwardbutton.render()?;
         self.prebutton.render()?;
         self.nextbutton.render()?;
 
-        Ok(true)
+        Ok(())
     }
 
-    pub fn set_position<X, Y>(&mut self, x: X, y: Y)
-    where
-        X: Into<Option<i32>>,
-        Y: Into<Option<i32>>,
-    {
-        let x: Option<i32> = x.into();
-        let y: Option<i32> = y.into();
-
-        // Set position of current control
-        self.inner.set_position(x, y);
+    fn is_cursor_in(&mut self, x: i32, y: i32) -> bool {
+        self.inner.is_cursor_in(x, y)
+    }
 
-        // Set position of play button
-        // Warn: Please mention that the `inner.center` function mutst be put
-        // after the `inner.set_position` function to get the latest value
-        let (center_x, center_y) = self.inner.center();
-
-        // Set position of play button, keep its center the same with the bar center
-        self.playbutton.set_center(center_x, center_y);
-        let (play_x, _) = self.playbutton.position();
-
-        // Set position of rewind button
-        let (rewind_width, rewind_height) = self.rewindbutton.size();
-        let (rewind_x, rewind_y) = (
-            play_x - rewind_width as i32 - BUTTON_MARGIN as i32,
-            center_y - rewind_height as i32 / 2,
-        );
-        self.rewindbutton.set_position(rewind_x, rewind_y);
-
-        // Set position of pre button
-        let (pre_width, pre_height) = self.prebutton.size();
-        let (pre_x, pre_y) = (
-            rewind_x - pre_width as i32 - BUTTON_MARGIN as i32,
-            center_y - pre_height as i32 / 2,
-        );
-        self.prebutton.set_position(pre_x, pre_y);
-
-        // Set position of forward button
-        let (forward_width, forward_height) = self.forwardbutton.size();
-        let (forward_x, forward_y) = (
-            play_x + forward_width as i32 + BUTTON_MARGIN as i32,
-            center_y - forward_height as i32 / 2,
-        );
-        self.forwardbutton.set_position(forward_x, forward_y);
-
-        // Set position of next button
-        let (_, next_height) = self.nextbutton.size();
-        let (next_x, next_y) = (
-            forward_x + forward_width as i32 + BUTTON_MARGIN as i32,
-            center_y - next_height as i32 / 2,
-        );
-        self.nextbutton.set_position(next_x, next_y);
+    fn distance(&self, x: i32, y: i32) -> ControlDistance {
+        self.inner.distance(x, y)
     }
 
-    pub fn on_mouse_up(&mut self, params: &MouseUpParam) -> Result<bool, SuperError> {
-        if !self.inner.on_mouse_up(params)? {
-            return Ok(false);
-        }
+    fn canvas(&self) -> Ref<Canvas<Window>> {
+        self.inner.canvas()
+    }
 
-        self.playbutton.on_mouse_up(params)?;
-        self.rewindbutton.on_mouse_up(params)?;
-        self.forwardbutton.on_mouse_up(params)?;
-        self.prebutton.on_mouse_up(params)?;
-        self.nextbutton.on_mouse_up(params)?;
+    fn canvas_mut(&self) -> RefMut<Canvas<Window>> {
+        self.inner.canvas_mut()
+    }
 
-        Ok(true)
+    fn children_mut(&mut self) -> Vec<&mut dyn TControl> {
+        vec![
+            &mut self.prebutton,
+            &mut self.rewindbutton,
+            &mut self.playbutton,
+            &mut self.forwardbutton,
+            &mut self.nextbutton,
+        ]
     }
 }
 
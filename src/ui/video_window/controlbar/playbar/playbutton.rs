@@ -1,23 +1,24 @@
 use std::{
-    cell::RefCell,
+    cell::{Ref, RefCell, RefMut},
     ops::{Deref, DerefMut},
     rc::Rc,
 };
 
-use sdl2::{
-    gfx::primitives::DrawRenderer, pixels::Color, render::Canvas, video::Window,
-};
+use sdl2::{gfx::primitives::DrawRenderer, render::Canvas, video::Window};
 
 use crate::{
     entity::EventMessage,
-    ui::components::{rectangle::button::Button, MouseUpParam, TControl},
+    ui::{
+        components::{
+            rectangle::button::Button, ControlDistance, KeyDownParam, MouseUpParam, TControl,
+        },
+        theme::Theme,
+        THEME,
+    },
     util::error::SuperError,
     EVENT_CHANNEL,
 };
 
-const ON_COLOR: Color = Color::WHITE;
-const OFF_COLOR: Color = Color::GRAY;
-
 pub struct PlayButton {
     inner: Button,
     paused: bool,
@@ -31,7 +32,9 @@ impl PlayButton {
         height: u32,
         canvas: Rc<RefCell<Canvas<Window>>>,
     ) -> Result<Self, SuperError> {
-        let inner = Button::new(x, y, width, height, canvas)?;
+        let mut inner = Button::new(x, y, width, height, canvas)?;
+        // The icon is dimmed until it's hovered or focused
+        inner.set_foreground_color(THEME.read()?.palette.text_dim);
 
         Ok(Self {
             inner,
@@ -39,15 +42,30 @@ impl PlayButton {
         })
     }
 
-    pub fn render(&mut self) -> Result<bool, SuperError> {
+    /// Toggle pause/resume state
+    fn click(&mut self) -> Result<(), SuperError> {
+        if self.paused {
+            EVENT_CHANNEL.0.send(EventMessage::Resume)?;
+        } else {
+            EVENT_CHANNEL.0.send(EventMessage::Pause)?;
+        }
+
+        self.paused = !self.paused;
+
+        Ok(())
+    }
+}
+
+impl TControl for PlayButton {
+    fn render(&mut self) -> Result<(), SuperError> {
         let canvas = self.canvas_mut();
         let (center_x, center_y) = self.center16();
         let (control_width, control_height) = self.size16();
         let (width, height) = (control_width * 2 / 3, control_height * 2 / 3);
-        let color = if self.is_cursorin() {
-            ON_COLOR
+        let color = if self.is_cursorin() || self.is_focused() {
+            self.cursorin_foreground_color()
         } else {
-            OFF_COLOR
+            self.foreground_color()
         };
 
         if self.paused {
@@ -73,25 +91,59 @@ impl PlayButton {
             canvas.rectangle(rect2_x1, rect2_y1, rect2_x2, rect2_y2, color)?;
         }
 
-        Ok(true)
+        Ok(())
     }
 
-    /// If button is clicked, toggle pause/resume state
-    pub fn on_mouse_up(&mut self, params: &MouseUpParam) -> Result<bool, SuperError> {
+    fn on_mouse_up(&mut self, params: &MouseUpParam) -> Result<bool, SuperError> {
         if !self.inner.on_mouse_up(params)? {
             return Ok(false);
         }
 
-        if self.paused {
-            EVENT_CHANNEL.0.send(EventMessage::Resume)?;
-        } else {
-            EVENT_CHANNEL.0.send(EventMessage::Pause)?;
+        self.click()?;
+
+        Ok(true)
+    }
+
+    fn on_key_down(&mut self, params: &KeyDownParam) -> Result<bool, SuperError> {
+        if !params.is_activation() {
+            return Ok(false);
         }
 
-        self.paused = !self.paused;
+        self.click()?;
 
         Ok(true)
     }
+
+    fn is_cursor_in(&mut self, x: i32, y: i32) -> bool {
+        self.inner.is_cursor_in(x, y)
+    }
+
+    fn distance(&self, x: i32, y: i32) -> ControlDistance {
+        self.inner.distance(x, y)
+    }
+
+    fn canvas(&self) -> Ref<Canvas<Window>> {
+        self.inner.canvas()
+    }
+
+    fn canvas_mut(&self) -> RefMut<Canvas<Window>> {
+        self.inner.canvas_mut()
+    }
+
+    fn is_focusable(&self) -> bool {
+        true
+    }
+
+    fn set_focused(&mut self, focused: bool) {
+        self.inner.set_focused(focused);
+    }
+
+    fn apply_theme(&mut self, theme: &Theme) -> Result<(), SuperError> {
+        self.inner.apply_theme(theme)?;
+        self.inner.set_foreground_color(theme.palette.text_dim);
+
+        Ok(())
+    }
 }
 
 impl Deref for PlayButton {
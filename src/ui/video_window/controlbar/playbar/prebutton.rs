@@ -1,22 +1,24 @@
 use std::{
-    cell::RefCell,
+    cell::{Ref, RefCell, RefMut},
     ops::{Deref, DerefMut},
-    path::PathBuf,
     rc::Rc,
 };
 
-use sdl2::{gfx::primitives::DrawRenderer, pixels::Color, render::Canvas, video::Window};
+use sdl2::{gfx::primitives::DrawRenderer, render::Canvas, video::Window};
 
 use crate::{
     entity::EventMessage,
-    ui::components::{rectangle::button::Button, MouseMotionParam, MouseUpParam, TControl},
-    util::error::SuperError,
+    ui::{
+        components::{
+            rectangle::button::Button, ControlDistance, KeyDownParam, MouseUpParam, TControl,
+        },
+        theme::Theme,
+        THEME,
+    },
+    util::error::{safe_send, SuperError},
     EVENT_CHANNEL,
 };
 
-const ON_COLOR: Color = Color::WHITE;
-const OFF_COLOR: Color = Color::GRAY;
-
 pub struct PreButton {
     inner: Button,
 }
@@ -29,19 +31,30 @@ impl PreButton {
         height: u32,
         canvas: Rc<RefCell<Canvas<Window>>>,
     ) -> Result<Self, SuperError> {
-        let inner = Button::new(x, y, width, height, canvas)?;
+        let mut inner = Button::new(x, y, width, height, canvas)?;
+        // The icon is dimmed until it's hovered or focused
+        inner.set_foreground_color(THEME.read()?.palette.text_dim);
 
         Ok(Self { inner })
     }
 
-    pub fn render(&mut self) -> Result<bool, SuperError> {
+    /// Play the previous file, a single file skips to the previous chapter instead
+    fn click(&mut self) -> Result<(), SuperError> {
+        safe_send(EVENT_CHANNEL.0.send(EventMessage::PreviousMedia));
+
+        Ok(())
+    }
+}
+
+impl TControl for PreButton {
+    fn render(&mut self) -> Result<(), SuperError> {
         let canvas = self.canvas_mut();
         let (center_x, center_y) = self.center16();
         let (control_width, control_height) = self.size16();
-        let color = if self.is_cursorin() {
-            ON_COLOR
+        let color = if self.is_cursorin() || self.is_focused() {
+            self.cursorin_foreground_color()
         } else {
-            OFF_COLOR
+            self.foreground_color()
         };
 
         let line_width = 2;
@@ -68,22 +81,59 @@ impl PreButton {
 
         canvas.aa_trigon(tx1, ty1, tx2, ty2, tx3, ty3, color)?;
 
-        Ok(true)
+        Ok(())
     }
 
-    pub fn on_mouse_up(&mut self, params: &MouseUpParam) -> Result<bool, SuperError> {
+    fn on_mouse_up(&mut self, params: &MouseUpParam) -> Result<bool, SuperError> {
         if !self.inner.on_mouse_up(params)? {
             return Ok(false);
         }
 
-        // EVENT_CHANNEL
-        //     .0
-        //     .send(EventMessage::Play(PathBuf::from("path")))?;
+        self.click()?;
+
+        Ok(true)
+    }
 
-        println!("play pre");
+    fn on_key_down(&mut self, params: &KeyDownParam) -> Result<bool, SuperError> {
+        if !params.is_activation() {
+            return Ok(false);
+        }
+
+        self.click()?;
 
         Ok(true)
     }
+
+    fn is_cursor_in(&mut self, x: i32, y: i32) -> bool {
+        self.inner.is_cursor_in(x, y)
+    }
+
+    fn distance(&self, x: i32, y: i32) -> ControlDistance {
+        self.inner.distance(x, y)
+    }
+
+    fn canvas(&self) -> Ref<Canvas<Window>> {
+        self.inner.canvas()
+    }
+
+    fn canvas_mut(&self) -> RefMut<Canvas<Window>> {
+        self.inner.canvas_mut()
+    }
+
+    fn is_focusable(&self) -> bool {
+        true
+    }
+
+    fn set_focused(&mut self, focused: bool) {
+        self.inner.set_focused(focused);
+    }
+
+    fn apply_theme(&mut self, theme: &Theme) -> Result<(), SuperError> {
+        self.inner.apply_theme(theme)?;
+        self.inner.set_foreground_color(theme.palette.text_dim);
+
+        Ok(())
+    }
 }
 
 impl Deref for PreButton {
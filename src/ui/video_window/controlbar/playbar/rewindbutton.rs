@@ -1,21 +1,22 @@
 use std::{
-    cell::RefCell,
+    cell::{Ref, RefCell, RefMut},
     ops::{Deref, DerefMut},
     rc::Rc,
 };
 
-use sdl2::{
-    gfx::primitives::DrawRenderer, pixels::Color, render::Canvas, video::Window,
-};
+use sdl2::{gfx::primitives::DrawRenderer, render::Canvas, video::Window};
 
 use crate::{
-    ui::components::{rectangle::button::Button, MouseMotionParam, MouseUpParam, TControl},
+    ui::{
+        components::{
+            rectangle::button::Button, ControlDistance, KeyDownParam, MouseUpParam, TControl,
+        },
+        theme::Theme,
+        THEME,
+    },
     util::error::SuperError, EVENT_CHANNEL, entity::EventMessage,
 };
 
-const ON_COLOR: Color = Color::WHITE;
-const OFF_COLOR: Color = Color::GRAY;
-
 pub struct RewindButton {
     inner: Button,
 }
@@ -28,20 +29,31 @@ impl RewindButton {
         height: u32,
         canvas: Rc<RefCell<Canvas<Window>>>,
     ) -> Result<Self, SuperError> {
-        let inner = Button::new(x, y, width, height, canvas)?;
+        let mut inner = Button::new(x, y, width, height, canvas)?;
+        // The icon is dimmed until it's hovered or focused
+        inner.set_foreground_color(THEME.read()?.palette.text_dim);
 
         Ok(Self { inner })
     }
 
-    pub fn render(&mut self) -> Result<bool, SuperError> {
+    /// Skip backward
+    fn click(&mut self) -> Result<(), SuperError> {
+        EVENT_CHANNEL.0.send(EventMessage::Rewind)?;
+
+        Ok(())
+    }
+}
+
+impl TControl for RewindButton {
+    fn render(&mut self) -> Result<(), SuperError> {
         let canvas = self.canvas_mut();
         let (center_x, center_y) = self.center16();
         let (control_width, control_height) = self.size16();
         let (trg_width, trg_height) = (control_width * 2 / 5, control_height * 2 / 3);
-        let color = if self.is_cursorin() {
-            ON_COLOR
+        let color = if self.is_cursorin() || self.is_focused() {
+            self.cursorin_foreground_color()
         } else {
-            OFF_COLOR
+            self.foreground_color()
         };
 
         let (trg1_x1, trg1_y1) = (center_x - trg_width as i16, center_y);
@@ -55,18 +67,59 @@ impl RewindButton {
         canvas.aa_trigon(trg1_x1, trg1_y1, trg1_x2, trg1_y2, trg1_x3, trg1_y3, color)?;
         canvas.aa_trigon(trg2_x1, trg2_y1, trg2_x2, trg2_y2, trg2_x3, trg2_y3, color)?;
 
-        Ok(true)
+        Ok(())
     }
 
-    pub fn on_mouse_up(&mut self, params: &MouseUpParam) -> Result<bool, SuperError> {
+    fn on_mouse_up(&mut self, params: &MouseUpParam) -> Result<bool, SuperError> {
         if !self.inner.on_mouse_up(params)? {
             return Ok(false);
         }
 
-        EVENT_CHANNEL.0.send(EventMessage::Rewind)?;
+        self.click()?;
+
+        Ok(true)
+    }
+
+    fn on_key_down(&mut self, params: &KeyDownParam) -> Result<bool, SuperError> {
+        if !params.is_activation() {
+            return Ok(false);
+        }
+
+        self.click()?;
 
         Ok(true)
     }
+
+    fn is_cursor_in(&mut self, x: i32, y: i32) -> bool {
+        self.inner.is_cursor_in(x, y)
+    }
+
+    fn distance(&self, x: i32, y: i32) -> ControlDistance {
+        self.inner.distance(x, y)
+    }
+
+    fn canvas(&self) -> Ref<Canvas<Window>> {
+        self.inner.canvas()
+    }
+
+    fn canvas_mut(&self) -> RefMut<Canvas<Window>> {
+        self.inner.canvas_mut()
+    }
+
+    fn is_focusable(&self) -> bool {
+        true
+    }
+
+    fn set_focused(&mut self, focused: bool) {
+        self.inner.set_focused(focused);
+    }
+
+    fn apply_theme(&mut self, theme: &Theme) -> Result<(), SuperError> {
+        self.inner.apply_theme(theme)?;
+        self.inner.set_foreground_color(theme.palette.text_dim);
+
+        Ok(())
+    }
 }
 
 impl Deref for RewindButton {
@@ -1,21 +1,61 @@
 use std::{
-    cell::RefCell,
+    cell::{Ref, RefCell, RefMut},
     ops::{Deref, DerefMut},
     rc::Rc,
     sync::atomic::Ordering,
+    time::{Duration, Instant},
 };
 
-use sdl2::{render::Canvas, video::Window};
+use log::warn;
+use rsmpeg::ffi::AVPixelFormat_AV_PIX_FMT_YUV420P as AVPIXELFORMAT_AV_PIX_FMT_YUV420P;
+use sdl2::{
+    gfx::primitives::DrawRenderer, pixels::PixelFormatEnum, rect::Rect, render::Canvas, ttf::Font,
+    video::Window,
+};
 
 use crate::{
     entity::EventMessage,
-    ui::components::{rectangle::progressbar::ProgressBar, MouseDownParam},
+    media::{
+        chapter::chapter_at,
+        decoder::VideoFrame,
+        thumbnail::{thumbnail_position, ThumbnailCache},
+    },
+    ui::{
+        components::{
+            rectangle::progressbar::ProgressBar, ControlDistance, MouseDownParam, MouseMotionParam,
+            MouseUpParam, TControl,
+        },
+        theme::{Palette, Theme},
+        THEME,
+    },
     util::error::{safe_send, SuperError},
-    EVENT_CHANNEL, VIDEO_PTS_MILLIS, VIDEO_SUMMARY,
+    AB_LOOP, AUDIO_PTS_MILLIS, EVENT_CHANNEL, MEDIA_SUMMARY, VIDEO_PTS_MILLIS, VIDEO_SUMMARY,
 };
 
+/// The number of thumbnails kept for the playing media
+const THUMBNAIL_CAPACITY: usize = 200;
+/// The space between the preview and its contents
+const PREVIEW_PADDING: u32 = 4;
+/// The space between the preview and the bar body
+const PREVIEW_MARGIN: i32 = 8;
+/// The minimum interval between the seeks while dragging the bar
+const SCRUB_INTERVAL: Duration = Duration::from_millis(100);
+
 pub struct StateBar {
     inner: ProgressBar,
+    /// Thumbnails of the playing media, the key is the quantized position
+    thumbnails: ThumbnailCache,
+    /// The position of last requested thumbnail, it's not requested again while waiting
+    requested: Option<i64>,
+    /// The font of timestamp in preview
+    font: Font<'static, 'static>,
+    scrub_throttle: ScrubThrottle,
+    /// Positions of the bookmarks of playing media, unit: milliseconds
+    bookmarks: Vec<u64>,
+    /// The colors of preview and marks
+    palette: Palette,
+    /// The radius of preview
+    preview_radius: u32,
 }
 
 impl StateBar {
@@ -27,12 +67,153 @@ impl StateBar {
         canvas: Rc<RefCell<Canvas<Window>>>,
     ) -> Result<Self, SuperError> {
         let inner = ProgressBar::new(x, y, width, height, canvas)?;
+        let theme = THEME.read()?;
+        let font = theme.load_font(theme.fonts.normal)?;
+
+        Ok(Self {
+            inner,
+            thumbnails: ThumbnailCache::new(THUMBNAIL_CAPACITY),
+            requested: None,
+            font,
+            scrub_throttle: ScrubThrottle::default(),
+            bookmarks: vec![],
+            palette: theme.palette.clone(),
+            preview_radius: theme.radii.panel,
+        })
+    }
+
+    /// Keep the generated thumbnail of the playing media
+    pub fn update_thumbnail(&mut self, millis: i64, thumbnail: VideoFrame) {
+        if self.requested == Some(millis) {
+            self.requested = None;
+        }
+        self.thumbnails.insert(millis, thumbnail);
+    }
+
+    /// Set the positions of bookmarks, unit: milliseconds
+    pub fn set_bookmarks(&mut self, bookmarks: Vec<u64>) {
+        self.bookmarks = bookmarks;
+    }
+
+    /// Remove the thumbnails of previous media
+    pub fn clear_thumbnails(&mut self) {
+        self.thumbnails.clear();
+        self.requested = None;
+    }
+
+    /// Draw the thumbnail, chapter title and timestamp of the hovered value,
+    /// the thumbnail is absent until it's generated.
+    /// The preview is centered at the cursor and kept in the bar
+    fn render_preview(&self, value: u64) -> Result<(), SuperError> {
+        let (duration, chapter) = match MEDIA_SUMMARY.read()?.as_ref() {
+            Some(s) => (
+                s.duration_millis as i64,
+                chapter_at(&s.chapters, value as i64).map(|(i, c)| c.display_title(i)),
+            ),
+            None => (0, None),
+        };
+        let thumbnail = self
+            .thumbnails
+            .get(thumbnail_position(value as i64, duration));
+
+        let mut canvas = self.canvas_mut();
+        let tc = canvas.texture_creator();
+        let sfs = self
+            .font
+            .render(&Self::format_millis(value))
+            .blended(self.palette.text)?;
+        let text = tc.create_texture_from_surface(sfs)?;
+        let (text_width, text_height) = (text.query().width, text.query().height);
+        let title = match chapter {
+            Some(chapter) => {
+                let sfs = self.font.render(&chapter).blended(self.palette.text)?;
+                Some(tc.create_texture_from_surface(sfs)?)
+            }
+            None => None,
+        };
+        let (title_width, title_height) = title
+            .as_ref()
+            .map_or((0, 0), |t| (t.query().width, t.query().height));
+        let (thumb_width, thumb_height) =
+            thumbnail.map_or((0, 0), |t| (t.width as u32, t.height as u32));
+
+        let width = thumb_width.max(text_width).max(title_width) + PREVIEW_PADDING * 2;
+        let height = thumb_height + title_height + text_height + PREVIEW_PADDING * 3;
+        let (_, bar_y) = self.bar_position();
+        let (cursor_x, _) = self.cursor_position();
+        let (left, _) = self.position();
+        let right = left + self.size().0 as i32 - width as i32;
+        let x = (cursor_x - width as i32 / 2).min(right).max(left);
+        let y = bar_y - PREVIEW_MARGIN - height as i32;
+
+        canvas.rounded_box(
+            x as i16,
+            y as i16,
+            (x + width as i32) as i16,
+            (y + height as i32) as i16,
+            self.preview_radius as i16,
+            self.palette.surface,
+        )?;
+
+        let padding = PREVIEW_PADDING as i32;
+        if let Some(thumbnail) = thumbnail {
+            if thumbnail.format == AVPIXELFORMAT_AV_PIX_FMT_YUV420P {
+                let mut texture =
+                    tc.create_texture_streaming(PixelFormatEnum::IYUV, thumb_width, thumb_height)?;
+                let data = &thumbnail.data;
+                let ypitch = thumbnail.width;
+                let uvpitch = (ypitch + 1) / 2;
+                texture.update_yuv(None, &data[0], ypitch, &data[1], uvpitch, &data[2], uvpitch)?;
+
+                let thumb_x = x + (width - thumb_width) as i32 / 2;
+                let rect = Rect::new(thumb_x, y + padding, thumb_width, thumb_height);
+                canvas.copy(&texture, None, rect)?;
+            } else {
+                warn!("unknown pixel format of thumbnail: {}", thumbnail.format);
+            }
+        }
+
+        // The chapter title is located between the thumbnail and timestamp
+        let text_x = x + (width - text_width) as i32 / 2;
+        let text_y = y + height as i32 - padding - text_height as i32;
+        if let Some(title) = title {
+            let title_x = x + (width - title_width) as i32 / 2;
+            let title_y = text_y - title_height as i32;
+            let rect = Rect::new(title_x, title_y, title_width, title_height);
+            canvas.copy(&title, None, rect)?;
+        }
+        canvas.copy(
+            &text,
+            None,
+            Rect::new(text_x, text_y, text_width, text_height),
+        )?;
+
+        Ok(())
+    }
+
+    /// Convert milliseconds to "HH:mm:ss" format
+    fn format_millis(millis: u64) -> String {
+        let total_secs = millis / 1000;
+
+        let hours = (total_secs / 3600).to_string();
+        let remainder = total_secs % 3600;
+        let minutes = (remainder / 60).to_string();
+        let secs = (remainder % 60).to_string();
+
+        let formatted_time = [
+            format!("{:0>2}", hours),
+            format!("{:0>2}", minutes),
+            format!("{:0>2}", secs),
+        ]
+        .join(":");
 
-        Ok(Self { inner })
+        formatted_time
     }
+}
 
-    pub fn render(&mut self) -> Result<bool, SuperError> {
-        let summary = VIDEO_SUMMARY.read()?;
+impl TControl for StateBar {
+    fn render(&mut self) -> Result<(), SuperError> {
+        let summary = MEDIA_SUMMARY.read()?;
         if let Some(summary) = summary.as_ref() {
             // Set max value
             let duration = summary.duration_millis;
@@ -41,51 +222,164 @@ impl StateBar {
             // Set suffix
             let suffix = Self::format_millis(duration);
             self.inner.set_suffix(suffix);
+
+            // Mark the start of chapters and the bookmarks, bookmarks are drawn over chapters
+            let chapters = summary.chapters.iter().map(|c| c.start.max(0) as u64);
+            let marks = chapters
+                .map(|m| (m, self.palette.chapter))
+                .chain(self.bookmarks.iter().map(|&m| (m, self.palette.bookmark)));
+            self.inner.set_marks(marks.collect());
         }
 
-        let pts = VIDEO_PTS_MILLIS.load(Ordering::Acquire) as u64;
-        let prefix = Self::format_millis(pts);
-        // Set current value
-        self.inner.set_value(pts);
+        // Audio media has no video timestamp, use the audio one instead
+        let pts = if VIDEO_SUMMARY.read()?.is_some() {
+            VIDEO_PTS_MILLIS.load(Ordering::Acquire)
+        } else {
+            AUDIO_PTS_MILLIS.load(Ordering::Acquire)
+        };
+        // The dragged value is kept until the button is released
+        if !self.is_dragging() {
+            self.inner.set_value(pts.max(0) as u64);
+        }
         // Set prefix
+        let prefix = Self::format_millis(self.value());
         self.inner.set_prefix(prefix);
+        // Highlight the A-B loop section
+        let (a, b) = AB_LOOP.read()?.markers();
+        self.inner
+            .set_region(a.map(|a| a as u64), b.map(|b| b as u64));
 
         // Render contents
         self.inner.render()?;
 
+        // Preview the hovered position above the bar
+        if let Some(value) = self.inner.hover_value() {
+            self.render_preview(value)?;
+        }
+
+        Ok(())
+    }
+
+    /// Request the thumbnail of hovered position if it's not generated yet.
+    /// While dragging, seek to the key frames near the cursor to show the frames there
+    fn on_mouse_motion(&mut self, params: &MouseMotionParam) -> Result<bool, SuperError> {
+        if !self.inner.on_mouse_motion(params)? {
+            return Ok(false);
+        }
+
+        let value = self.value();
+        if self.is_dragging() && self.scrub_throttle.should_scrub(value, Instant::now()) {
+            safe_send(EVENT_CHANNEL.0.send(EventMessage::ScrubTo(value as i64)));
+        }
+
+        let value = match self.inner.hover_value() {
+            Some(value) => value as i64,
+            None => return Ok(true),
+        };
+        let duration = match MEDIA_SUMMARY.read()?.as_ref() {
+            Some(summary) => summary.duration_millis as i64,
+            None => return Ok(true),
+        };
+        // Audio media has no thumbnails
+        if VIDEO_SUMMARY.read()?.is_none() {
+            return Ok(true);
+        }
+
+        let position = thumbnail_position(value, duration);
+        if !self.thumbnails.contains(position) && self.requested != Some(position) {
+            self.requested = Some(position);
+            safe_send(
+                EVENT_CHANNEL
+                    .0
+                    .send(EventMessage::RequestThumbnail(position)),
+            );
+        }
+
         Ok(true)
     }
 
     /// If mouse is clicked in the body of the progress bar,
-    /// skip to the corresponding position to play
-    pub fn on_mouse_down(&mut self, params: &MouseDownParam) -> Result<bool, SuperError> {
+    /// skip to the corresponding position to play.
+    /// Pressing the left button starts dragging, the accurate seek is done once it's released
+    fn on_mouse_down(&mut self, params: &MouseDownParam) -> Result<bool, SuperError> {
         if !self.inner.on_mouse_down(params)? {
             return Ok(false);
         }
 
+        let value = self.value();
+        let millis = value as i64;
+        if self.is_dragging() {
+            self.scrub_throttle.reset();
+            self.scrub_throttle.should_scrub(value, Instant::now());
+            safe_send(EVENT_CHANNEL.0.send(EventMessage::ScrubTo(millis)));
+        } else {
+            safe_send(EVENT_CHANNEL.0.send(EventMessage::SeekTo(millis)));
+        }
+
+        Ok(true)
+    }
+
+    /// Seek to the position accurately if the dragging is finished
+    fn on_mouse_up(&mut self, params: &MouseUpParam) -> Result<bool, SuperError> {
+        if !self.inner.on_mouse_up(params)? {
+            return Ok(false);
+        }
+
         let millis = self.value() as i64;
         safe_send(EVENT_CHANNEL.0.send(EventMessage::SeekTo(millis)));
 
         Ok(true)
     }
 
-    /// Convert milliseconds to "HH:mm:ss" format
-    fn format_millis(millis: u64) -> String {
-        let total_secs = millis / 1000;
+    fn is_cursor_in(&mut self, x: i32, y: i32) -> bool {
+        self.inner.is_cursor_in(x, y)
+    }
 
-        let hours = (total_secs / 3600).to_string();
-        let remainder = total_secs % 3600;
-        let minutes = (remainder / 60).to_string();
-        let secs = (remainder % 60).to_string();
+    fn distance(&self, x: i32, y: i32) -> ControlDistance {
+        self.inner.distance(x, y)
+    }
 
-        let formatted_time = [
-            format!("{:0>2}", hours),
-            format!("{:0>2}", minutes),
-            format!("{:0>2}", secs),
-        ]
-        .join(":");
+    fn canvas(&self) -> Ref<Canvas<Window>> {
+        self.inner.canvas()
+    }
 
-        formatted_time
+    fn canvas_mut(&self) -> RefMut<Canvas<Window>> {
+        self.inner.canvas_mut()
+    }
+
+    fn apply_theme(&mut self, theme: &Theme) -> Result<(), SuperError> {
+        self.inner.apply_theme(theme)?;
+        self.font = theme.load_font(theme.fonts.normal)?;
+        self.palette = theme.palette.clone();
+        self.preview_radius = theme.radii.panel;
+
+        Ok(())
+    }
+}
+
+/// Limit the frequency of seeks while dragging,
+/// the decoder would be busy seeking if every motion was followed
+#[derive(Default)]
+struct ScrubThrottle {
+    /// The value and time of last seek
+    last: Option<(u64, Instant)>,
+}
+
+impl ScrubThrottle {
+    /// Whether to seek to the value, the seek is recorded if it's allowed
+    fn should_scrub(&mut self, value: u64, now: Instant) -> bool {
+        if let Some((last_value, last_time)) = self.last {
+            if last_value == value || now.duration_since(last_time) < SCRUB_INTERVAL {
+                return false;
+            }
+        }
+
+        self.last = Some((value, now));
+        true
+    }
+
+    fn reset(&mut self) {
+        self.last = None;
     }
 }
 
@@ -102,3 +396,34 @@ impl DerefMut for StateBar {
         &mut self.inner
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scrub_throttle() {
+        let start = Instant::now();
+        let mut throttle = ScrubThrottle::default();
+        assert_eq!(true, throttle.should_scrub(1000, start));
+
+        // Too frequent
+        assert_eq!(
+            false,
+            throttle.should_scrub(2000, start + SCRUB_INTERVAL / 2)
+        );
+        assert_eq!(true, throttle.should_scrub(2000, start + SCRUB_INTERVAL));
+
+        // The value is not changed
+        assert_eq!(
+            false,
+            throttle.should_scrub(2000, start + SCRUB_INTERVAL * 3)
+        );
+
+        throttle.reset();
+        assert_eq!(
+            true,
+            throttle.should_scrub(2000, start + SCRUB_INTERVAL * 3)
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use std::{
+    cell::{Ref, RefCell, RefMut},
+    ops::{Deref, DerefMut},
+    rc::Rc,
+    sync::atomic::Ordering,
+};
+
+use sdl2::{pixels::Color, render::Canvas, video::Window};
+
+use crate::{
+    entity::EventMessage,
+    ui::{
+        components::{
+            rectangle::progressbar::ProgressBar, ControlDistance, MouseDownParam, MouseMotionParam,
+            MouseUpParam, TControl,
+        },
+        theme::Theme,
+        THEME,
+    },
+    util::error::{safe_send, SuperError},
+    EVENT_CHANNEL, MAX_VOLUME, MUTED, VOLUME,
+};
+
+pub struct VolumeBar {
+    inner: ProgressBar,
+    /// The color of volume while muted
+    muted_color: Color,
+    unmuted_color: Color,
+}
+
+impl VolumeBar {
+    pub fn new(
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        canvas: Rc<RefCell<Canvas<Window>>>,
+    ) -> Result<Self, SuperError> {
+        let mut inner = ProgressBar::new(x, y, width, height, canvas)?;
+        inner.set_max_value(MAX_VOLUME as u64);
+        let theme = THEME.read()?;
+
+        Ok(Self {
+            inner,
+            muted_color: theme.palette.text_dim,
+            unmuted_color: theme.palette.accent,
+        })
+    }
+}
+
+impl TControl for VolumeBar {
+    fn render(&mut self) -> Result<(), SuperError> {
+        let volume = VOLUME.load(Ordering::Acquire);
+        let muted = MUTED.load(Ordering::Acquire);
+
+        // The volume is kept while muted, only the appearance is changed
+        self.inner.set_value(volume.max(0) as u64);
+        if muted {
+            self.inner.set_progressed_color(self.muted_color);
+            self.inner.set_suffix(String::from("Muted"));
+        } else {
+            self.inner.set_progressed_color(self.unmuted_color);
+            self.inner.set_suffix(format!("{}%", volume));
+        }
+
+        self.inner.render()?;
+
+        Ok(())
+    }
+
+    /// If mouse is clicked in the body of the volume bar,
+    /// change the volume to the corresponding value
+    fn on_mouse_down(&mut self, params: &MouseDownParam) -> Result<bool, SuperError> {
+        if !self.inner.on_mouse_down(params)? {
+            return Ok(false);
+        }
+
+        let volume = self.value() as i16;
+        safe_send(EVENT_CHANNEL.0.send(EventMessage::SetVolume(volume)));
+
+        Ok(true)
+    }
+
+    fn on_mouse_motion(&mut self, params: &MouseMotionParam) -> Result<bool, SuperError> {
+        self.inner.on_mouse_motion(params)
+    }
+
+    fn on_mouse_up(&mut self, params: &MouseUpParam) -> Result<bool, SuperError> {
+        self.inner.on_mouse_up(params)
+    }
+
+    fn is_cursor_in(&mut self, x: i32, y: i32) -> bool {
+        self.inner.is_cursor_in(x, y)
+    }
+
+    fn distance(&self, x: i32, y: i32) -> ControlDistance {
+        self.inner.distance(x, y)
+    }
+
+    fn canvas(&self) -> Ref<Canvas<Window>> {
+        self.inner.canvas()
+    }
+
+    fn canvas_mut(&self) -> RefMut<Canvas<Window>> {
+        self.inner.canvas_mut()
+    }
+
+    fn apply_theme(&mut self, theme: &Theme) -> Result<(), SuperError> {
+        self.inner.apply_theme(theme)?;
+        self.muted_color = theme.palette.text_dim;
+        self.unmuted_color = theme.palette.accent;
+
+        Ok(())
+    }
+}
+
+impl Deref for VolumeBar {
+    type Target = ProgressBar;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl DerefMut for VolumeBar {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
@@ -1,40 +1,62 @@
+mod autohide;
 mod controlbar;
+pub mod osd;
 mod playbox;
+pub mod scaling;
+mod stats;
 mod titlebar;
+mod visualizer;
 
 use std::{
     cell::RefCell,
     ops::{Deref, DerefMut},
+    path::{Path, PathBuf},
+    ptr,
     rc::Rc,
+    time::{Duration, Instant},
 };
 
 use log::error;
 use sdl2::{
     image::LoadSurface,
-    pixels::Color,
-    render::Canvas,
+    mouse::MouseUtil,
+    pixels::{Color, PixelFormatEnum},
+    render::{BlendMode, Canvas, Texture},
     surface::Surface,
     video::{FullscreenType, Window, WindowPos},
     VideoSubsystem,
 };
 
 use crate::media::decoder::VideoFrame;
-use crate::util::error::SuperError;
-use crate::{APP_NAME, INIT_HEIGHT, INIT_WIDTH, LOGO_PATH};
+use crate::util::{error::SuperError, screenshot};
+use crate::{APP_NAME, INIT_HEIGHT, INIT_WIDTH, LOGO_PATH, SETTINGS, VIDEO_SUMMARY};
 
+use self::autohide::IdleTimer;
 use self::controlbar::ControlBar;
+use self::osd::{Osd, OsdMessage};
 use self::playbox::PlayBox;
+use self::scaling::{AspectRatio, ScaleMode};
+use self::stats::StatsOverlay;
 use self::titlebar::TitleBar;
 
 use super::{
     components::{
-        rectangle::Rectangle, MouseDownParam, MouseMotionParam, MouseUpParam, MouseWheelParam,
-        TControl,
+        dispatch::EventDispatcher,
+        layout::{Bounds, Layout, Length},
+        rectangle::{Align, Rectangle},
+        KeyDownParam, MouseDownParam, MouseMotionParam, MouseUpParam, MouseWheelParam, TControl,
+        TextEditingParam, TextInputParam,
     },
-    NTWindow,
+    theme::{self, Theme},
+    NTWindow, THEME,
 };
 
-pub const BACKGROUND_COLOR: Color = Color::RGB(0, 0, 0);
+/// The position of on-screen messages, they are located below the title bar
+const OSD_POSITION: (i32, i32) = (20, 60);
+/// The distance from the stats overlay to the right edge of window
+const STATS_MARGIN: i32 = 20;
+/// The bars are revealed while the cursor moves within this distance from them
+const REVEAL_DISTANCE: i32 = 60;
 
 pub struct VideoWindow {
     pub id: u32,
@@ -42,23 +64,50 @@ pub struct VideoWindow {
     titlebar: TitleBar,
     controlbar: ControlBar,
     playbox: PlayBox,
+    osd: Osd,
+    stats: StatsOverlay,
+    /// Route the mouse and key events to the controls, and keep the keyboard focus
+    dispatcher: EventDispatcher,
+    mouse: MouseUtil,
+    /// Hide the title bar and control bar if the cursor does not move near them
+    bars_timer: IdleTimer,
+    /// Hide the cursor in fullscreen if it does not move
+    cursor_timer: IdleTimer,
+    paused: bool,
+    cursor_hidden: bool,
+    /// The color around the video, it's taken from the theme
+    background_color: Color,
 }
 
 impl VideoWindow {
     pub fn new(sys: &VideoSubsystem) -> Result<Self, SuperError> {
         let wind = Self::prepare_window(sys)?;
+        // The text input is started by the text boxes while they are focused,
+        // otherwise IME takes the keys of shortcuts
+        sys.text_input().stop();
         let window_id = wind.id();
         let (x, y) = wind.position();
         let (width, height) = wind.size();
 
-        let canvas = Self::prepare_canvas(wind)?;
+        let background_color = THEME.read()?.palette.background;
+        let canvas = Self::prepare_canvas(wind, background_color)?;
         let canvas = Rc::new(RefCell::new(canvas));
         let play_box = PlayBox::new(0, 0, INIT_WIDTH, INIT_HEIGHT, canvas.clone())?;
+        let autohide_delay = Duration::from_millis(SETTINGS.read()?.autohide_delay);
 
         Ok(Self {
             titlebar: TitleBar::new(canvas.clone())?,
             controlbar: ControlBar::new(canvas.clone())?,
             playbox: play_box,
+            osd: Osd::new()?,
+            stats: StatsOverlay::new()?,
+            dispatcher: EventDispatcher::new(),
+            mouse: sys.sdl().mouse(),
+            bars_timer: IdleTimer::new(autohide_delay),
+            cursor_timer: IdleTimer::new(autohide_delay),
+            paused: false,
+            cursor_hidden: false,
+            background_color,
             id: window_id,
             inner: Rectangle::new(x, y, width, height, canvas.clone())?,
         })
@@ -70,12 +119,111 @@ impl VideoWindow {
 
     pub fn hide(&mut self) {
         self.canvas_mut().window_mut().hide();
+        self.set_cursor_hidden(false);
+    }
+
+    /// The bars are kept visible while the playback is paused
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
     }
 
     pub fn update_video_frame(&mut self, frame: VideoFrame) {
         self.playbox.update_frame(frame);
     }
 
+    pub fn update_cover(&mut self, cover: VideoFrame) {
+        self.playbox.update_cover(cover);
+    }
+
+    pub fn update_audio_samples(&mut self, data: &[f32], channels: u8, sample_rate: i32) {
+        self.playbox
+            .update_audio_samples(data, channels, sample_rate);
+    }
+
+    pub fn set_scale_mode(&mut self, mode: ScaleMode) {
+        self.playbox.set_scale_mode(mode);
+    }
+
+    pub fn set_aspect_ratio(&mut self, aspect_ratio: AspectRatio) {
+        self.playbox.set_aspect_ratio(aspect_ratio);
+    }
+
+    pub fn reset_zoom(&mut self) {
+        self.playbox.reset_zoom();
+    }
+
+    /// Rotate the video clockwise, the window follows the shape of video if it's not fullscreen
+    pub fn rotate(&mut self, degrees: i32) {
+        self.playbox.rotate(degrees);
+
+        let is_quarter_turn = (degrees / 90) % 2 != 0;
+        let is_windowed = self.canvas().window().fullscreen_state() == FullscreenType::Off;
+        if is_quarter_turn && is_windowed {
+            let (width, height) = self.inner.size();
+            self.set_size(height, width);
+        }
+    }
+
+    pub fn flip_horizontal(&mut self) {
+        self.playbox.flip_horizontal();
+    }
+
+    pub fn flip_vertical(&mut self) {
+        self.playbox.flip_vertical();
+    }
+
+    /// Save the displayed frame with its native resolution as png,
+    /// `None` is returned if there's nothing displayed
+    pub fn screenshot(&self, dir: &Path, media_name: &str) -> Result<Option<PathBuf>, SuperError> {
+        match self.playbox.displayed_frame() {
+            Some(frame) => Ok(Some(screenshot::save(frame, dir, media_name)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Keep the thumbnail of the playing media for the preview of progress
+    pub fn update_thumbnail(&mut self, millis: i64, thumbnail: VideoFrame) {
+        self.controlbar.update_thumbnail(millis, thumbnail);
+    }
+
+    /// Show the transient message above the video
+    pub fn show_osd(&mut self, msg: OsdMessage) {
+        self.osd.push(msg);
+    }
+
+    /// Show or hide the statistics of playing media
+    pub fn toggle_stats(&mut self) {
+        self.stats.toggle();
+    }
+
+    /// Show the positions of bookmarks on the progress bar, unit: milliseconds
+    pub fn set_bookmarks(&mut self, bookmarks: Vec<u64>) {
+        self.controlbar.set_bookmarks(bookmarks);
+    }
+
+    /// Remove the contents of previous media, it should be called before playing new media
+    pub fn reset_media(&mut self) {
+        self.playbox.reset();
+        self.controlbar.clear_thumbnails();
+        self.controlbar.set_bookmarks(vec![]);
+        self.osd.clear();
+        self.paused = false;
+        self.titlebar.set_title(None);
+    }
+
+    /// Show the title of media on title bar and the sdl window
+    pub fn set_media_title(&mut self, title: &str) {
+        self.titlebar.set_title(title.to_string());
+
+        if let Err(err) = self
+            .canvas_mut()
+            .window_mut()
+            .set_title(&format!("{} - {}", title, APP_NAME))
+        {
+            error!("set window title failed: {:?}", err);
+        }
+    }
+
     fn prepare_window(sys: &VideoSubsystem) -> Result<Window, SuperError> {
         let mut wind = sys
             .window("NT Player", INIT_WIDTH, INIT_HEIGHT)
@@ -94,9 +242,9 @@ impl VideoWindow {
         Ok(wind)
     }
 
-    fn prepare_canvas(wind: Window) -> Result<Canvas<Window>, SuperError> {
+    fn prepare_canvas(wind: Window, background_color: Color) -> Result<Canvas<Window>, SuperError> {
         let mut canvas = wind.into_canvas().build()?;
-        canvas.set_draw_color(BACKGROUND_COLOR);
+        canvas.set_draw_color(background_color);
 
         Ok(canvas)
     }
@@ -106,8 +254,9 @@ impl VideoWindow {
             return Ok(false);
         }
 
-        self.controlbar.on_mouse_down(params)?;
-        self.titlebar.on_mouse_down(params)?;
+        // The bars are over the play box, so only the area out of bars can be dragged to pan the video
+        let (dispatcher, mut controls) = self.split_controls();
+        dispatcher.on_mouse_down(&mut controls, params)?;
 
         Ok(true)
     }
@@ -117,9 +266,8 @@ impl VideoWindow {
             return Ok(false);
         }
 
-        self.playbox.on_mouse_up(params)?;
-        self.titlebar.on_mouse_up(params)?;
-        self.controlbar.on_mouse_up(params)?;
+        let (dispatcher, mut controls) = self.split_controls();
+        dispatcher.on_mouse_up(&mut controls, params)?;
 
         Ok(true)
     }
@@ -129,18 +277,103 @@ impl VideoWindow {
             return Ok(false);
         }
 
-        self.titlebar.on_mouse_motion(params)?;
-        self.controlbar.on_mouse_motion(params)?;
+        let (dispatcher, mut controls) = self.split_controls();
+        dispatcher.on_mouse_motion(&mut controls, params)?;
+
+        let now = Instant::now();
+        self.cursor_timer.touch(now);
+        if self.is_near_bars(params.y) {
+            self.bars_timer.touch(now);
+        }
+
         Ok(true)
     }
 
+    /// Whether the vertical position of cursor is close to the title bar or control bar
+    fn is_near_bars(&self, y: i32) -> bool {
+        let titlebar_bottom = self.titlebar.position().1 + self.titlebar.size().1 as i32;
+        let controlbar_top = self.controlbar.position().1;
+
+        y <= titlebar_bottom + REVEAL_DISTANCE || y >= controlbar_top - REVEAL_DISTANCE
+    }
+
     pub fn on_mouse_wheel(&mut self, params: &MouseWheelParam) -> Result<bool, SuperError> {
         if params.window_id != self.id {
             return Ok(false);
         }
+
+        let (dispatcher, mut controls) = self.split_controls();
+        dispatcher.on_mouse_wheel(&mut controls, params)?;
+
         Ok(true)
     }
 
+    /// Deliver the key to the focused control, Tab and Shift+Tab move the focus
+    /// # Returns
+    /// `true` if the key is consumed by the controls, then it's not taken as a shortcut
+    pub fn on_key_down(&mut self, params: &KeyDownParam) -> Result<bool, SuperError> {
+        if params.window_id != self.id {
+            return Ok(false);
+        }
+
+        let (dispatcher, mut controls) = self.split_controls();
+        let handled = dispatcher.on_key_down(&mut controls, params)?;
+        // Keep the bars shown while they are operated by keyboard
+        if handled {
+            self.bars_timer.touch(Instant::now());
+        }
+
+        Ok(handled)
+    }
+
+    /// Deliver the typed text to the focused control
+    /// # Returns
+    /// `true` if the text is taken by the controls
+    pub fn on_text_input(&mut self, params: &TextInputParam) -> Result<bool, SuperError> {
+        if params.window_id != self.id {
+            return Ok(false);
+        }
+
+        let (dispatcher, mut controls) = self.split_controls();
+        let handled = dispatcher.on_text_input(&mut controls, params)?;
+        if handled {
+            self.bars_timer.touch(Instant::now());
+        }
+
+        Ok(handled)
+    }
+
+    /// Deliver the text being composed by IME to the focused control
+    /// # Returns
+    /// `true` if the composition is taken by the controls
+    pub fn on_text_editing(&mut self, params: &TextEditingParam) -> Result<bool, SuperError> {
+        if params.window_id != self.id {
+            return Ok(false);
+        }
+
+        let (dispatcher, mut controls) = self.split_controls();
+        let handled = dispatcher.on_text_editing(&mut controls, params)?;
+        if handled {
+            self.bars_timer.touch(Instant::now());
+        }
+
+        Ok(handled)
+    }
+
+    /// Borrow the dispatcher with the top-level controls,
+    /// the controls are in the order of drawing, the later ones are on top
+    fn split_controls(&mut self) -> (&mut EventDispatcher, [&mut dyn TControl; 3]) {
+        let Self {
+            dispatcher,
+            playbox,
+            titlebar,
+            controlbar,
+            ..
+        } = self;
+
+        (dispatcher, [playbox, titlebar, controlbar])
+    }
+
     pub fn set_size(&mut self, width: u32, height: u32) {
         // Update inner size first, it's important for later computing
         self.inner.set_size(width, height);
@@ -162,19 +395,54 @@ impl VideoWindow {
         }
     }
 
+    /// Switch all the controls to the theme,
+    /// the bars are placed again since their heights may be changed
+    pub fn apply_theme(&mut self, theme: &Theme) -> Result<(), SuperError> {
+        let (_, controls) = self.split_controls();
+        for control in controls {
+            theme::apply(control, theme)?;
+        }
+        self.osd.apply_theme(theme)?;
+        self.stats.apply_theme(theme)?;
+        self.background_color = theme.palette.background;
+
+        let (width, height) = self.size();
+        self.on_resized(width, height);
+
+        Ok(())
+    }
+
     pub fn on_resized(&mut self, width: u32, height: u32) {
         self.inner.set_size(width, height);
 
-        // Adjust playbox size
-        self.playbox.set_size(width, height);
-        // Adjuist titlebar size
-        self.titlebar.set_size(width, None);
-        // Adjust controlbar size
-        self.controlbar.set_size(width, None);
-
-        // Adjust controlbar position        
-        let y = height - self.controlbar.size().1 - self.controlbar.margin().3;
-        self.controlbar.set_position(None, y as i32);
+        // The video fills the window, the title bar sticks to the top,
+        // and the control bar sticks to the bottom above its margin
+        let (_, titlebar_height) = self.titlebar.size();
+        let (_, controlbar_height) = self.controlbar.size();
+        let layout = Layout::new(None, Length::Flex(1), Length::Flex(1))
+            .child(Layout::new("playbox", Length::Flex(1), Length::Flex(1)))
+            .child(
+                Layout::new("titlebar", Length::Flex(1), Length::Fixed(titlebar_height))
+                    .with_align(Align::Top),
+            )
+            .child(
+                Layout::new(
+                    "controlbar",
+                    Length::Flex(1),
+                    Length::Fixed(controlbar_height),
+                )
+                .with_align(Align::Bottom)
+                .with_margin((0, 0, 0, self.controlbar.margin().3)),
+            );
+        let bounds = layout.compute(Bounds::new(0, 0, width, height));
+
+        self.playbox.set_bounds(&bounds["playbox"]);
+        let titlebar = bounds["titlebar"];
+        self.titlebar.set_size(titlebar.width, titlebar.height);
+        let controlbar = bounds["controlbar"];
+        self.controlbar
+            .set_size(controlbar.width, controlbar.height);
+        self.controlbar.set_position(controlbar.x, controlbar.y);
     }
 
     pub fn set_position<X, Y>(&mut self, x: X, y: Y)
@@ -199,13 +467,22 @@ impl VideoWindow {
     }
 
     pub fn render(&mut self) -> Result<bool, SuperError> {
-        self.canvas_mut().set_draw_color(Color::BLACK);
+        let background_color = self.background_color;
+        self.canvas_mut().set_draw_color(background_color);
         self.canvas_mut().clear();
 
         // Render content
         self.playbox.render()?;
-        self.titlebar.render()?;
-        self.controlbar.render()?;
+        {
+            let (x, y) = OSD_POSITION;
+            // Borrow the canvas from inner, so that the OSD can be borrowed at the same time
+            let mut canvas = self.inner.canvas_mut();
+            self.osd.render(&mut canvas, x, y)?;
+
+            let right = canvas.output_size()?.0 as i32 - STATS_MARGIN;
+            self.stats.render(&mut canvas, right, y)?;
+        }
+        self.render_bars()?;
 
         // Display on screen
         self.canvas_mut().present();
@@ -213,6 +490,85 @@ impl VideoWindow {
         Ok(true)
     }
 
+    /// Draw the title bar and control bar, they fade out if they are idle during playback
+    fn render_bars(&mut self) -> Result<(), SuperError> {
+        let now = Instant::now();
+        let is_active = self.titlebar.is_active() || self.controlbar.is_active();
+        // There's no video to be covered by the bars of audio media
+        let is_audio_only = VIDEO_SUMMARY.read()?.is_none();
+        if self.paused || is_active || is_audio_only {
+            self.bars_timer.touch(now);
+        }
+
+        match self.bars_timer.opacity(now) {
+            255 => self.draw_bars(true)?,
+            0 => self.draw_bars(false)?,
+            alpha => self.draw_faded_bars(alpha)?,
+        }
+
+        // The cursor is hidden only in fullscreen, it would be lost in a window otherwise
+        let is_fullscreen = self.canvas().window().fullscreen_state() != FullscreenType::Off;
+        let is_idle = !self.paused && !is_active && self.cursor_timer.is_idle(now);
+        self.set_cursor_hidden(is_fullscreen && is_idle);
+
+        Ok(())
+    }
+
+    fn draw_bars(&mut self, shown: bool) -> Result<(), SuperError> {
+        // The title bar is always drawn, since the title of audio media stays on it
+        self.titlebar.set_revealed(shown);
+        self.controlbar.set_revealed(shown);
+        self.titlebar.render()?;
+        if shown {
+            self.controlbar.render()?;
+        }
+
+        Ok(())
+    }
+
+    /// Draw the bars onto a transparent layer, then blend the layer with the opacity
+    fn draw_faded_bars(&mut self, alpha: u8) -> Result<(), SuperError> {
+        let (width, height) = self.canvas().output_size()?;
+        let tc = self.canvas().texture_creator();
+        let mut layer = tc.create_texture_target(PixelFormatEnum::ARGB8888, width, height)?;
+        layer.set_blend_mode(BlendMode::Blend);
+        layer.set_alpha_mod(alpha);
+
+        // The bars borrow the shared canvas by themselves while rendering,
+        // so the target is switched directly instead of `with_texture_canvas`
+        self.set_render_target(Some(&layer))?;
+        {
+            let mut canvas = self.canvas_mut();
+            canvas.set_draw_color(Color::RGBA(0, 0, 0, 0));
+            canvas.clear();
+        }
+        let drawn = self.draw_bars(true);
+        self.set_render_target(None)?;
+        drawn?;
+
+        self.canvas_mut().copy(&layer, None, None)?;
+
+        Ok(())
+    }
+
+    /// Render to the texture, or to the window if it's `None`
+    fn set_render_target(&self, texture: Option<&Texture>) -> Result<(), SuperError> {
+        let raw_texture = texture.map_or(ptr::null_mut(), |t| t.raw());
+        let ret = unsafe { sdl2::sys::SDL_SetRenderTarget(self.canvas().raw(), raw_texture) };
+        if ret != 0 {
+            return Err(sdl2::get_error().into());
+        }
+
+        Ok(())
+    }
+
+    fn set_cursor_hidden(&mut self, hidden: bool) {
+        if self.cursor_hidden != hidden {
+            self.mouse.show_cursor(!hidden);
+            self.cursor_hidden = hidden;
+        }
+    }
+
     pub fn set_fullscreen(&mut self, fs_type: FullscreenType) {
         if let Err(err) = self.canvas_mut().window_mut().set_fullscreen(fs_type) {
             error!("Failed to set fullscreen state, error: {:?}", err);
@@ -0,0 +1,391 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use sdl2::{
+    gfx::primitives::DrawRenderer,
+    pixels::Color,
+    rect::Rect,
+    render::{BlendMode, Canvas},
+    ttf::Font,
+    video::Window,
+};
+
+use crate::{
+    ui::{
+        theme::{Palette, Theme},
+        THEME,
+    },
+    util::error::SuperError,
+    MAX_VOLUME,
+};
+
+/// The time of fading out at the end of message
+const FADE_DURATION: Duration = Duration::from_millis(400);
+/// A message is shown at least this long before the next one in queue
+const MIN_DURATION: Duration = Duration::from_millis(600);
+/// The messages waiting to be shown, the oldest ones are dropped if it's exceeded
+const MAX_PENDING: usize = 5;
+const PADDING: u32 = 10;
+const LEVEL_WIDTH: u32 = 160;
+const LEVEL_HEIGHT: u32 = 6;
+
+/// The kind of on-screen message, it decides the icon and how the messages are queued
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OsdKind {
+    /// Volume changed, the level is in range [0, 1]
+    Volume(f32),
+    /// Play position changed, the progress is in range [0, 1]
+    Seek(f32),
+    Info,
+    /// Errors which do not need a dialog
+    Error,
+}
+
+impl OsdKind {
+    /// Whether a message of the same kind replaces the shown or queued one,
+    /// so that only the latest level is shown while the key is held
+    fn is_replaceable(&self, other: &OsdKind) -> bool {
+        matches!(
+            (self, other),
+            (OsdKind::Volume(_), OsdKind::Volume(_)) | (OsdKind::Seek(_), OsdKind::Seek(_))
+        )
+    }
+
+    fn default_duration(&self) -> Duration {
+        match self {
+            OsdKind::Volume(_) | OsdKind::Seek(_) => Duration::from_millis(1200),
+            OsdKind::Info => Duration::from_millis(2000),
+            OsdKind::Error => Duration::from_millis(4000),
+        }
+    }
+}
+
+/// A transient message shown above the video
+#[derive(Debug, Clone, PartialEq)]
+pub struct OsdMessage {
+    kind: OsdKind,
+    text: String,
+    /// How long the message is shown, including fading out
+    duration: Duration,
+}
+
+impl OsdMessage {
+    pub fn new<T: Into<String>>(kind: OsdKind, text: T) -> Self {
+        Self {
+            kind,
+            text: text.into(),
+            duration: kind.default_duration(),
+        }
+    }
+
+    pub fn info<T: Into<String>>(text: T) -> Self {
+        Self::new(OsdKind::Info, text)
+    }
+
+    pub fn error<T: Into<String>>(text: T) -> Self {
+        Self::new(OsdKind::Error, text)
+    }
+
+    /// The volume in percent, e.g. "Volume 80%"
+    pub fn volume(volume: i16, muted: bool) -> Self {
+        let level = volume.clamp(0, MAX_VOLUME) as f32 / MAX_VOLUME as f32;
+        let text = if muted {
+            "Muted".to_string()
+        } else {
+            format!("Volume {}%", volume)
+        };
+
+        Self::new(OsdKind::Volume(level), text)
+    }
+
+    /// The play position and duration, e.g. "00:01:05 / 01:30:00"
+    /// # Arguments
+    /// * `millis` - The position to play, unit: milliseconds
+    /// * `duration` - Duration of media, unit: milliseconds, 0 if it's unknown
+    pub fn seek(millis: i64, duration: u64) -> Self {
+        let millis = millis.max(0) as u64;
+        let (progress, text) = if duration > 0 {
+            let progress = millis.min(duration) as f32 / duration as f32;
+            let text = format!("{} / {}", format_secs(millis), format_secs(duration));
+            (progress, text)
+        } else {
+            (0.0, format_secs(millis))
+        };
+
+        Self::new(OsdKind::Seek(progress), text)
+    }
+
+    /// Show the message for the specified time instead of the default one of its kind
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+}
+
+/// Convert milliseconds to "HH:mm:ss" format
+fn format_secs(millis: u64) -> String {
+    let secs = millis / 1000;
+
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// The shown message and the ones waiting to be shown
+#[derive(Default)]
+struct OsdQueue {
+    /// The shown message and the time when it's shown
+    current: Option<(OsdMessage, Instant)>,
+    pending: VecDeque<OsdMessage>,
+}
+
+impl OsdQueue {
+    fn push(&mut self, msg: OsdMessage, now: Instant) {
+        self.advance(now);
+
+        if let Some((current, start)) = self.current.as_mut() {
+            if current.kind.is_replaceable(&msg.kind) {
+                *current = msg;
+                *start = now;
+                return;
+            }
+        }
+        if let Some(queued) = self
+            .pending
+            .iter_mut()
+            .find(|m| m.kind.is_replaceable(&msg.kind))
+        {
+            *queued = msg;
+            return;
+        }
+
+        self.pending.push_back(msg);
+        while self.pending.len() > MAX_PENDING {
+            self.pending.pop_front();
+        }
+        self.advance(now);
+    }
+
+    /// Drop the expired message and show the next one
+    fn advance(&mut self, now: Instant) {
+        loop {
+            match self.current.as_ref() {
+                Some((msg, start)) if now.duration_since(*start) < self.shown_duration(msg) => {
+                    break
+                }
+                _ => match self.pending.pop_front() {
+                    Some(next) => self.current = Some((next, now)),
+                    None => {
+                        self.current = None;
+                        break;
+                    }
+                },
+            }
+        }
+    }
+
+    /// The message is cut short if others are waiting
+    fn shown_duration(&self, msg: &OsdMessage) -> Duration {
+        if self.pending.is_empty() {
+            msg.duration
+        } else {
+            msg.duration.min(MIN_DURATION)
+        }
+    }
+
+    /// The shown message and its opacity, it fades out at the end
+    fn current(&self, now: Instant) -> Option<(&OsdMessage, u8)> {
+        let (msg, start) = self.current.as_ref()?;
+        let remaining = self
+            .shown_duration(msg)
+            .checked_sub(now.duration_since(*start))
+            .filter(|r| !r.is_zero())?;
+        let alpha = if remaining < FADE_DURATION {
+            255.0 * remaining.as_secs_f32() / FADE_DURATION.as_secs_f32()
+        } else {
+            255.0
+        };
+
+        Some((msg, alpha as u8))
+    }
+
+    fn clear(&mut self) {
+        self.current = None;
+        self.pending.clear();
+    }
+}
+
+/// On-screen display, shows the transient feedback of user operations above the video
+pub struct Osd {
+    queue: OsdQueue,
+    font: Font<'static, 'static>,
+    palette: Palette,
+    /// The radius of the box of message
+    radius: u32,
+}
+
+impl Osd {
+    pub fn new() -> Result<Self, SuperError> {
+        let theme = THEME.read()?;
+        let font = theme.load_font(theme.fonts.xlarge)?;
+
+        Ok(Self {
+            queue: OsdQueue::default(),
+            font,
+            palette: theme.palette.clone(),
+            radius: theme.radii.panel,
+        })
+    }
+
+    /// Take the colors, font and radius from the theme
+    pub fn apply_theme(&mut self, theme: &Theme) -> Result<(), SuperError> {
+        self.font = theme.load_font(theme.fonts.xlarge)?;
+        self.palette = theme.palette.clone();
+        self.radius = theme.radii.panel;
+
+        Ok(())
+    }
+
+    /// Show the message, it waits in queue if another one is being shown
+    pub fn push(&mut self, msg: OsdMessage) {
+        self.queue.push(msg, Instant::now());
+    }
+
+    /// Remove all the messages
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+
+    /// Draw the shown message with its top left corner at the specified position
+    pub fn render(
+        &mut self,
+        canvas: &mut Canvas<Window>,
+        x: i32,
+        y: i32,
+    ) -> Result<(), SuperError> {
+        let now = Instant::now();
+        self.queue.advance(now);
+        let (msg, alpha) = match self.queue.current(now) {
+            Some(current) => current,
+            None => return Ok(()),
+        };
+
+        let color = if msg.kind == OsdKind::Error {
+            self.palette.error
+        } else {
+            self.palette.text
+        };
+        let tc = canvas.texture_creator();
+        let sfs = self.font.render(&msg.text).blended(color)?;
+        let mut text = tc.create_texture_from_surface(sfs)?;
+        text.set_alpha_mod(alpha);
+        let (text_width, text_height) = (text.query().width, text.query().height);
+
+        // Volume and seek messages have a level bar under the text
+        let level = match msg.kind {
+            OsdKind::Volume(level) | OsdKind::Seek(level) => Some(level.clamp(0.0, 1.0)),
+            _ => None,
+        };
+        let level_height = level.map_or(0, |_| LEVEL_HEIGHT + PADDING);
+        let width = text_width.max(level.map_or(0, |_| LEVEL_WIDTH)) + PADDING * 2;
+        let height = text_height + level_height + PADDING * 2;
+
+        canvas.rounded_box(
+            x as i16,
+            y as i16,
+            (x + width as i32) as i16,
+            (y + height as i32) as i16,
+            self.radius as i16,
+            with_alpha(self.palette.overlay, alpha),
+        )?;
+        canvas.set_blend_mode(BlendMode::Blend);
+
+        let padding = PADDING as i32;
+        let text_rect = Rect::new(x + padding, y + padding, text_width, text_height);
+        canvas.copy(&text, None, text_rect)?;
+
+        if let Some(level) = level {
+            let level_y = y + padding * 2 + text_height as i32;
+            canvas.set_draw_color(with_alpha(self.palette.track, alpha));
+            canvas.fill_rect(Rect::new(x + padding, level_y, LEVEL_WIDTH, LEVEL_HEIGHT))?;
+
+            let filled = (LEVEL_WIDTH as f32 * level) as u32;
+            if filled > 0 {
+                canvas.set_draw_color(with_alpha(self.palette.accent, alpha));
+                canvas.fill_rect(Rect::new(x + padding, level_y, filled, LEVEL_HEIGHT))?;
+            }
+        }
+        canvas.set_blend_mode(BlendMode::None);
+
+        Ok(())
+    }
+}
+
+/// Scale the opacity of color by the specified alpha
+fn with_alpha(color: Color, alpha: u8) -> Color {
+    let a = color.a as u16 * alpha as u16 / 255;
+    Color::RGBA(color.r, color.g, color.b, a as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replace_same_kind() {
+        let now = Instant::now();
+        let mut queue = OsdQueue::default();
+        queue.push(OsdMessage::volume(80, false), now);
+        queue.push(
+            OsdMessage::volume(90, false),
+            now + Duration::from_millis(100),
+        );
+
+        // The latest volume is shown at once, and shown for the full duration again
+        let later = now + Duration::from_millis(1250);
+        let (msg, _) = queue.current(later).unwrap();
+        assert_eq!("Volume 90%", msg.text);
+        assert_eq!(0, queue.pending.len());
+    }
+
+    #[test]
+    fn test_queue() {
+        let now = Instant::now();
+        let mut queue = OsdQueue::default();
+        queue.push(OsdMessage::info("first"), now);
+        queue.push(OsdMessage::error("second"), now);
+        assert_eq!("first", queue.current(now).unwrap().0.text);
+
+        // The first one is cut short since another one is waiting
+        let next = now + MIN_DURATION;
+        queue.advance(next);
+        assert_eq!("second", queue.current(next).unwrap().0.text);
+
+        // The last one is shown for its own duration
+        let end = next + OsdKind::Error.default_duration();
+        queue.advance(end);
+        assert_eq!(None, queue.current(end));
+    }
+
+    #[test]
+    fn test_fade_out() {
+        let now = Instant::now();
+        let mut queue = OsdQueue::default();
+        let duration = Duration::from_millis(1000);
+        queue.push(OsdMessage::info("fade").with_duration(duration), now);
+
+        assert_eq!(255, queue.current(now).unwrap().1);
+        let half_faded = now + duration - FADE_DURATION / 2;
+        let alpha = queue.current(half_faded).unwrap().1;
+        assert!((126..=128).contains(&alpha), "alpha: {}", alpha);
+        assert_eq!(None, queue.current(now + duration));
+    }
+
+    #[test]
+    fn test_seek_text() {
+        let msg = OsdMessage::seek(65_500, 5_400_000);
+        assert_eq!("00:01:05 / 01:30:00", msg.text);
+        assert_eq!(OsdKind::Seek(65_500.0 / 5_400_000.0), msg.kind);
+        assert_eq!("00:00:03", OsdMessage::seek(3_000, 0).text);
+    }
+}
@@ -1,5 +1,5 @@
 use std::{
-    cell::RefCell,
+    cell::{Cell, Ref, RefCell, RefMut},
     ops::{Deref, DerefMut},
     rc::Rc,
     time::Instant,
@@ -8,25 +8,59 @@ use std::{
 use log::warn;
 use rsmpeg::ffi::AVPixelFormat_AV_PIX_FMT_YUV420P as AVPIXELFORMAT_AV_PIX_FMT_YUV420P;
 use sdl2::{
+    keyboard::Mod,
+    mouse::{MouseButton, MouseWheelDirection},
     pixels::PixelFormatEnum,
     rect::Rect,
     render::Canvas,
     video::{Window, WindowPos},
 };
 
+use super::{
+    scaling::{
+        self, Area, AspectRatio, FrameGeometry, Orientation, Placement, ScaleMode, Viewport,
+    },
+    visualizer::Visualizer,
+};
 use crate::{
     entity::EventMessage,
     media::decoder::VideoFrame,
-    ui::components::{rectangle::Rectangle, MouseUpParam, TControl},
+    ui::{
+        components::{
+            rectangle::Rectangle, ControlDistance, MouseDownParam, MouseMotionParam, MouseUpParam,
+            MouseWheelParam, TControl,
+        },
+        theme::Theme,
+        THEME,
+    },
     util::error::{safe_send, SuperError},
-    EVENT_CHANNEL,
+    EVENT_CHANNEL, SETTINGS, VIDEO_SUMMARY,
 };
 
 const DOUBLE_CLICK_INTERVAL: u128 = 200;
 
+/// The ratio of visualization height to the height of play box
+const VISUALIZER_HEIGHT_RATIO: u32 = 3;
+/// The zoom factor of each wheel step
+const ZOOM_STEP: f64 = 1.25;
+
 pub struct PlayBox {
     inner: Rectangle,
     frame: Option<VideoFrame>,
+    /// The cover of audio media
+    cover: Option<VideoFrame>,
+    /// The spectrum which is displayed for audio media without cover
+    visualizer: Visualizer,
+    scale_mode: ScaleMode,
+    aspect_ratio: AspectRatio,
+    /// Zoom and pan of the video
+    viewport: Viewport,
+    /// The rotation and flip by user, it's applied after the rotation of stream
+    orientation: Orientation,
+    /// The placement of the latest rendered frame, it's used to compute zoom and pan
+    placement: Cell<Option<Placement>>,
+    /// Whether the video is being dragged
+    dragging: bool,
     preclick: Instant,
 }
 
@@ -38,56 +72,242 @@ impl PlayBox {
         height: u32,
         canvas: Rc<RefCell<Canvas<Window>>>,
     ) -> Result<Self, SuperError> {
+        let inner = Rectangle::new(x, y, width, height, canvas.clone())?;
+        let palette = THEME.read()?.palette.clone();
+        let r = SETTINGS.read()?;
+
         Ok(Self {
-            inner: Rectangle::new(x, y, width, height, canvas.clone())?,
+            inner,
             frame: None,
+            cover: None,
+            visualizer: Visualizer::new(palette.accent, palette.accent_light),
+            scale_mode: r.scale_mode,
+            aspect_ratio: r.aspect_ratio,
+            viewport: Viewport::default(),
+            orientation: Orientation::default(),
+            placement: Cell::new(None),
+            dragging: false,
             preclick: Instant::now(),
         })
     }
 
+    pub fn set_scale_mode(&mut self, mode: ScaleMode) {
+        self.scale_mode = mode;
+    }
+
+    pub fn set_aspect_ratio(&mut self, aspect_ratio: AspectRatio) {
+        self.aspect_ratio = aspect_ratio;
+    }
+
+    pub fn reset_zoom(&mut self) {
+        self.viewport.reset();
+    }
+
+    /// Rotate the video clockwise, the zoom is reset since the shape may be changed
+    pub fn rotate(&mut self, degrees: i32) {
+        self.orientation.rotate(degrees);
+        self.viewport.reset();
+    }
+
+    pub fn flip_horizontal(&mut self) {
+        self.orientation.flip_horizontal();
+    }
+
+    pub fn flip_vertical(&mut self) {
+        self.orientation.flip_vertical();
+    }
+
+    /// The frame which is being displayed, it's the cover for audio media
+    pub fn displayed_frame(&self) -> Option<&VideoFrame> {
+        self.frame.as_ref().or(self.cover.as_ref())
+    }
+
     pub fn update_frame(&mut self, frame: VideoFrame) {
         self.frame = Some(frame);
     }
 
-    pub fn render(&mut self) -> Result<bool, SuperError> {
+    pub fn update_cover(&mut self, cover: VideoFrame) {
+        self.cover = Some(cover);
+    }
+
+    /// Feed the samples that are being played to the visualization
+    pub fn update_audio_samples(&mut self, data: &[f32], channels: u8, sample_rate: i32) {
+        self.visualizer.push_samples(data, channels, sample_rate);
+    }
+
+    /// Remove the contents of previous media
+    pub fn reset(&mut self) {
+        self.frame = None;
+        self.cover = None;
+        self.visualizer.reset();
+        self.viewport.reset();
+        self.orientation = Orientation::default();
+        self.placement.set(None);
+    }
+
+    /// Render the frame with current scale mode and aspect ratio
+    /// # Arguments
+    /// * `sar` - Sample aspect ratio of the frame
+    /// * `orientation` - Rotation and flip of the frame
+    fn render_frame(
+        &self,
+        frame: &VideoFrame,
+        sar: (i32, i32),
+        orientation: &Orientation,
+    ) -> Result<bool, SuperError> {
         let mut canvas = self.canvas_mut();
         let texture_creator = canvas.texture_creator();
 
-        if let Some(frame) = self.frame.as_ref() {
-            let frame_width = frame.width as u32;
-            let frame_height = frame.height as u32;
+        let frame_width = frame.width as u32;
+        let frame_height = frame.height as u32;
 
-            let mut texture = texture_creator.create_texture_streaming(
-                PixelFormatEnum::IYUV,
-                frame_width,
-                frame_height,
+        let mut texture = texture_creator.create_texture_streaming(
+            PixelFormatEnum::IYUV,
+            frame_width,
+            frame_height,
+        )?;
+        match frame.format {
+            AVPIXELFORMAT_AV_PIX_FMT_YUV420P => {
+                let data = &frame.data;
+                let ypitch = frame.width;
+                let upitch = (ypitch + 1) / 2;
+                let vpitch = (ypitch + 1) / 2;
+
+                texture.update_yuv(None, &data[0], ypitch, &data[1], upitch, &data[2], vpitch)?;
+            }
+            _ => {
+                warn!("unknown pixel format: {}", frame.format);
+                return Ok(false);
+            }
+        }
+
+        let geometry = FrameGeometry {
+            width: frame_width,
+            height: frame_height,
+            sar,
+        };
+        // The placement is computed with the shape after rotation
+        let placement = scaling::compute_placement(
+            &orientation.apply(&geometry),
+            self.aspect_ratio,
+            self.scale_mode,
+            &self.viewport,
+            &self.bounds(),
+        );
+        self.placement.set(placement);
+        if let Some(placement) = placement {
+            let params = scaling::orient_placement(&placement, &geometry, orientation);
+            let src = params.src;
+            let dst = params.dst;
+            canvas.copy_ex(
+                &texture,
+                Rect::new(src.x, src.y, src.width, src.height),
+                Rect::new(dst.x, dst.y, dst.width, dst.height),
+                params.angle,
+                None,
+                params.flip_h,
+                params.flip_v,
             )?;
-            match frame.format {
-                AVPIXELFORMAT_AV_PIX_FMT_YUV420P => {
-                    let data = &frame.data;
-                    let ypitch = frame.width;
-                    let upitch = ypitch / 2;
-                    let vpitch = ypitch / 2;
-
-                    texture
-                        .update_yuv(None, &data[0], ypitch, &data[1], upitch, &data[2], vpitch)?;
-                }
-                _ => {
-                    warn!("unknown pixel format: {}", frame.format);
-                    return Ok(false);
-                }
+        }
+
+        Ok(true)
+    }
+
+    fn bounds(&self) -> Area {
+        let (x, y) = self.position();
+        let (width, height) = self.size();
+
+        Area::new(x, y, width, height)
+    }
+}
+
+impl TControl for PlayBox {
+    fn render(&mut self) -> Result<(), SuperError> {
+        // Audio media has no video stream, show its cover or the spectrum instead
+        let is_audio_only = VIDEO_SUMMARY.read()?.is_none();
+
+        if let Some(frame) = self.frame.as_ref() {
+            let (sar, rotation) = VIDEO_SUMMARY
+                .read()?
+                .as_ref()
+                .map_or(((1, 1), 0), |s| (s.sample_aspect_ratio, s.rotation));
+            let orientation = self.orientation.after(rotation);
+            self.render_frame(frame, sar, &orientation)?;
+            return Ok(());
+        }
+
+        if is_audio_only {
+            if let Some(cover) = self.cover.as_ref() {
+                self.render_frame(cover, (1, 1), &self.orientation)?;
+                return Ok(());
             }
 
-            let (width, height) = self.compute_render_size(frame_width, frame_height)?;
-            let (x, y) = self.compute_render_position(width, height);
-            canvas.copy(&texture, None, Rect::new(x, y, width, height))?;
+            let (x, y) = self.position();
+            let (width, height) = self.size();
+            let vis_height = height / VISUALIZER_HEIGHT_RATIO;
+            let vis_y = y + (height - vis_height) as i32 / 2;
+            let canvas = self.inner.canvas();
+            self.visualizer
+                .render(&canvas, x, vis_y, width, vis_height)?;
+        }
+
+        Ok(())
+    }
+
+    /// Start dragging the video while it's zoomed in
+    fn on_mouse_down(&mut self, params: &MouseDownParam) -> Result<bool, SuperError> {
+        if !self.inner.on_mouse_down(params)? {
+            return Ok(false);
+        }
+
+        self.dragging = params.mouse_btn == MouseButton::Left && self.viewport.is_zoomed();
+
+        Ok(true)
+    }
+
+    /// Pan the video while it's being dragged
+    fn on_mouse_motion(&mut self, params: &MouseMotionParam) -> Result<bool, SuperError> {
+        if !self.dragging {
+            return Ok(false);
+        }
+
+        if let Some(placement) = self.placement.get() {
+            let bounds = self.bounds();
+            self.viewport
+                .pan_by(params.xrel, params.yrel, &placement, &bounds);
         }
 
         Ok(true)
     }
 
-    pub fn on_mouse_up(&mut self, params: &MouseUpParam) -> Result<bool, SuperError> {
-        if !self.inner.on_mouse_up(params)? {
+    /// Zoom in or out around the cursor while ctrl key is pressed
+    fn on_mouse_wheel(&mut self, params: &MouseWheelParam) -> Result<bool, SuperError> {
+        if !self.inner.on_mouse_wheel(params)?
+            || !params.keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD)
+        {
+            return Ok(false);
+        }
+
+        let placement = match self.placement.get() {
+            Some(placement) => placement,
+            None => return Ok(false),
+        };
+        let steps = match params.direction {
+            MouseWheelDirection::Flipped => -params.y,
+            _ => params.y,
+        };
+        let factor = ZOOM_STEP.powi(steps);
+        let cursor = (params.mouse_x, params.mouse_y);
+        self.viewport
+            .zoom_at(factor, cursor, &placement, &self.bounds());
+
+        Ok(true)
+    }
+
+    fn on_mouse_up(&mut self, params: &MouseUpParam) -> Result<bool, SuperError> {
+        // Stop dragging even if the cursor has left the play box
+        let dragged = std::mem::replace(&mut self.dragging, false);
+        if !self.inner.on_mouse_up(params)? || dragged {
             return Ok(false);
         }
 
@@ -101,25 +321,29 @@ impl PlayBox {
         Ok(true)
     }
 
-    fn compute_render_size(
-        &self,
-        frame_width: u32,
-        frame_height: u32,
-    ) -> Result<(u32, u32), SuperError> {
-        let ratio = frame_width / frame_height;
+    fn is_cursor_in(&mut self, x: i32, y: i32) -> bool {
+        self.inner.is_cursor_in(x, y)
+    }
+
+    fn distance(&self, x: i32, y: i32) -> ControlDistance {
+        self.inner.distance(x, y)
+    }
 
-        let width = self.size().0;
-        let height = width / ratio;
+    fn canvas(&self) -> Ref<Canvas<Window>> {
+        self.inner.canvas()
+    }
 
-        Ok((width, height))
+    fn canvas_mut(&self) -> RefMut<Canvas<Window>> {
+        self.inner.canvas_mut()
     }
 
-    fn compute_render_position(&self, width: u32, height: u32) -> (i32, i32) {
-        let (center_x, center_y) = self.center();
-        let render_x = center_x - width as i32 / 2;
-        let render_y = center_y - height as i32 / 2;
+    fn apply_theme(&mut self, theme: &Theme) -> Result<(), SuperError> {
+        self.inner.apply_theme(theme)?;
+        let palette = &theme.palette;
+        self.visualizer
+            .set_colors(palette.accent, palette.accent_light);
 
-        (render_x, render_y)
+        Ok(())
     }
 }
 
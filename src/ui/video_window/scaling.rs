@@ -0,0 +1,784 @@
+use serde::{Deserialize, Serialize};
+
+/// The range of zoom factor
+const MIN_ZOOM: f64 = 1.0;
+const MAX_ZOOM: f64 = 8.0;
+
+/// How the video is scaled into the play box
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScaleMode {
+    /// Show the whole video, with black bars if the aspect ratio is different
+    Fit,
+    /// Fill the whole box, the parts out of box are cropped
+    Fill,
+    /// Fill the whole box, the aspect ratio is ignored
+    Stretch,
+    /// Show the video with its own size, it's cropped if it's larger than the box
+    Original,
+}
+
+impl ScaleMode {
+    pub fn next(&self) -> Self {
+        match self {
+            ScaleMode::Fit => ScaleMode::Fill,
+            ScaleMode::Fill => ScaleMode::Stretch,
+            ScaleMode::Stretch => ScaleMode::Original,
+            ScaleMode::Original => ScaleMode::Fit,
+        }
+    }
+}
+
+/// Override the display aspect ratio of the video
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AspectRatio {
+    /// Use the aspect ratio of the stream
+    Auto,
+    Ratio4x3,
+    Ratio16x9,
+    Ratio235x1,
+}
+
+impl AspectRatio {
+    pub fn next(&self) -> Self {
+        match self {
+            AspectRatio::Auto => AspectRatio::Ratio4x3,
+            AspectRatio::Ratio4x3 => AspectRatio::Ratio16x9,
+            AspectRatio::Ratio16x9 => AspectRatio::Ratio235x1,
+            AspectRatio::Ratio235x1 => AspectRatio::Auto,
+        }
+    }
+
+    /// Width divided by height, `None` means the ratio of stream is used
+    pub fn value(&self) -> Option<f64> {
+        match self {
+            AspectRatio::Auto => None,
+            AspectRatio::Ratio4x3 => Some(4.0 / 3.0),
+            AspectRatio::Ratio16x9 => Some(16.0 / 9.0),
+            AspectRatio::Ratio235x1 => Some(2.35),
+        }
+    }
+}
+
+/// A rectangle area, the unit is pixel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Area {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Area {
+    pub fn new(x: i32, y: i32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+/// Where to copy the frame from and to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    /// The visible part of frame, in frame pixels
+    pub src: Area,
+    /// The area on canvas
+    pub dst: Area,
+    /// The area of the whole frame on canvas, it may exceed the bounds while zoomed in
+    pub frame_area: Area,
+}
+
+/// Zoom and pan of the video, pan is relative to the displayed size,
+/// so that the same part of video is visible after the window is resized
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    zoom: f64,
+    /// The offset of frame center from box center, divided by the displayed frame size
+    pan: (f64, f64),
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            zoom: MIN_ZOOM,
+            pan: (0.0, 0.0),
+        }
+    }
+}
+
+impl Viewport {
+    pub fn zoom(&self) -> f64 {
+        self.zoom
+    }
+
+    pub fn is_zoomed(&self) -> bool {
+        self.zoom > MIN_ZOOM
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Multiply the zoom by `factor`, the point of video under cursor stays at the same place
+    /// # Arguments
+    /// * `cursor` - Position of cursor on canvas
+    /// * `placement` - Current placement of the frame
+    /// * `bounds` - The area of play box
+    pub fn zoom_at(
+        &mut self,
+        factor: f64,
+        cursor: (i32, i32),
+        placement: &Placement,
+        bounds: &Area,
+    ) {
+        let zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let ratio = zoom / self.zoom;
+        let frame_area = placement.frame_area;
+        if ratio == 1.0 || frame_area.width == 0 || frame_area.height == 0 {
+            return;
+        }
+
+        // The relative position of the point under cursor in frame
+        let u = (cursor.0 - frame_area.x) as f64 / frame_area.width as f64;
+        let v = (cursor.1 - frame_area.y) as f64 / frame_area.height as f64;
+
+        let width = frame_area.width as f64 * ratio;
+        let height = frame_area.height as f64 * ratio;
+        let center_x = cursor.0 as f64 - u * width + width / 2.0;
+        let center_y = cursor.1 as f64 - v * height + height / 2.0;
+        let (box_x, box_y) = Self::center_of(bounds);
+
+        self.zoom = zoom;
+        self.pan = ((center_x - box_x) / width, (center_y - box_y) / height);
+        self.pan = self.clamped_pan(width, height, bounds);
+        if !self.is_zoomed() {
+            self.pan = (0.0, 0.0);
+        }
+    }
+
+    /// Move the video by the specified pixels, it stops at the edges of frame
+    /// # Arguments
+    /// * `placement` - Current placement of the frame
+    /// * `bounds` - The area of play box
+    pub fn pan_by(&mut self, dx: i32, dy: i32, placement: &Placement, bounds: &Area) {
+        let frame_area = placement.frame_area;
+        if !self.is_zoomed() || frame_area.width == 0 || frame_area.height == 0 {
+            return;
+        }
+
+        let (width, height) = (frame_area.width as f64, frame_area.height as f64);
+        self.pan.0 += dx as f64 / width;
+        self.pan.1 += dy as f64 / height;
+        // The overshoot is not kept, so dragging back moves the video at once
+        self.pan = self.clamped_pan(width, height, bounds);
+    }
+
+    /// Limit the pan so that no empty space is shown on the side that is larger than the box
+    fn clamped_pan(&self, width: f64, height: f64, bounds: &Area) -> (f64, f64) {
+        let limit = |size: f64, box_size: f64| {
+            if size > box_size {
+                (size - box_size) / (2.0 * size)
+            } else {
+                0.0
+            }
+        };
+        let limit_x = limit(width, bounds.width as f64);
+        let limit_y = limit(height, bounds.height as f64);
+
+        (
+            self.pan.0.clamp(-limit_x, limit_x),
+            self.pan.1.clamp(-limit_y, limit_y),
+        )
+    }
+
+    fn center_of(area: &Area) -> (f64, f64) {
+        (
+            area.x as f64 + area.width as f64 / 2.0,
+            area.y as f64 + area.height as f64 / 2.0,
+        )
+    }
+}
+
+/// The size and aspect information of a frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameGeometry {
+    pub width: u32,
+    pub height: u32,
+    /// Sample aspect ratio, aka pixel aspect ratio, `(numerator, denominator)`
+    pub sar: (i32, i32),
+}
+
+impl FrameGeometry {
+    /// Display aspect ratio, which is the frame ratio corrected by sample aspect ratio
+    pub fn display_aspect(&self) -> f64 {
+        let (num, den) = self.sar;
+        let sar = if num > 0 && den > 0 {
+            num as f64 / den as f64
+        } else {
+            1.0
+        };
+
+        self.width as f64 * sar / self.height as f64
+    }
+}
+
+/// Rotation and flip of the video, the flips are applied on screen after rotation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Orientation {
+    /// Clockwise rotation in degrees, one of 0, 90, 180 and 270
+    rotation: u32,
+    flip_h: bool,
+    flip_v: bool,
+}
+
+impl Orientation {
+    /// Create with clockwise rotation, it's rounded to the nearest quarter turn
+    pub fn from_rotation(degrees: i32) -> Self {
+        let mut orientation = Self::default();
+        orientation.rotate(degrees);
+
+        orientation
+    }
+
+    pub fn rotation(&self) -> u32 {
+        self.rotation
+    }
+
+    /// Rotate the displayed video clockwise, negative degrees rotate counterclockwise
+    pub fn rotate(&mut self, degrees: i32) {
+        let turns = (degrees as f64 / 90.0).round() as i32;
+        // Flips are on screen, so a quarter turn of the flipped video changes their direction
+        if turns % 2 != 0 {
+            std::mem::swap(&mut self.flip_h, &mut self.flip_v);
+        }
+        self.rotation = (self.rotation as i32 + turns * 90).rem_euclid(360) as u32;
+    }
+
+    pub fn flip_horizontal(&mut self) {
+        self.flip_h = !self.flip_h;
+    }
+
+    pub fn flip_vertical(&mut self) {
+        self.flip_v = !self.flip_v;
+    }
+
+    /// The rotation of `base` is applied before this one, e.g. the rotation from metadata
+    pub fn after(&self, base: u32) -> Self {
+        Self {
+            rotation: (self.rotation + base) % 360,
+            ..*self
+        }
+    }
+
+    /// Whether the width and height are swapped
+    pub fn is_quarter_turn(&self) -> bool {
+        self.rotation % 180 == 90
+    }
+
+    /// The geometry of the frame after rotation
+    pub fn apply(&self, frame: &FrameGeometry) -> FrameGeometry {
+        if !self.is_quarter_turn() {
+            return *frame;
+        }
+
+        FrameGeometry {
+            width: frame.height,
+            height: frame.width,
+            sar: (frame.sar.1, frame.sar.0),
+        }
+    }
+}
+
+/// The arguments to copy the frame with rotation and flip,
+/// the frame is rotated around the center of `dst`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CopyParams {
+    /// The visible part of frame, in pixels of the original frame
+    pub src: Area,
+    /// The area on canvas before rotation
+    pub dst: Area,
+    /// Clockwise rotation in degrees
+    pub angle: f64,
+    /// Flips of the frame before rotation
+    pub flip_h: bool,
+    pub flip_v: bool,
+}
+
+/// Convert the placement of the oriented frame to the arguments of copying the original frame
+/// # Arguments
+/// * `placement` - The placement computed with the geometry from `Orientation::apply`
+/// * `frame` - The geometry of the original frame
+pub fn orient_placement(
+    placement: &Placement,
+    frame: &FrameGeometry,
+    orientation: &Orientation,
+) -> CopyParams {
+    let (width, height) = (frame.width as i32, frame.height as i32);
+    let oriented = orientation.apply(frame);
+    let src = placement.src;
+
+    // Undo the flips on screen, in the coordinates of oriented frame
+    let x0 = if orientation.flip_h {
+        oriented.width as i32 - src.x - src.width as i32
+    } else {
+        src.x
+    };
+    let y0 = if orientation.flip_v {
+        oriented.height as i32 - src.y - src.height as i32
+    } else {
+        src.y
+    };
+    let (x1, y1) = (x0 + src.width as i32, y0 + src.height as i32);
+
+    // Undo the rotation, map to the coordinates of original frame
+    let (left, top, right, bottom) = match orientation.rotation {
+        90 => (y0, height - x1, y1, height - x0),
+        180 => (width - x1, height - y1, width - x0, height - y0),
+        270 => (width - y1, x0, width - y0, x1),
+        _ => (x0, y0, x1, y1),
+    };
+    let src = Area::new(left, top, (right - left) as u32, (bottom - top) as u32);
+
+    // The frame is rotated after copied, so the area before rotation has swapped size
+    let dst = placement.dst;
+    let (dst, flip_h, flip_v) = if orientation.is_quarter_turn() {
+        let offset_x = (dst.width as i32 - dst.height as i32) / 2;
+        let offset_y = (dst.height as i32 - dst.width as i32) / 2;
+        (
+            Area::new(dst.x + offset_x, dst.y + offset_y, dst.height, dst.width),
+            orientation.flip_v,
+            orientation.flip_h,
+        )
+    } else {
+        (dst, orientation.flip_h, orientation.flip_v)
+    };
+
+    CopyParams {
+        src,
+        dst,
+        angle: orientation.rotation as f64,
+        flip_h,
+        flip_v,
+    }
+}
+
+/// Compute where the frame is rendered in the box.
+/// `None` is returned if nothing can be displayed, e.g. the frame or box is empty
+pub fn compute_placement(
+    frame: &FrameGeometry,
+    aspect: AspectRatio,
+    mode: ScaleMode,
+    view: &Viewport,
+    bounds: &Area,
+) -> Option<Placement> {
+    if frame.width == 0 || frame.height == 0 || bounds.width == 0 || bounds.height == 0 {
+        return None;
+    }
+
+    let dar = aspect.value().unwrap_or_else(|| frame.display_aspect());
+    let (box_width, box_height) = (bounds.width as f64, bounds.height as f64);
+    let box_ratio = box_width / box_height;
+
+    // The displayed size of the whole frame, it may be larger than the box
+    let (width, height) = match mode {
+        ScaleMode::Stretch => (box_width, box_height),
+        ScaleMode::Fit if box_ratio > dar => (box_height * dar, box_height),
+        ScaleMode::Fit => (box_width, box_width / dar),
+        ScaleMode::Fill if box_ratio > dar => (box_width, box_width / dar),
+        ScaleMode::Fill => (box_height * dar, box_height),
+        // Keep the vertical resolution, the width is corrected by aspect ratio
+        ScaleMode::Original => (frame.height as f64 * dar, frame.height as f64),
+    };
+    let (width, height) = (width * view.zoom, height * view.zoom);
+    let (pan_x, pan_y) = view.clamped_pan(width, height, bounds);
+    let (center_x, center_y) = Viewport::center_of(bounds);
+    let x = center_x + pan_x * width - width / 2.0;
+    let y = center_y + pan_y * height - height / 2.0;
+
+    // Only the part inside the box is visible
+    let left = x.max(bounds.x as f64);
+    let top = y.max(bounds.y as f64);
+    let right = (x + width).min(bounds.x as f64 + box_width);
+    let bottom = (y + height).min(bounds.y as f64 + box_height);
+    if right - left < 1.0 || bottom - top < 1.0 {
+        return None;
+    }
+
+    // Map the visible part back to frame pixels
+    let scale_x = frame.width as f64 / width;
+    let scale_y = frame.height as f64 / height;
+    let src = Area::new(
+        ((left - x) * scale_x).round() as i32,
+        ((top - y) * scale_y).round() as i32,
+        (((right - left) * scale_x).round() as u32).clamp(1, frame.width),
+        (((bottom - top) * scale_y).round() as u32).clamp(1, frame.height),
+    );
+    let dst = Area::new(
+        left.round() as i32,
+        top.round() as i32,
+        (right - left).round() as u32,
+        (bottom - top).round() as u32,
+    );
+
+    let frame_area = Area::new(
+        x.round() as i32,
+        y.round() as i32,
+        width.round() as u32,
+        height.round() as u32,
+    );
+
+    Some(Placement {
+        src,
+        dst,
+        frame_area,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOUNDS: Area = Area {
+        x: 0,
+        y: 0,
+        width: 1000,
+        height: 500,
+    };
+
+    fn frame(width: u32, height: u32) -> FrameGeometry {
+        FrameGeometry {
+            width,
+            height,
+            sar: (1, 1),
+        }
+    }
+
+    fn place(frame: &FrameGeometry, aspect: AspectRatio, mode: ScaleMode) -> Placement {
+        compute_placement(frame, aspect, mode, &Viewport::default(), &BOUNDS).unwrap()
+    }
+
+    #[test]
+    fn test_fit_pillarbox() {
+        // 4:3 in a 2:1 box, black bars on left and right
+        let p = place(&frame(640, 480), AspectRatio::Auto, ScaleMode::Fit);
+
+        assert_eq!(Area::new(167, 0, 667, 500), p.dst);
+        assert_eq!(Area::new(0, 0, 640, 480), p.src);
+    }
+
+    #[test]
+    fn test_fit_letterbox() {
+        // 2.35:1 in a 2:1 box, black bars on top and bottom
+        let p = place(&frame(1880, 800), AspectRatio::Auto, ScaleMode::Fit);
+
+        assert_eq!(Area::new(0, 37, 1000, 426), p.dst);
+        assert_eq!(Area::new(0, 0, 1880, 800), p.src);
+    }
+
+    #[test]
+    fn test_sample_aspect_ratio() {
+        // Anamorphic PAL DVD, 720x576 with 64:45 pixels is 16:9
+        let anamorphic = FrameGeometry {
+            width: 720,
+            height: 576,
+            sar: (64, 45),
+        };
+        assert!((anamorphic.display_aspect() - 16.0 / 9.0).abs() < 1e-9);
+
+        let p = place(&anamorphic, AspectRatio::Auto, ScaleMode::Fit);
+        assert_eq!(Area::new(56, 0, 889, 500), p.dst);
+    }
+
+    #[test]
+    fn test_invalid_sar_is_square() {
+        let unknown = FrameGeometry {
+            width: 640,
+            height: 480,
+            sar: (0, 1),
+        };
+
+        assert!((unknown.display_aspect() - 4.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_fill_crops() {
+        // 4:3 fills a 2:1 box, the top and bottom of frame are cropped
+        let p = place(&frame(640, 480), AspectRatio::Auto, ScaleMode::Fill);
+
+        assert_eq!(BOUNDS, p.dst);
+        assert_eq!(Area::new(0, 80, 640, 320), p.src);
+    }
+
+    #[test]
+    fn test_stretch() {
+        let p = place(&frame(640, 480), AspectRatio::Auto, ScaleMode::Stretch);
+
+        assert_eq!(BOUNDS, p.dst);
+        assert_eq!(Area::new(0, 0, 640, 480), p.src);
+    }
+
+    #[test]
+    fn test_original() {
+        // Smaller than the box, centered without scaling
+        let p = place(&frame(320, 240), AspectRatio::Auto, ScaleMode::Original);
+        assert_eq!(Area::new(340, 130, 320, 240), p.dst);
+
+        // Larger than the box, the center part is visible
+        let p = place(&frame(1920, 1080), AspectRatio::Auto, ScaleMode::Original);
+        assert_eq!(BOUNDS, p.dst);
+        assert_eq!(Area::new(460, 290, 1000, 500), p.src);
+    }
+
+    #[test]
+    fn test_forced_aspect() {
+        let p = place(&frame(640, 480), AspectRatio::Ratio16x9, ScaleMode::Fit);
+        assert_eq!(Area::new(56, 0, 889, 500), p.dst);
+
+        let p = place(&frame(640, 480), AspectRatio::Ratio235x1, ScaleMode::Fit);
+        assert_eq!(Area::new(0, 37, 1000, 426), p.dst);
+    }
+
+    #[test]
+    fn test_offset_bounds() {
+        let bounds = Area::new(100, 50, 400, 300);
+        let view = Viewport::default();
+        let p = compute_placement(
+            &frame(400, 300),
+            AspectRatio::Auto,
+            ScaleMode::Fit,
+            &view,
+            &bounds,
+        )
+        .unwrap();
+
+        assert_eq!(bounds, p.dst);
+    }
+
+    #[test]
+    fn test_empty() {
+        let empty = Area::new(0, 0, 0, 0);
+        let view = Viewport::default();
+
+        assert_eq!(
+            None,
+            compute_placement(
+                &frame(640, 480),
+                AspectRatio::Auto,
+                ScaleMode::Fit,
+                &view,
+                &empty
+            )
+        );
+        assert_eq!(
+            None,
+            compute_placement(
+                &frame(0, 0),
+                AspectRatio::Auto,
+                ScaleMode::Fit,
+                &view,
+                &BOUNDS
+            )
+        );
+    }
+
+    fn place_with(frame: &FrameGeometry, view: &Viewport) -> Placement {
+        compute_placement(frame, AspectRatio::Auto, ScaleMode::Fit, view, &BOUNDS).unwrap()
+    }
+
+    #[test]
+    fn test_zoom_at_center() {
+        // 2:1 frame fills the box, zoom 2x around the center shows the center quarter
+        let frame = frame(2000, 1000);
+        let mut view = Viewport::default();
+        let p = place_with(&frame, &view);
+        view.zoom_at(2.0, (500, 250), &p, &BOUNDS);
+
+        let p = place_with(&frame, &view);
+        assert_eq!(BOUNDS, p.dst);
+        assert_eq!(Area::new(-500, -250, 2000, 1000), p.frame_area);
+        assert_eq!(Area::new(500, 250, 1000, 500), p.src);
+    }
+
+    #[test]
+    fn test_zoom_keeps_point_under_cursor() {
+        let frame = frame(2000, 1000);
+        let mut view = Viewport::default();
+        let p = place_with(&frame, &view);
+        // The cursor is on the frame pixel (500, 500)
+        view.zoom_at(2.0, (250, 250), &p, &BOUNDS);
+
+        let p = place_with(&frame, &view);
+        let area = p.frame_area;
+        let u = (250 - area.x) as f64 / area.width as f64;
+        let v = (250 - area.y) as f64 / area.height as f64;
+        assert!((u - 0.25).abs() < 1e-3 && (v - 0.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_pan_is_clamped() {
+        let frame = frame(2000, 1000);
+        let mut view = Viewport::default();
+        let p = place_with(&frame, &view);
+        // Panning is ignored while not zoomed
+        view.pan_by(100, 100, &p, &BOUNDS);
+        assert_eq!(Viewport::default(), view);
+
+        view.zoom_at(2.0, (500, 250), &p, &BOUNDS);
+        let p = place_with(&frame, &view);
+        // Drag far to the right, the left edge of frame stops at the left edge of box
+        view.pan_by(5000, 0, &p, &BOUNDS);
+        let p = place_with(&frame, &view);
+        assert_eq!(0, p.frame_area.x);
+        assert_eq!(Area::new(0, 250, 1000, 500), p.src);
+    }
+
+    #[test]
+    fn test_drag_back_after_overshoot() {
+        let frame = frame(2000, 1000);
+        let mut view = Viewport::default();
+        let p = place_with(&frame, &view);
+        view.zoom_at(2.0, (500, 250), &p, &BOUNDS);
+
+        // Drag far past the left edge, then drag back a little
+        let p = place_with(&frame, &view);
+        view.pan_by(5000, 0, &p, &BOUNDS);
+        let p = place_with(&frame, &view);
+        view.pan_by(-100, 0, &p, &BOUNDS);
+
+        let p = place_with(&frame, &view);
+        assert_eq!(-100, p.frame_area.x);
+        assert_eq!(Area::new(100, 250, 1000, 500), p.src);
+    }
+
+    #[test]
+    fn test_zoom_preserved_after_resize() {
+        let frame = frame(2000, 1000);
+        let mut view = Viewport::default();
+        let p = place_with(&frame, &view);
+        view.zoom_at(2.0, (250, 250), &p, &BOUNDS);
+        let before = place_with(&frame, &view).src;
+
+        // The box is doubled, the same part of frame is visible
+        let bounds = Area::new(0, 0, 2000, 1000);
+        let after = compute_placement(&frame, AspectRatio::Auto, ScaleMode::Fit, &view, &bounds)
+            .unwrap()
+            .src;
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn test_zoom_range() {
+        let frame = frame(2000, 1000);
+        let mut view = Viewport::default();
+        let p = place_with(&frame, &view);
+        view.zoom_at(0.5, (500, 250), &p, &BOUNDS);
+        assert_eq!(1.0, view.zoom());
+
+        for _ in 0..20 {
+            let p = place_with(&frame, &view);
+            view.zoom_at(2.0, (500, 250), &p, &BOUNDS);
+        }
+        assert_eq!(MAX_ZOOM, view.zoom());
+
+        view.reset();
+        assert_eq!(false, view.is_zoomed());
+    }
+
+    #[test]
+    fn test_orientation_rotate() {
+        let mut orientation = Orientation::from_rotation(-90);
+        assert_eq!(270, orientation.rotation());
+        assert_eq!(true, orientation.is_quarter_turn());
+
+        orientation.rotate(180);
+        assert_eq!(90, orientation.rotation());
+        // Rounded to the nearest quarter turn
+        assert_eq!(0, Orientation::from_rotation(-2).rotation());
+        assert_eq!(180, Orientation::from_rotation(185).rotation());
+
+        assert_eq!(0, Orientation::from_rotation(90).after(270).rotation());
+    }
+
+    #[test]
+    fn test_rotate_flipped() {
+        // Rotating a horizontally flipped video by a quarter turn makes it vertically flipped
+        let mut orientation = Orientation::default();
+        orientation.flip_horizontal();
+        orientation.rotate(90);
+
+        assert_eq!(false, orientation.flip_h);
+        assert_eq!(true, orientation.flip_v);
+    }
+
+    #[test]
+    fn test_rotated_geometry() {
+        let geometry = FrameGeometry {
+            width: 1920,
+            height: 1080,
+            sar: (4, 3),
+        };
+        let rotated = Orientation::from_rotation(90).apply(&geometry);
+
+        assert_eq!(1080, rotated.width);
+        assert_eq!(1920, rotated.height);
+        assert!((rotated.display_aspect() - 1.0 / geometry.display_aspect()).abs() < 1e-9);
+        assert_eq!(geometry, Orientation::from_rotation(180).apply(&geometry));
+    }
+
+    #[test]
+    fn test_portrait_phone_video() {
+        // 1920x1080 recorded with the phone held upright, shown as 1080x1920 in a 2:1 box
+        let geometry = frame(1920, 1080);
+        let orientation = Orientation::from_rotation(90);
+        let p = place(
+            &orientation.apply(&geometry),
+            AspectRatio::Auto,
+            ScaleMode::Fit,
+        );
+        assert_eq!(Area::new(359, 0, 281, 500), p.dst);
+
+        let params = orient_placement(&p, &geometry, &orientation);
+        assert_eq!(Area::new(0, 0, 1920, 1080), params.src);
+        // Same center as the visible area, with swapped size
+        assert_eq!(Area::new(250, 109, 500, 281), params.dst);
+        assert_eq!(90.0, params.angle);
+    }
+
+    #[test]
+    fn test_orient_cropped_source() {
+        let geometry = frame(400, 200);
+        let oriented_src = Area::new(10, 20, 30, 40);
+        let placement = Placement {
+            src: oriented_src,
+            dst: Area::new(0, 0, 30, 40),
+            frame_area: Area::new(0, 0, 200, 400),
+        };
+        let src_of =
+            |orientation: Orientation| orient_placement(&placement, &geometry, &orientation).src;
+
+        // Oriented 200x400, the column 10..40 is the row 160..190 of original frame
+        assert_eq!(
+            Area::new(20, 160, 40, 30),
+            src_of(Orientation::from_rotation(90))
+        );
+        assert_eq!(
+            Area::new(340, 10, 40, 30),
+            src_of(Orientation::from_rotation(270))
+        );
+        assert_eq!(
+            Area::new(360, 140, 30, 40),
+            src_of(Orientation::from_rotation(180))
+        );
+
+        let mut flipped = Orientation::default();
+        flipped.flip_horizontal();
+        assert_eq!(Area::new(360, 20, 30, 40), src_of(flipped));
+
+        // The screen flip becomes the vertical flip of frame before a quarter turn
+        flipped.rotate(90);
+        let params = orient_placement(&placement, &geometry, &flipped);
+        assert_eq!((true, false), (params.flip_h, params.flip_v));
+    }
+}
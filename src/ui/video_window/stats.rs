@@ -0,0 +1,256 @@
+use std::time::Instant;
+
+use sdl2::{gfx::primitives::DrawRenderer, rect::Rect, render::Canvas, ttf::Font, video::Window};
+
+use crate::{
+    media::{
+        decoder::{AudioSummary, MediaSummary, VideoSummary},
+        stats::StatsSnapshot,
+    },
+    ui::{
+        theme::{Palette, Theme},
+        THEME,
+    },
+    util::error::SuperError,
+    AUDIO_BUFFER, AUDIO_SUMMARY, MEDIA_SUMMARY, PLAYBACK_STATS, VIDEO_BUFFER, VIDEO_SUMMARY,
+};
+
+const PADDING: u32 = 10;
+const LINE_SPACING: u32 = 4;
+
+/// The fill levels of frame buffers, `(length, capacity)`
+pub struct BufferLevels {
+    pub audio: (usize, usize),
+    pub video: (usize, usize),
+}
+
+/// Overlay of media statistics for debugging stutter, it's hidden by default
+pub struct StatsOverlay {
+    font: Font<'static, 'static>,
+    shown: bool,
+    palette: Palette,
+    /// The radius of the box of statistics
+    radius: u32,
+}
+
+impl StatsOverlay {
+    pub fn new() -> Result<Self, SuperError> {
+        let theme = THEME.read()?;
+
+        Ok(Self {
+            font: theme.load_font(theme.fonts.large)?,
+            shown: false,
+            palette: theme.palette.clone(),
+            radius: theme.radii.panel,
+        })
+    }
+
+    /// Take the colors, font and radius from the theme
+    pub fn apply_theme(&mut self, theme: &Theme) -> Result<(), SuperError> {
+        self.font = theme.load_font(theme.fonts.large)?;
+        self.palette = theme.palette.clone();
+        self.radius = theme.radii.panel;
+
+        Ok(())
+    }
+
+    pub fn toggle(&mut self) {
+        self.shown = !self.shown;
+    }
+
+    /// Draw the statistics with the top right corner at the specified position
+    pub fn render(
+        &mut self,
+        canvas: &mut Canvas<Window>,
+        right: i32,
+        y: i32,
+    ) -> Result<(), SuperError> {
+        if !self.shown {
+            return Ok(());
+        }
+
+        let snapshot = PLAYBACK_STATS.snapshot(Instant::now());
+        let buffers = BufferLevels {
+            audio: (AUDIO_BUFFER.len(), AUDIO_BUFFER.capacity()),
+            video: (VIDEO_BUFFER.len(), VIDEO_BUFFER.capacity()),
+        };
+        let lines = stats_lines(
+            MEDIA_SUMMARY.read()?.as_ref(),
+            VIDEO_SUMMARY.read()?.as_ref(),
+            AUDIO_SUMMARY.read()?.as_ref(),
+            &snapshot,
+            &buffers,
+        );
+
+        let tc = canvas.texture_creator();
+        let mut textures = Vec::with_capacity(lines.len());
+        for line in lines.iter() {
+            let sfs = self.font.render(line).blended(self.palette.text)?;
+            textures.push(tc.create_texture_from_surface(sfs)?);
+        }
+
+        let text_width = textures.iter().map(|t| t.query().width).max().unwrap_or(0);
+        let text_height: u32 = textures
+            .iter()
+            .map(|t| t.query().height + LINE_SPACING)
+            .sum();
+        let (width, height) = (text_width + PADDING * 2, text_height + PADDING * 2);
+        let x = right - width as i32;
+
+        canvas.rounded_box(
+            x as i16,
+            y as i16,
+            (x + width as i32) as i16,
+            (y + height as i32) as i16,
+            self.radius as i16,
+            self.palette.overlay,
+        )?;
+
+        let mut line_y = y + PADDING as i32;
+        for texture in textures.iter() {
+            let query = texture.query();
+            let dst = Rect::new(x + PADDING as i32, line_y, query.width, query.height);
+            canvas.copy(texture, None, dst)?;
+            line_y += (query.height + LINE_SPACING) as i32;
+        }
+
+        Ok(())
+    }
+}
+
+/// The lines of text to show in the overlay
+pub fn stats_lines(
+    media: Option<&MediaSummary>,
+    video: Option<&VideoSummary>,
+    audio: Option<&AudioSummary>,
+    snapshot: &StatsSnapshot,
+    buffers: &BufferLevels,
+) -> Vec<String> {
+    let mut lines = vec![format!(
+        "Container: {}",
+        media.map_or("-", |m| m.format_name.as_str())
+    )];
+
+    match video {
+        Some(video) => {
+            lines.push(format!(
+                "Video: {} {}x{}",
+                video.decoder_name, video.width, video.height
+            ));
+            lines.push(format!(
+                "Frame rate: {:.2} / {:.2} fps",
+                snapshot.frame_rate,
+                nominal_frame_rate(video)
+            ));
+            lines.push(format!(
+                "Frames: {} decoded, {} dropped, {} late, {} skipped by seeking",
+                snapshot.decoded, snapshot.dropped, snapshot.late, snapshot.skipped
+            ));
+            lines.push(format!(
+                "Decode time: {:.2} ms/frame",
+                snapshot.decode_time.as_secs_f64() * 1000.0
+            ));
+        }
+        None => lines.push("Video: -".to_string()),
+    }
+
+    lines.push(match audio {
+        Some(audio) => format!(
+            "Audio: {} {} Hz, {} ch",
+            audio.decoder_name, audio.sample_rate, audio.channels
+        ),
+        None => "Audio: -".to_string(),
+    });
+
+    let (audio_len, audio_cap) = buffers.audio;
+    let (video_len, video_cap) = buffers.video;
+    lines.push(format!(
+        "Buffers: audio {}/{}, video {}/{}",
+        audio_len, audio_cap, video_len, video_cap
+    ));
+    lines.push(match snapshot.av_offset {
+        Some(offset) => format!("A/V offset: {:+} ms", offset),
+        None => "A/V offset: -".to_string(),
+    });
+
+    lines
+}
+
+/// Frames per second declared by the stream, or computed from the play interval
+fn nominal_frame_rate(video: &VideoSummary) -> f64 {
+    let (num, den) = video.frame_rate;
+    if num > 0 && den > 0 {
+        num as f64 / den as f64
+    } else if video.play_interval > 0 {
+        1000.0 / video.play_interval as f64
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn video_summary(frame_rate: (i32, i32), play_interval: u64) -> VideoSummary {
+        VideoSummary {
+            decoder_name: "h264".to_string(),
+            duration: 0,
+            duration_millis: 0,
+            frames: 0,
+            timebase_num: 1,
+            timebase_den: 1000,
+            timebase_inverse: 1000,
+            play_interval,
+            width: 1920,
+            height: 1080,
+            sample_aspect_ratio: (1, 1),
+            rotation: 0,
+            frame_rate,
+        }
+    }
+
+    #[test]
+    fn test_nominal_frame_rate() {
+        let ntsc = nominal_frame_rate(&video_summary((30000, 1001), 33));
+        assert!((ntsc - 29.97).abs() < 0.01, "{}", ntsc);
+        // Unknown frame rate falls back to the play interval
+        assert_eq!(25.0, nominal_frame_rate(&video_summary((0, 1), 40)));
+        assert_eq!(0.0, nominal_frame_rate(&video_summary((0, 0), 0)));
+    }
+
+    #[test]
+    fn test_stats_lines() {
+        let video = video_summary((25, 1), 40);
+        let snapshot = StatsSnapshot {
+            decoded: 100,
+            dropped: 2,
+            skipped: 12,
+            late: 1,
+            decode_time: Duration::from_micros(2500),
+            av_offset: Some(-15),
+            frame_rate: 24.5,
+        };
+        let buffers = BufferLevels {
+            audio: (10, 50),
+            video: (3, 10),
+        };
+
+        let lines = stats_lines(None, Some(&video), None, &snapshot, &buffers);
+        assert_eq!(
+            vec![
+                "Container: -",
+                "Video: h264 1920x1080",
+                "Frame rate: 24.50 / 25.00 fps",
+                "Frames: 100 decoded, 2 dropped, 1 late, 12 skipped by seeking",
+                "Decode time: 2.50 ms/frame",
+                "Audio: -",
+                "Buffers: audio 10/50, video 3/10",
+                "A/V offset: -15 ms",
+            ],
+            lines
+        );
+    }
+}
@@ -1,24 +1,26 @@
 use std::{
-    cell::RefCell,
+    cell::{Ref, RefCell, RefMut},
     ops::{Deref, DerefMut},
     rc::Rc,
 };
 
 use sdl2::{
-    pixels::Color,
     rect::{Point, Rect},
     render::Canvas,
     video::Window,
 };
 
 use crate::{
-    ui::components::{rectangle::button::Button, MouseMotionParam, MouseUpParam, TControl},
+    ui::{
+        components::{
+            rectangle::button::Button, ControlDistance, MouseMotionParam, MouseUpParam, TControl,
+        },
+        theme::Theme,
+        THEME,
+    },
     util::error::SuperError,
 };
 
-const BACKGROUND_COLOR: Color = Color::RGB(51, 51, 255);
-const FORE_COLOR: Color = Color::WHITE;
-
 pub struct MinimizeButton {
     inner: Button,
     selected: bool,
@@ -33,43 +35,51 @@ impl MinimizeButton {
         canvas: Rc<RefCell<Canvas<Window>>>,
     ) -> Result<Self, SuperError> {
         let inner = Button::new(x, y, width, height, canvas)?;
-
-        Ok(Self {
+        let mut button = Self {
             inner,
             selected: false,
-        })
+        };
+        button.apply_theme(&THEME.read()?)?;
+
+        Ok(button)
     }
+}
 
-    pub fn render(&mut self) -> Result<bool, SuperError> {
+impl TControl for MinimizeButton {
+    fn render(&mut self) -> Result<(), SuperError> {
         let mut canvas = self.canvas_mut();
         let (x, y) = self.position();
         let (width, height) = self.size();
         let button_rect = Rect::new(x, y, width, height);
         // draw background
         if self.selected {
-            canvas.set_draw_color(BACKGROUND_COLOR);
+            canvas.set_draw_color(self.cursorin_background_color());
             canvas.fill_rect(button_rect)?;
         }
 
         // draw X shape, the size of X shape is const value 20X20
         let (center_x, center_y) = self.center();
         let step = 5;
-        canvas.set_draw_color(FORE_COLOR);
+        canvas.set_draw_color(if self.selected {
+            self.cursorin_foreground_color()
+        } else {
+            self.foreground_color()
+        });
         canvas.draw_line(
             Point::new(center_x - step, center_y),
             Point::new(center_x + step, center_y),
         )?;
 
-        Ok(true)
+        Ok(())
     }
 
-    pub fn on_mouse_motion(&mut self, params: &MouseMotionParam) -> Result<bool, SuperError> {
+    fn on_mouse_motion(&mut self, params: &MouseMotionParam) -> Result<bool, SuperError> {
         self.selected = self.inner.on_mouse_motion(params)?;
 
         Ok(true)
     }
 
-    pub fn on_mouse_up(&mut self, params: &MouseUpParam) -> Result<bool, SuperError> {
+    fn on_mouse_up(&mut self, params: &MouseUpParam) -> Result<bool, SuperError> {
         if !self.inner.on_mouse_up(params)? {
             return Ok(false);
         }
@@ -78,6 +88,30 @@ impl MinimizeButton {
 
         Ok(true)
     }
+
+    fn is_cursor_in(&mut self, x: i32, y: i32) -> bool {
+        self.inner.is_cursor_in(x, y)
+    }
+
+    fn distance(&self, x: i32, y: i32) -> ControlDistance {
+        self.inner.distance(x, y)
+    }
+
+    fn canvas(&self) -> Ref<Canvas<Window>> {
+        self.inner.canvas()
+    }
+
+    fn canvas_mut(&self) -> RefMut<Canvas<Window>> {
+        self.inner.canvas_mut()
+    }
+
+    fn apply_theme(&mut self, theme: &Theme) -> Result<(), SuperError> {
+        self.inner.apply_theme(theme)?;
+        self.set_cursorin_background_color(theme.palette.button);
+        self.set_cursorin_foreground_color(theme.palette.on_accent);
+
+        Ok(())
+    }
 }
 
 impl Deref for MinimizeButton {
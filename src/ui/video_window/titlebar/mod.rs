@@ -3,24 +3,34 @@ mod maximize_button;
 mod minimize_button;
 
 use std::{
-    cell::RefCell,
+    cell::{Ref, RefCell, RefMut},
     ops::{Deref, DerefMut},
     rc::Rc,
 };
 
-use sdl2::{render::Canvas, video::Window};
+use sdl2::{pixels::Color, rect::Rect, render::Canvas, ttf::Font, video::Window};
 
 use self::{
     close_button::CloseButton, maximize_button::MaximizeButton, minimize_button::MinimizeButton,
 };
 use crate::{
-    ui::components::{
-        rectangle::Rectangle, MouseDownParam, MouseMotionParam, MouseUpParam, TControl,
+    ui::{
+        components::{
+            layout::{Bounds, Layout, Length},
+            rectangle::{Align, Rectangle},
+            ControlDistance, MouseDownParam, MouseMotionParam, MouseUpParam, TControl,
+        },
+        theme::Theme,
+        THEME,
     },
     util::error::SuperError,
+    VIDEO_SUMMARY,
 };
 
-const TITLEBAR_HEIGHT: u32 = 40;
+/// The left margin of title and the space between title and buttons
+const TITLE_MARGIN: i32 = 10;
+const BUTTON_WIDTH: u32 = 40;
+const BUTTON_SPACING: u32 = 5;
 
 pub struct TitleBar {
     inner: Rectangle,
@@ -29,40 +39,51 @@ pub struct TitleBar {
     minimize_button: MinimizeButton,
     /// Indicate if user is operating on this control
     op_flag: bool,
+    /// Indicate if the buttons and title are shown without operating on this control
+    revealed: bool,
+    /// The title of media
+    title: Option<String>,
+    /// The font of title
+    font: Font<'static, 'static>,
+    title_color: Color,
+    /// The height of buttons, they are as wide as `BUTTON_WIDTH`
+    button_height: u32,
 }
 
 impl TitleBar {
     pub fn new(canvas: Rc<RefCell<Canvas<Window>>>) -> Result<Self, SuperError> {
         let (window_width, _) = canvas.clone().borrow().output_size()?;
+        let theme = THEME.read()?.clone();
         let x = 0;
         let y = 0;
         let width = window_width;
-        let height = TITLEBAR_HEIGHT;
+        let height = theme.heights.titlebar;
 
-        let inner = Rectangle::new(x, y, width, height, canvas.clone())?;
+        let mut inner = Rectangle::new(x, y, width, height, canvas.clone())?;
+        inner.set_background_color(theme.palette.bar);
+        inner.set_cursorin_background_color(theme.palette.bar);
 
-        let btn_width: u32 = 40;
-        let btn_height: u32 = 30;
-        let btn_y = inner.center().1 - btn_height as i32 / 2;
+        // The buttons are placed by the layout later
+        let (btn_width, btn_height) = (BUTTON_WIDTH, theme.heights.title_button);
+        let close_button = CloseButton::new(x, y, btn_width, btn_height, canvas.clone())?;
+        let maximize_button = MaximizeButton::new(x, y, btn_width, btn_height, canvas.clone())?;
+        let minimize_button = MinimizeButton::new(x, y, btn_width, btn_height, canvas.clone())?;
 
-        let close_btn_x = (window_width - btn_width) as i32;
-        let max_btn_x = close_btn_x - btn_width as i32 - 5;
-        let mini_button_x = max_btn_x - btn_width as i32 - 5;
-
-        let close_button =
-            CloseButton::new(close_btn_x, btn_y, btn_width, btn_height, canvas.clone())?;
-        let maximize_button =
-            MaximizeButton::new(max_btn_x, btn_y, btn_width, btn_height, canvas.clone())?;
-        let minimize_button =
-            MinimizeButton::new(mini_button_x, btn_y, btn_width, btn_height, canvas.clone())?;
-
-        Ok(Self {
+        let mut titlebar = Self {
             inner,
             close_button,
             maxmize_button: maximize_button,
             minimize_button,
             op_flag: false,
-        })
+            revealed: false,
+            title: None,
+            font: theme.load_font(theme.fonts.large)?,
+            title_color: theme.palette.text,
+            button_height: btn_height,
+        };
+        titlebar.relayout();
+
+        Ok(titlebar)
     }
 
     pub fn set_size<W, H>(&mut self, width: W, height: H)
@@ -70,40 +91,101 @@ impl TitleBar {
         W: Into<Option<u32>>,
         H: Into<Option<u32>>,
     {
-        let width: Option<u32> = width.into();
-        let height: Option<u32> = height.into();
-
-        // Set size of current control
         self.inner.set_size(width, height);
+        self.relayout();
+    }
 
-        // Adjust the position of controls within
-        if let Some(width) = width {
-            // Adjust position of close button since the titlebar size has been changed
-            let clbtn_x = self.position().0 + width as i32 - self.close_button.size().0 as i32;
-            self.close_button.set_position(clbtn_x, None);
+    /// Keep the buttons in a row at the right side, the title takes the space left
+    fn relayout(&mut self) {
+        let (x, y) = self.position();
+        let (width, height) = self.size();
+
+        let button = |name: &'static str| {
+            Layout::new(
+                name,
+                Length::Fixed(BUTTON_WIDTH),
+                Length::Fixed(self.button_height),
+            )
+            .with_align(Align::VerticalCenter)
+        };
+        let layout = Layout::new(None, Length::Flex(1), Length::Flex(1))
+            .horizontal(BUTTON_SPACING)
+            .child(Layout::spacer())
+            .child(button("minimize"))
+            .child(button("maximize"))
+            .child(button("close"));
+        let bounds = layout.compute(Bounds::new(x, y, width, height));
+
+        self.minimize_button.set_bounds(&bounds["minimize"]);
+        self.maxmize_button.set_bounds(&bounds["maximize"]);
+        self.close_button.set_bounds(&bounds["close"]);
+    }
+
+    /// Set the title that will be displayed at the left side
+    pub fn set_title<T: Into<Option<String>>>(&mut self, title: T) {
+        self.title = title.into();
+    }
 
-            // Adjust position of maxmize button since the titlebar size has been changed
-            let mxbtn_x = clbtn_x - self.maxmize_button.size().0 as i32;
-            self.maxmize_button.set_position(mxbtn_x, None);
+    /// Show the buttons and title even if user is not operating on this control
+    pub fn set_revealed(&mut self, revealed: bool) {
+        self.revealed = revealed;
+    }
 
-            // Adjust position of minimize button since the titlebar size has been changed
-            let mibtn_x = mxbtn_x - self.minimize_button.size().0 as i32;
-            self.minimize_button.set_position(mibtn_x, None);
+    /// Whether user is operating on the title bar
+    pub fn is_active(&self) -> bool {
+        self.op_flag
+    }
+
+    fn render_title(&self) -> Result<(), SuperError> {
+        let title = match self.title.as_ref() {
+            Some(title) if !title.is_empty() => title,
+            _ => return Ok(()),
+        };
+
+        let mut canvas = self.canvas_mut();
+        let sfs = self.font.render(title).blended(self.title_color)?;
+        let tc = canvas.texture_creator();
+        let texture = tc.create_texture_from_surface(sfs)?;
+        let query = texture.query();
+
+        // Don't overlap the buttons, cut the title if it's too long
+        let x = self.position().0 + TITLE_MARGIN;
+        let max_width = self.minimize_button.position().0 - TITLE_MARGIN - x;
+        if max_width <= 0 {
+            return Ok(());
         }
+        let width = query.width.min(max_width as u32);
+        let y = self.center().1 - query.height as i32 / 2;
+
+        let src_rect = Rect::new(0, 0, width, query.height);
+        let dst_rect = Rect::new(x, y, width, query.height);
+        canvas.copy(&texture, src_rect, dst_rect)?;
+
+        Ok(())
     }
+}
 
-    pub fn render(&mut self) -> Result<bool, SuperError> {
+impl TControl for TitleBar {
+    fn render(&mut self) -> Result<(), SuperError> {
         // If user is currently operating on canvas, show sub components
-        if self.op_flag {
+        let shown = self.op_flag || self.revealed;
+        if shown {
+            self.inner.render()?;
             self.close_button.render()?;
             self.maxmize_button.render()?;
             self.minimize_button.render()?;
         }
 
-        Ok(true)
+        // Title of audio media is always displayed since there's no video
+        let is_audio_only = VIDEO_SUMMARY.read()?.is_none();
+        if shown || is_audio_only {
+            self.render_title()?;
+        }
+
+        Ok(())
     }
 
-    pub fn on_mouse_motion(&mut self, params: &MouseMotionParam) -> Result<bool, SuperError> {
+    fn on_mouse_motion(&mut self, params: &MouseMotionParam) -> Result<bool, SuperError> {
         if !self.inner.on_mouse_motion(params)? {
             self.op_flag = false;
             return Ok(false);
@@ -111,14 +193,10 @@ impl TitleBar {
 
         self.op_flag = true;
 
-        self.close_button.on_mouse_motion(params)?;
-        self.maxmize_button.on_mouse_motion(params)?;
-        self.minimize_button.on_mouse_motion(params)?;
-
         Ok(true)
     }
 
-    pub fn on_mouse_up(&mut self, params: &MouseUpParam) -> Result<bool, SuperError> {
+    fn on_mouse_up(&mut self, params: &MouseUpParam) -> Result<bool, SuperError> {
         if !self.inner.on_mouse_up(params)? {
             self.op_flag = false;
             return Ok(false);
@@ -126,14 +204,10 @@ impl TitleBar {
 
         self.op_flag = true;
 
-        self.close_button.on_mouse_up(params)?;
-        self.maxmize_button.on_mouse_up(params)?;
-        self.minimize_button.on_mouse_up(params)?;
-
         Ok(true)
     }
 
-    pub fn on_mouse_down(&mut self, params: &MouseDownParam) -> Result<bool, SuperError> {
+    fn on_mouse_down(&mut self, params: &MouseDownParam) -> Result<bool, SuperError> {
         if !self.inner.on_mouse_down(params)? {
             self.op_flag = false;
             return Ok(false);
@@ -143,6 +217,44 @@ impl TitleBar {
 
         Ok(true)
     }
+
+    /// The hidden bar lets the clicks through to the video beneath
+    fn is_cursor_in(&mut self, x: i32, y: i32) -> bool {
+        self.revealed && self.inner.is_cursor_in(x, y)
+    }
+
+    fn distance(&self, x: i32, y: i32) -> ControlDistance {
+        self.inner.distance(x, y)
+    }
+
+    fn canvas(&self) -> Ref<Canvas<Window>> {
+        self.inner.canvas()
+    }
+
+    fn canvas_mut(&self) -> RefMut<Canvas<Window>> {
+        self.inner.canvas_mut()
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn TControl> {
+        vec![
+            &mut self.minimize_button,
+            &mut self.maxmize_button,
+            &mut self.close_button,
+        ]
+    }
+
+    /// The buttons within are resized by the heights of theme, which are applied after this
+    fn apply_theme(&mut self, theme: &Theme) -> Result<(), SuperError> {
+        self.inner.apply_theme(theme)?;
+        self.inner.set_background_color(theme.palette.bar);
+        self.inner.set_cursorin_background_color(theme.palette.bar);
+        self.font = theme.load_font(theme.fonts.large)?;
+        self.title_color = theme.palette.text;
+        self.button_height = theme.heights.title_button;
+        self.set_size(None, theme.heights.titlebar);
+
+        Ok(())
+    }
 }
 
 impl Deref for TitleBar {
@@ -0,0 +1,145 @@
+use std::{collections::VecDeque, f32::consts::PI};
+
+use sdl2::{gfx::primitives::DrawRenderer, pixels::Color, render::Canvas, video::Window};
+
+use crate::util::error::SuperError;
+
+/// Number of samples used to compute the spectrum
+const WINDOW_SIZE: usize = 1024;
+/// Number of bars in the spectrum
+const BAND_COUNT: usize = 32;
+/// The frequency of the first band, unit: Hz
+const MIN_FREQUENCY: f32 = 40.0;
+/// The lowest level that can be displayed, unit: dB
+const FLOOR_DB: f32 = -60.0;
+/// The ratio of previous level kept in each frame, makes the bars fall down smoothly
+const DECAY: f32 = 0.85;
+/// The horizontal space between bars
+const BAR_SPACE: u32 = 2;
+
+/// Spectrum visualization for audio media without video
+pub struct Visualizer {
+    /// The latest mono samples
+    samples: VecDeque<f32>,
+    /// Samples per second
+    sample_rate: i32,
+    /// The displayed level of each band, range: [0, 1]
+    levels: [f32; BAND_COUNT],
+    /// The color of bars
+    low_color: Color,
+    /// The color of the bars which are near the top
+    high_color: Color,
+}
+
+impl Visualizer {
+    pub fn new(low_color: Color, high_color: Color) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(WINDOW_SIZE * 2),
+            sample_rate: 0,
+            levels: [0.0; BAND_COUNT],
+            low_color,
+            high_color,
+        }
+    }
+
+    pub fn set_colors(&mut self, low_color: Color, high_color: Color) {
+        self.low_color = low_color;
+        self.high_color = high_color;
+    }
+
+    /// Push the interleaved samples of audio frame, only the latest samples are kept
+    pub fn push_samples(&mut self, data: &[f32], channels: u8, sample_rate: i32) {
+        let channels = channels.max(1) as usize;
+        self.sample_rate = sample_rate;
+
+        // Mix all channels to mono
+        for frame in data.chunks(channels) {
+            let mono = frame.iter().sum::<f32>() / frame.len() as f32;
+            self.samples.push_back(mono);
+        }
+
+        while self.samples.len() > WINDOW_SIZE {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Remove all the samples and levels, it should be called while media changed
+    pub fn reset(&mut self) {
+        self.samples.clear();
+        self.levels = [0.0; BAND_COUNT];
+    }
+
+    /// Draw the spectrum bars in the specified area
+    pub fn render(
+        &mut self,
+        canvas: &Canvas<Window>,
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), SuperError> {
+        self.update_levels();
+
+        let bar_width = (width / BAND_COUNT as u32).saturating_sub(BAR_SPACE).max(1);
+        let bottom = (y + height as i32) as i16;
+        for (band, level) in self.levels.iter().enumerate() {
+            let bar_height = (height as f32 * level) as i16;
+            if bar_height <= 0 {
+                continue;
+            }
+
+            let x1 = x + (band as u32 * (bar_width + BAR_SPACE)) as i32;
+            let x2 = x1 + bar_width as i32;
+            let color = if *level > 0.7 {
+                self.high_color
+            } else {
+                self.low_color
+            };
+            canvas.box_(x1 as i16, bottom - bar_height, x2 as i16, bottom, color)?;
+        }
+
+        Ok(())
+    }
+
+    /// Compute the level of each band with latest samples,
+    /// bands are spaced logarithmically from `MIN_FREQUENCY` to half of the sample rate
+    fn update_levels(&mut self) {
+        if self.samples.len() < WINDOW_SIZE || self.sample_rate <= 0 {
+            for level in self.levels.iter_mut() {
+                *level *= DECAY;
+            }
+            return;
+        }
+
+        let samples = self.samples.make_contiguous();
+        let sample_rate = self.sample_rate as f32;
+        let max_frequency = sample_rate / 2.0;
+        for (band, level) in self.levels.iter_mut().enumerate() {
+            let position = (band as f32 + 0.5) / BAND_COUNT as f32;
+            let frequency = MIN_FREQUENCY * (max_frequency / MIN_FREQUENCY).powf(position);
+            let magnitude = Self::magnitude_at(samples, frequency, sample_rate);
+
+            let db = 20.0 * (magnitude + f32::EPSILON).log10();
+            let new_level = ((db - FLOOR_DB) / -FLOOR_DB).clamp(0.0, 1.0);
+            *level = new_level.max(*level * DECAY);
+        }
+    }
+
+    /// Compute the amplitude of the specified frequency with Hann window,
+    /// a full scale sine wave at that frequency results in 1.0
+    fn magnitude_at(samples: &[f32], frequency: f32, sample_rate: f32) -> f32 {
+        let count = samples.len() as f32;
+        let step = 2.0 * PI * frequency / sample_rate;
+
+        let (mut re, mut im) = (0.0, 0.0);
+        for (n, sample) in samples.iter().enumerate() {
+            let window = 0.5 - 0.5 * (2.0 * PI * n as f32 / count).cos();
+            let value = sample * window;
+            re += value * (step * n as f32).cos();
+            im -= value * (step * n as f32).sin();
+        }
+
+        // The sum of Hann window is half of the sample count
+        2.0 * (re * re + im * im).sqrt() / (count / 2.0)
+    }
+}
@@ -2,4 +2,5 @@ pub mod log_builder;
 pub mod error;
 pub mod pixel_format;
 pub mod sample_format;
+pub mod screenshot;
 pub mod subtitle_format;
\ No newline at end of file
@@ -1,6 +1,6 @@
 use std::slice;
 
-use log::warn;
+use log::{error, warn};
 use rsmpeg::{ffi::{
     // AVPixelFormat_AV_PIX_FMT_ABGR, AVPixelFormat_AV_PIX_FMT_BAYER_BGGR8,
     // AVPixelFormat_AV_PIX_FMT_BAYER_GRBG16, AVPixelFormat_AV_PIX_FMT_BGR32,
@@ -25,6 +25,7 @@ use rsmpeg::{ffi::{
     // AVPixelFormat_AV_PIX_FMT_YUVA444P12LE, AVPixelFormat_AV_PIX_FMT_YUVA444P16LE,
     // AVPixelFormat_AV_PIX_FMT_YUVJ422P
 }, avutil::AVFrame};
+use rsmpeg::{ffi::SWS_BICUBIC, swscale::SwsContext};
 
 use crate::{media::decoder::VideoFrame, VIDEO_SUMMARY};
 
@@ -36,18 +37,11 @@ pub fn parse_video_frame(frame: &AVFrame) -> VideoFrame {
 
     match frame.format {
         AVPIXELFORMAT_AV_PIX_FMT_YUV420P => {
-            let y_size = width * height;
-            let u_size = y_size / 4; // width/2 * height/2
-            let v_size = y_size / 4; // width/2 * height/2
-
-            let y_ptr = frame.data[0];
-            let y = unsafe { slice::from_raw_parts(y_ptr, y_size) }.to_vec();
-
-            let u_ptr = frame.data[1];
-            let u = unsafe { slice::from_raw_parts(u_ptr, u_size) }.to_vec();
-
-            let v_ptr = frame.data[2];
-            let v = unsafe { slice::from_raw_parts(v_ptr, v_size) }.to_vec();
+            // Lines may be padded, e.g. the frames from filters, copy them line by line
+            let (chroma_width, chroma_height) = ((width + 1) / 2, (height + 1) / 2);
+            let y = copy_plane(frame, 0, width, height);
+            let u = copy_plane(frame, 1, chroma_width, chroma_height);
+            let v = copy_plane(frame, 2, chroma_width, chroma_height);
 
             VideoFrame {
                 format: frame.format,
@@ -125,3 +119,80 @@ pub fn parse_video_frame(frame: &AVFrame) -> VideoFrame {
         }
     }
 }
+
+/// Parse a still picture, such as the cover of audio media, to `VideoFrame`.
+/// The picture is converted to YUV420P format if necessary, its pts is always 0.
+pub fn parse_picture(frame: &AVFrame) -> Option<VideoFrame> {
+    let width = frame.width as usize;
+    let height = frame.height as usize;
+
+    let converted;
+    let frame = if frame.format == AVPIXELFORMAT_AV_PIX_FMT_YUV420P {
+        frame
+    } else {
+        converted = convert_frame(frame, AVPIXELFORMAT_AV_PIX_FMT_YUV420P)?;
+        &converted
+    };
+
+    let (chroma_width, chroma_height) = ((width + 1) / 2, (height + 1) / 2);
+    let y = copy_plane(frame, 0, width, height);
+    let u = copy_plane(frame, 1, chroma_width, chroma_height);
+    let v = copy_plane(frame, 2, chroma_width, chroma_height);
+
+    Some(VideoFrame {
+        format: AVPIXELFORMAT_AV_PIX_FMT_YUV420P,
+        data: [y, u, v, vec![], vec![], vec![], vec![], vec![]],
+        width,
+        height,
+        pts: 0,
+        pts_millis: 0,
+    })
+}
+
+/// Convert the frame to the specified pixel format with the same size
+pub fn convert_frame(frame: &AVFrame, format: i32) -> Option<AVFrame> {
+    scale_frame(frame, frame.width, frame.height, format)
+}
+
+/// Scale the frame to the specified size and convert it to the pixel format
+pub fn scale_frame(frame: &AVFrame, width: i32, height: i32, format: i32) -> Option<AVFrame> {
+    let mut sws_ctx = SwsContext::get_context(
+        frame.width,
+        frame.height,
+        frame.format,
+        width,
+        height,
+        format,
+        SWS_BICUBIC,
+    )?;
+
+    let mut dst = AVFrame::new();
+    dst.set_width(width);
+    dst.set_height(height);
+    dst.set_format(format);
+    if let Err(err) = dst.alloc_buffer() {
+        error!("failed to allocate frame buffer: {}", err);
+        return None;
+    }
+
+    if let Err(err) = sws_ctx.scale_frame(frame, 0, frame.height, &mut dst) {
+        error!("failed to convert pixel format: {}", err);
+        return None;
+    }
+    dst.set_pts(frame.pts);
+
+    Some(dst)
+}
+
+/// Copy the plane of frame without the padding at the end of each line
+fn copy_plane(frame: &AVFrame, plane: usize, width: usize, height: usize) -> Vec<u8> {
+    let linesize = frame.linesize[plane] as usize;
+    let data = unsafe { slice::from_raw_parts(frame.data[plane], linesize * height) };
+
+    let mut plane_data = Vec::with_capacity(width * height);
+    for line in data.chunks(linesize) {
+        plane_data.extend_from_slice(&line[..width]);
+    }
+
+    plane_data
+}
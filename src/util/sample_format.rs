@@ -1,8 +1,18 @@
-use std::{slice, sync::atomic::Ordering};
+use std::slice;
 
-use rsmpeg::avutil::AVFrame;
+use rsmpeg::{
+    avutil::AVFrame,
+    ffi::{
+        av_get_packed_sample_fmt, av_sample_fmt_is_planar,
+        AVSampleFormat_AV_SAMPLE_FMT_DBL as AV_SAMPLE_FMT_DBL,
+        AVSampleFormat_AV_SAMPLE_FMT_FLT as AV_SAMPLE_FMT_FLT,
+        AVSampleFormat_AV_SAMPLE_FMT_S16 as AV_SAMPLE_FMT_S16,
+        AVSampleFormat_AV_SAMPLE_FMT_S32 as AV_SAMPLE_FMT_S32,
+        AVSampleFormat_AV_SAMPLE_FMT_U8 as AV_SAMPLE_FMT_U8,
+    },
+};
 
-use crate::{media::decoder::AudioFrame, AUDIO_SUMMARY, VOLUME_BENCHMARK, VOLUME};
+use crate::{media::decoder::AudioFrame, AUDIO_SUMMARY};
 
 /// Parse ffmpeg audio frame to AudioFrame
 pub fn parse_audio_frame(frame: &mut AVFrame) -> AudioFrame {
@@ -15,13 +25,12 @@ pub fn parse_audio_frame(frame: &mut AVFrame) -> AudioFrame {
     let right_slice =
         unsafe { slice::from_raw_parts(frame.data[1] as *const f32, frame.nb_samples as usize) };
 
-    // Convert planar data to mono data
-    // Set volume to current frame
-    let volume = VOLUME.load(Ordering::Acquire) as f32 / VOLUME_BENCHMARK;
+    // Convert planar data to interleaved data,
+    // the volume is applied later while the data is output
     let mut data = vec![];
     for (left, right) in left_slice.iter().zip(right_slice.iter()) {
-        data.push((*left) * volume);
-        data.push((*right) * volume);
+        data.push(*left);
+        data.push(*right);
     }
 
     let audio_frame = AudioFrame {
@@ -36,3 +45,42 @@ pub fn parse_audio_frame(frame: &mut AVFrame) -> AudioFrame {
 
     audio_frame
 }
+
+/// Convert the samples of any common format to interleaved f32 samples in [-1, 1],
+/// all the channels are kept. `None` is returned if the format is not supported
+pub fn interleave_samples(frame: &AVFrame) -> Option<Vec<f32>> {
+    let channels = frame.channels.max(0) as usize;
+    let nb_samples = frame.nb_samples.max(0) as usize;
+    if channels == 0 || frame.extended_data.is_null() {
+        return None;
+    }
+
+    let planar = unsafe { av_sample_fmt_is_planar(frame.format) } != 0;
+    let format = unsafe { av_get_packed_sample_fmt(frame.format) };
+
+    let mut data = Vec::with_capacity(channels * nb_samples);
+    for n in 0..nb_samples {
+        for c in 0..channels {
+            // Planar data has one plane for each channel
+            let (plane, index) = if planar {
+                (c, n)
+            } else {
+                (0, n * channels + c)
+            };
+            let value = unsafe {
+                let ptr = *frame.extended_data.add(plane);
+                match format {
+                    AV_SAMPLE_FMT_FLT => *(ptr as *const f32).add(index),
+                    AV_SAMPLE_FMT_DBL => *(ptr as *const f64).add(index) as f32,
+                    AV_SAMPLE_FMT_S16 => *(ptr as *const i16).add(index) as f32 / 32768.0,
+                    AV_SAMPLE_FMT_S32 => *(ptr as *const i32).add(index) as f32 / 2147483648.0,
+                    AV_SAMPLE_FMT_U8 => (*ptr.add(index) as f32 - 128.0) / 128.0,
+                    _ => return None,
+                }
+            };
+            data.push(value);
+        }
+    }
+
+    Some(data)
+}
@@ -0,0 +1,222 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local};
+use rsmpeg::ffi::AVPixelFormat_AV_PIX_FMT_YUV420P as AVPIXELFORMAT_AV_PIX_FMT_YUV420P;
+use sdl2::{image::SaveSurface, pixels::PixelFormatEnum, surface::Surface};
+
+use super::error::{CustomError, SuperError};
+use crate::media::decoder::VideoFrame;
+
+/// The directory of screenshots if it's not configured
+pub const DEFAULT_DIR: &str = "screenshots";
+
+/// Save the frame as png with its native resolution.
+/// Subtitles are not burned in, since subtitle frames are not decoded yet,
+/// there's no option for it until they are rendered over the video
+/// # Arguments
+/// * `dir` - The directory to save to, it's created if not exists
+/// * `media_name` - Name of the playing media, it's a part of file name
+/// # Returns
+/// The path of saved file
+pub fn save(frame: &VideoFrame, dir: &Path, media_name: &str) -> Result<PathBuf, SuperError> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(file_name(media_name, frame.pts_millis, &Local::now()));
+    save_png(frame, &path)?;
+
+    Ok(path)
+}
+
+/// Save the frame to the specified path as png
+pub fn save_png(frame: &VideoFrame, path: &Path) -> Result<(), SuperError> {
+    let mut rgb = to_rgb24(frame)
+        .ok_or_else(|| CustomError::new(format!("unsupported pixel format: {}", frame.format)))?;
+    let (width, height) = (frame.width as u32, frame.height as u32);
+    let surface = Surface::from_data(&mut rgb, width, height, width * 3, PixelFormatEnum::RGB24)?;
+    surface.save(path)?;
+
+    Ok(())
+}
+
+/// File name of screenshot: "{media name}_{pts}_{local time}.png",
+/// e.g. "movie_01-02-03.456_20230102-150405.png"
+pub fn file_name(media_name: &str, pts_millis: i64, time: &DateTime<Local>) -> String {
+    // Characters which are invalid in file names of some platforms are replaced
+    let name: String = media_name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let name = if name.trim().is_empty() {
+        "screenshot"
+    } else {
+        name.trim()
+    };
+
+    let millis = pts_millis.max(0);
+    let (hours, minutes) = (millis / 3_600_000, millis / 60_000 % 60);
+    let (secs, millis) = (millis / 1000 % 60, millis % 1000);
+
+    format!(
+        "{}_{:02}-{:02}-{:02}.{:03}_{}.png",
+        name,
+        hours,
+        minutes,
+        secs,
+        millis,
+        time.format("%Y%m%d-%H%M%S")
+    )
+}
+
+/// Convert the frame to packed RGB24 data with BT.601 limited range,
+/// `None` is returned if the format is not supported
+pub fn to_rgb24(frame: &VideoFrame) -> Option<Vec<u8>> {
+    if frame.format != AVPIXELFORMAT_AV_PIX_FMT_YUV420P {
+        return None;
+    }
+
+    let (width, height) = (frame.width, frame.height);
+    let chroma_width = (width + 1) / 2;
+    let [y_plane, u_plane, v_plane, ..] = &frame.data;
+    if y_plane.len() < width * height || u_plane.len() < chroma_width * ((height + 1) / 2) {
+        return None;
+    }
+    if v_plane.len() < u_plane.len() {
+        return None;
+    }
+
+    let mut rgb = Vec::with_capacity(width * height * 3);
+    for row in 0..height {
+        for col in 0..width {
+            let chroma = row / 2 * chroma_width + col / 2;
+            let y = 1.164 * (y_plane[row * width + col] as f32 - 16.0);
+            let u = u_plane[chroma] as f32 - 128.0;
+            let v = v_plane[chroma] as f32 - 128.0;
+
+            let to_u8 = |value: f32| value.round().clamp(0.0, 255.0) as u8;
+            rgb.push(to_u8(y + 1.596 * v));
+            rgb.push(to_u8(y - 0.392 * u - 0.813 * v));
+            rgb.push(to_u8(y + 2.017 * u));
+        }
+    }
+
+    Some(rgb)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use sdl2::image::LoadSurface;
+
+    use super::*;
+
+    /// A 2x2 frame with neutral chroma, the pixels are black, white and two grays
+    fn known_frame() -> VideoFrame {
+        VideoFrame {
+            format: AVPIXELFORMAT_AV_PIX_FMT_YUV420P,
+            data: [
+                vec![16, 235, 81, 126],
+                vec![128],
+                vec![128],
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+            ],
+            width: 2,
+            height: 2,
+            pts: 0,
+            pts_millis: 3_723_456,
+        }
+    }
+
+    #[test]
+    fn test_to_rgb24() {
+        let rgb = to_rgb24(&known_frame()).unwrap();
+
+        assert_eq!(vec![0, 0, 0], rgb[0..3]);
+        assert_eq!(vec![255, 255, 255], rgb[3..6]);
+        // Chroma is shared by the 2x2 block, so the third one is gray as well
+        assert_eq!(vec![76, 76, 76], rgb[6..9]);
+        assert_eq!(vec![128, 128, 128], rgb[9..12]);
+    }
+
+    #[test]
+    fn test_to_rgb24_color() {
+        let mut frame = known_frame();
+        // Pure red in BT.601, which is rounded to 254
+        frame.data[0] = vec![81; 4];
+        frame.data[1] = vec![90];
+        frame.data[2] = vec![240];
+        let rgb = to_rgb24(&frame).unwrap();
+
+        assert_eq!(vec![254, 0, 0], rgb[0..3]);
+    }
+
+    #[test]
+    fn test_unsupported_frame() {
+        let mut frame = known_frame();
+        frame.data[0] = vec![];
+        assert_eq!(None, to_rgb24(&frame));
+
+        frame.format = -1;
+        assert_eq!(None, to_rgb24(&frame));
+    }
+
+    #[test]
+    fn test_file_name() {
+        let time = Local.with_ymd_and_hms(2023, 1, 2, 15, 4, 5).unwrap();
+
+        assert_eq!(
+            "movie_01-02-03.456_20230102-150405.png",
+            file_name("movie", 3_723_456, &time)
+        );
+        assert_eq!(
+            "a_b_c_00-00-00.000_20230102-150405.png",
+            file_name("a/b:c", -1, &time)
+        );
+        assert_eq!(
+            "screenshot_00-00-01.000_20230102-150405.png",
+            file_name(" ", 1000, &time)
+        );
+    }
+
+    #[test]
+    fn test_save_png() {
+        // Red on the left half and blue on the right half, so that the order of channels matters
+        let mut frame = known_frame();
+        frame.width = 4;
+        frame.data[0] = vec![81, 81, 41, 41, 81, 81, 41, 41];
+        frame.data[1] = vec![90, 240];
+        frame.data[2] = vec![240, 110];
+
+        let dir = std::env::temp_dir().join("ntplayer_screenshot_test");
+        let path = save(&frame, &dir, "known").unwrap();
+        assert_eq!(true, path.exists());
+
+        let surface = Surface::from_file(&path)
+            .unwrap()
+            .convert_format(PixelFormatEnum::RGB24)
+            .unwrap();
+        assert_eq!((4, 2), surface.size());
+        let pitch = surface.pitch() as usize;
+        surface.with_lock(|pixels| {
+            for row in 0..2 {
+                for col in 0..4 {
+                    let offset = row * pitch + col * 3;
+                    let pixel = &pixels[offset..offset + 3];
+                    let expected: [u8; 3] = if col < 2 { [254, 0, 0] } else { [0, 0, 255] };
+                    assert_eq!(expected, pixel, "pixel ({}, {})", col, row);
+                }
+            }
+        });
+
+        fs::remove_file(path).unwrap();
+    }
+}